@@ -0,0 +1,229 @@
+//! Solves every puzzle in `benches/corpus` and reports solve time and solver statistics.
+//!
+//! Run with `cargo bench --bench corpus [filter]`.
+//! Only the puzzles whose name contains `filter` are measured.
+//!
+//! - `dbchoco.txt`: `<name> <max depth> <url>` per line
+//! - `numlin.txt`, `slither.txt`: `<name> <url>` per line
+//!
+//! Every file must have at least one puzzle; the bench fails otherwise.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use puzz_sqr::common::{Grid, LP, P};
+use puzz_sqr::doublechoco::{self, Border};
+use puzz_sqr::{logic, numberlink, slitherlink, url};
+
+const MIN_TOTAL_TIME: Duration = Duration::from_millis(300);
+const MAX_RUNS: u32 = 10;
+
+struct Report {
+    genre: &'static str,
+    name: String,
+    solver: String,
+    time: Duration,
+    stats: String,
+}
+
+/// Runs `f` until `MIN_TOTAL_TIME` elapses (at most `MAX_RUNS` times) and returns the mean time
+/// together with the result of the last run.
+fn measure<T, F: FnMut() -> T>(mut f: F) -> (Duration, T) {
+    let start = Instant::now();
+    let mut runs = 0;
+    loop {
+        let ret = f();
+        runs += 1;
+        let elapsed = start.elapsed();
+        if elapsed >= MIN_TOTAL_TIME || runs >= MAX_RUNS {
+            return (elapsed / runs, ret);
+        }
+    }
+}
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("benches").join("corpus")
+}
+
+/// Returns the tokens of the non-comment lines of `file` whose name (the first token) contains
+/// `filter`. Panics if `file` has no puzzles at all.
+fn corpus_entries(file: &str, filter: &str) -> Vec<Vec<String>> {
+    let reader = BufReader::new(File::open(corpus_dir().join(file)).unwrap());
+    let mut ret = vec![];
+    let mut n_puzzles = 0;
+    for line in reader.lines() {
+        let line = line.unwrap();
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('%') {
+            continue;
+        }
+        let tokens = line
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        n_puzzles += 1;
+        if tokens[0].contains(filter) {
+            ret.push(tokens);
+        }
+    }
+    assert!(n_puzzles > 0, "no puzzles in {}", file);
+    ret
+}
+
+fn bench_dbchoco(filter: &str, reports: &mut Vec<Report>) {
+    for tokens in corpus_entries("dbchoco.txt", filter) {
+        let name = &tokens[0];
+        let max_depth = tokens[1].parse::<i32>().unwrap();
+        let (color, clue) = puzz_sqr::parse_url_dblchoco_internal(&tokens[2]).unwrap();
+
+        for depth in 0..(max_depth + 1) {
+            let (time, field) = measure(|| {
+                let mut field = doublechoco::Field::new(&color, &clue);
+                field.trial_and_error(depth);
+                field
+            });
+            let height = color.height();
+            let width = color.width();
+            let mut n_borders = 0;
+            let mut n_decided = 0;
+            for y in 0..(height * 2 - 1) {
+                for x in 0..(width * 2 - 1) {
+                    if y % 2 != x % 2 {
+                        n_borders += 1;
+                        if field.border(LP(y, x)) != Border::Undecided {
+                            n_decided += 1;
+                        }
+                    }
+                }
+            }
+            reports.push(Report {
+                genre: "dbchoco",
+                name: name.clone(),
+                solver: format!("depth {}", depth),
+                time,
+                stats: format!(
+                    "decided {:.3}{}",
+                    n_decided as f64 / n_borders as f64,
+                    if field.inconsistent() { " (inconsistent)" } else { "" }
+                ),
+            });
+        }
+    }
+}
+
+fn bench_numberlink(filter: &str, reports: &mut Vec<Report>) {
+    for tokens in corpus_entries("numlin.txt", filter) {
+        let name = tokens[0].clone();
+        let problem = url::decode::<url::Numberlink>(&tokens[1]).unwrap();
+
+        let (time, ans) = measure(|| {
+            numberlink::solve2(
+                &problem,
+                &numberlink::SolverOption {
+                    limit: Some(2),
+                    ..Default::default()
+                },
+            )
+        });
+        reports.push(Report {
+            genre: "numberlink",
+            name: name.clone(),
+            solver: String::from("solve2"),
+            time,
+            stats: format!("answers {} n_steps {}", ans.len(), ans.n_steps),
+        });
+
+        let field = to_logic_field(&problem);
+        let (time, ans) = measure(|| logic::solve_numberlink(&field));
+        reports.push(Report {
+            genre: "numberlink",
+            name,
+            solver: String::from("sat"),
+            time,
+            stats: format!("answers {}", if ans.is_some() { 1 } else { 0 }),
+        });
+    }
+}
+
+fn to_logic_field(problem: &Grid<numberlink::Clue>) -> Vec<Vec<usize>> {
+    let mut ret = vec![vec![0; problem.width() as usize]; problem.height() as usize];
+    for y in 0..problem.height() {
+        for x in 0..problem.width() {
+            ret[y as usize][x as usize] = problem[P(y, x)].0.max(0) as usize;
+        }
+    }
+    ret
+}
+
+fn bench_slitherlink(filter: &str, reports: &mut Vec<Report>) {
+    let (time, dic) = measure(slitherlink::Dictionary::complete);
+    reports.push(Report {
+        genre: "slitherlink",
+        name: String::from("-"),
+        solver: String::from("dictionary"),
+        time,
+        stats: String::new(),
+    });
+    let (time, _) = measure(|| slitherlink::Dictionary::load().unwrap());
+    reports.push(Report {
+        genre: "slitherlink",
+        name: String::from("-"),
+        solver: String::from("dictionary load"),
+        time,
+        stats: String::new(),
+    });
+
+    for tokens in corpus_entries("slither.txt", filter) {
+        let name = tokens[0].clone();
+        let problem = slitherlink::parse_url(&tokens[1]).unwrap();
+
+        let (time, field) = measure(|| {
+            let mut field = slitherlink::Field::new(&problem, &dic);
+            field.check_all_cell();
+            field
+        });
+        let height = problem.height();
+        let width = problem.width();
+        let n_edges = height * (width + 1) + (height + 1) * width;
+        reports.push(Report {
+            genre: "slitherlink",
+            name,
+            solver: String::from("check_all_cell"),
+            time,
+            stats: format!(
+                "decided {:.3}{}",
+                field.num_decided_edges() as f64 / n_edges as f64,
+                if field.inconsistent() { " (inconsistent)" } else { "" }
+            ),
+        });
+    }
+}
+
+fn main() {
+    let filter = std::env::args()
+        .skip(1)
+        .find(|arg| !arg.starts_with('-'))
+        .unwrap_or_default();
+
+    let mut reports = vec![];
+    bench_dbchoco(&filter, &mut reports);
+    bench_numberlink(&filter, &mut reports);
+    bench_slitherlink(&filter, &mut reports);
+
+    println!(
+        "{:<12} {:<24} {:<16} {:>12}  stats",
+        "genre", "name", "solver", "time [ms]"
+    );
+    for r in &reports {
+        println!(
+            "{:<12} {:<24} {:<16} {:>12.3}  {}",
+            r.genre,
+            r.name,
+            r.solver,
+            r.time.as_secs_f64() * 1000.0,
+            r.stats
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! Precomputes the Slitherlink dictionary so that it need not be enumerated at runtime.
+//! See `slitherlink::Dictionary::load`.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[allow(dead_code)]
+#[path = "src/slitherlink/dictionary_table.rs"]
+mod dictionary_table;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/slitherlink/dictionary_table.rs");
+
+    let table = dictionary_table::complete_table();
+    let bytes = table
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect::<Vec<u8>>();
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("slitherlink_dictionary.bin");
+    fs::write(out, bytes).unwrap();
+}
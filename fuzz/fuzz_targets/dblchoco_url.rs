@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use puzz_sqr::{encode_url_dblchoco, parse_url_dblchoco_internal};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(url) = std::str::from_utf8(data) {
+        if let Some((color, clue)) = parse_url_dblchoco_internal(url) {
+            let encoded = encode_url_dblchoco(&color, &clue);
+            let (color2, clue2) = parse_url_dblchoco_internal(&encoded).unwrap();
+            assert_eq!(color, color2);
+            assert_eq!(clue, clue2);
+        }
+    }
+});
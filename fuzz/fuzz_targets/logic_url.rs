@@ -0,0 +1,22 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use puzz_sqr::common::{Grid, P};
+use puzz_sqr::{encode_url_numlin, logic, numberlink};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(url) = std::str::from_utf8(data) {
+        if let Some(field) = logic::parse_url(url.to_string()) {
+            let height = field.len() as i32;
+            let width = field[0].len() as i32;
+            let mut clue = Grid::new(height, width, numberlink::NO_CLUE);
+            for y in 0..height {
+                for x in 0..width {
+                    clue[P(y, x)] = numberlink::Clue(field[y as usize][x as usize] as i32);
+                }
+            }
+            let field2 = logic::parse_url(encode_url_numlin(&clue)).unwrap();
+            assert_eq!(field, field2);
+        }
+    }
+});
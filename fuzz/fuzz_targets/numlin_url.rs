@@ -0,0 +1,14 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use puzz_sqr::{encode_url_numlin, parse_url_numlin_internal};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(url) = std::str::from_utf8(data) {
+        if let Some(clue) = parse_url_numlin_internal(url.to_string()) {
+            let encoded = encode_url_numlin(&clue);
+            let clue2 = parse_url_numlin_internal(encoded).unwrap();
+            assert_eq!(clue, clue2);
+        }
+    }
+});
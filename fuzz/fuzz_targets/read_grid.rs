@@ -0,0 +1,26 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use puzz_sqr::io::{read_grid, write_grid, ReadError};
+use puzz_sqr::{numberlink, slitherlink};
+
+fuzz_target!(|data: &[u8]| {
+    let converter = |s: &str| s.parse::<i32>().map_err(|_| ReadError::InvalidValue);
+    if let Ok(grid) = read_grid(&mut &data[..], converter, 0) {
+        let mut buf = vec![];
+        write_grid(&mut buf, &grid, |n| n.to_string()).unwrap();
+        assert_eq!(read_grid(&mut &buf[..], converter, 0).unwrap(), grid);
+    }
+
+    if let Ok(problem) = numberlink::read_penciloid_problem(&mut &data[..]) {
+        let mut buf = vec![];
+        numberlink::write_penciloid_problem(&mut buf, &problem).unwrap();
+        assert_eq!(numberlink::read_penciloid_problem(&mut &buf[..]).unwrap(), problem);
+    }
+
+    if let Ok(problem) = slitherlink::read_penciloid_problem(&mut &data[..]) {
+        let mut buf = vec![];
+        slitherlink::write_penciloid_problem(&mut buf, &problem).unwrap();
+        assert_eq!(slitherlink::read_penciloid_problem(&mut &buf[..]).unwrap(), problem);
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use puzz_sqr::slitherlink::{encode_url_with_givens, parse_url_with_givens};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(url) = std::str::from_utf8(data) {
+        if let Some((problem, givens)) = parse_url_with_givens(url) {
+            let encoded = encode_url_with_givens(&problem, &givens);
+            assert_eq!(parse_url_with_givens(&encoded).unwrap(), (problem, givens));
+        }
+    }
+});
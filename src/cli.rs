@@ -0,0 +1,725 @@
+//! Subcommands of the `main` binary.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::time::Duration;
+
+use getopts::Options;
+use rand::Rng;
+use serde::Serialize;
+
+use crate::common::{Grid, Symmetry, LP};
+use crate::grid_loop::Edge;
+use crate::io::{is_pzprv3, ReadError};
+use crate::pack::{self, Genre, PackEntry};
+use crate::{doublechoco, numberlink, render, slitherlink, Seed};
+
+const USAGE: &str = "usage: main <subcommand> [options]
+
+subcommands:
+    slither-gen    generate a Slitherlink problem
+    numlin-gen     generate a Numberlink problem of a given difficulty
+    numlin-solve   solve a Numberlink problem
+    render         draw a problem or its answer as SVG or PNG
+    minimize       remove redundant clues from a problem
+    generate-pack  generate distinct problems with their answers into a directory";
+
+/// Runs the subcommand specified by `args[0]`.
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args.first().map(|s| s.as_str()) {
+        Some("slither-gen") => slither_gen(&args[1..]),
+        Some("numlin-gen") => numlin_gen(&args[1..]),
+        Some("numlin-solve") => numlin_solve(&args[1..]),
+        Some("render") => render(&args[1..]),
+        Some("minimize") => minimize(&args[1..]),
+        Some("generate-pack") => generate_pack(&args[1..]),
+        _ => Err(String::from(USAGE)),
+    }
+}
+
+fn parse_opt<T: std::str::FromStr>(
+    matches: &getopts::Matches,
+    name: &str,
+    default: T,
+) -> Result<T, String> {
+    Ok(parse_opt_maybe(matches, name)?.unwrap_or(default))
+}
+
+/// Same as `parse_opt`, but returns `None` if the option is not given.
+fn parse_opt_maybe<T: std::str::FromStr>(
+    matches: &getopts::Matches,
+    name: &str,
+) -> Result<Option<T>, String> {
+    match matches.opt_str(name) {
+        Some(s) => s
+            .parse::<T>()
+            .map(Some)
+            .map_err(|_| format!("invalid value for --{}: {}", name, s)),
+        None => Ok(None),
+    }
+}
+
+/// Returns the size given by `--height` and `--width`, which are 10 by default.
+fn parse_size(matches: &getopts::Matches) -> Result<(i32, i32), String> {
+    let height = parse_opt(matches, "height", 10)?;
+    let width = parse_opt(matches, "width", 10)?;
+    if height <= 0 || width <= 0 {
+        return Err(String::from("the size of the problem must be positive"));
+    }
+    Ok((height, width))
+}
+
+fn parse_symmetry(matches: &getopts::Matches) -> Result<Symmetry, String> {
+    let names = matches.opt_str("symmetry").unwrap_or_default();
+    Symmetry::from_names(&names).ok_or_else(|| format!("invalid symmetry: {}", names))
+}
+
+/// Returns the seed given by `--seed`, or a new random seed for `genre` and `size`.
+/// The size of the given seed must be `size` if `size_given` is `true`.
+fn parse_seed(
+    matches: &getopts::Matches,
+    genre: &str,
+    size: (i32, i32),
+    size_given: bool,
+) -> Result<Seed, String> {
+    let seed = match matches.opt_str("seed") {
+        Some(s) => s.parse::<Seed>()?,
+        None => return Ok(Seed::new(genre, size.0, size.1, rand::thread_rng().gen())),
+    };
+    if seed.genre != genre {
+        return Err(format!("the seed is not for {}", genre));
+    }
+    if size_given && (seed.height, seed.width) != size {
+        return Err(format!(
+            "the seed is for a problem of size {}x{}",
+            seed.height, seed.width
+        ));
+    }
+    Ok(seed)
+}
+
+fn slither_gen(args: &[String]) -> Result<(), String> {
+    let mut opts = Options::new();
+    opts.optopt("", "height", "height of the problem (default: 10)", "H");
+    opts.optopt("", "width", "width of the problem (default: 10)", "W");
+    opts.optopt(
+        "",
+        "clues",
+        "number of clues (default: 40% of the cells)",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "symmetry",
+        "comma-separated list of dyad, tetrad, horizontal and vertical",
+        "LIST",
+    );
+    opts.optopt(
+        "",
+        "seed",
+        "reproduce the problem of a seed such as XX-HxW-HEX",
+        "SEED",
+    );
+    opts.optopt(
+        "",
+        "trials",
+        "maximum number of clue placements tried (default: 100)",
+        "N",
+    );
+
+    let matches = opts.parse(args).map_err(|e| e.to_string())?;
+    let (height, width) = parse_size(&matches)?;
+    let size_given = matches.opt_present("height") || matches.opt_present("width");
+    let seed = parse_seed(&matches, "sl", (height, width), size_given)?;
+    let (height, width) = (seed.height, seed.width);
+    let num_clues = parse_opt(&matches, "clues", height * width * 2 / 5)?;
+    let symmetry = parse_symmetry(&matches)?;
+    let max_trials = parse_opt(&matches, "trials", 100)?;
+
+    let mut rng = seed.rng();
+    let dic = slitherlink::Dictionary::shared();
+    let problem = slitherlink::generate_problem(
+        height, width, num_clues, symmetry, dic, max_trials, &mut rng,
+    )
+    .ok_or_else(|| String::from("no problem was generated"))?;
+
+    println!("% seed {}", seed);
+    println!("% {}", slitherlink::encode_url(&problem));
+    slitherlink::write_penciloid_problem(&mut io::stdout(), &problem).map_err(|e| e.to_string())
+}
+
+fn numlin_gen(args: &[String]) -> Result<(), String> {
+    let mut opts = Options::new();
+    opts.optopt("", "height", "height of the problem (default: 10)", "H");
+    opts.optopt("", "width", "width of the problem (default: 10)", "W");
+    opts.optopt(
+        "",
+        "chain-threshold",
+        "minimum length of lines (default: 3)",
+        "N",
+    );
+    opts.optflag(
+        "",
+        "forbid-adjacent-clue",
+        "forbid numbers next to each other",
+    );
+    opts.optopt(
+        "",
+        "symmetry",
+        "comma-separated list of dyad, tetrad, horizontal and vertical",
+        "LIST",
+    );
+    opts.optopt("", "max-clues", "maximum number of pairs of numbers", "N");
+    opts.optopt("", "min-clues", "minimum number of pairs of numbers", "N");
+    opts.optflag(
+        "",
+        "prioritized-extension",
+        "extend lines at the most constrained places first",
+    );
+    opts.optopt(
+        "",
+        "empty-width",
+        "width of the margin without numbers (default: 0)",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "corner",
+        "put numbers at distances LO to HI from the corners",
+        "LO,HI",
+    );
+    opts.optopt(
+        "",
+        "min-steps",
+        "minimum search steps of the solver (default: 0)",
+        "N",
+    );
+    opts.optopt("", "max-steps", "maximum search steps of the solver", "N");
+    opts.optopt("", "time-limit", "give up after SEC seconds", "SEC");
+    opts.optopt(
+        "",
+        "seed",
+        "reproduce the problem of a seed such as XX-HxW-HEX",
+        "SEED",
+    );
+
+    let matches = opts.parse(args).map_err(|e| e.to_string())?;
+    let (height, width) = parse_size(&matches)?;
+    let size_given = matches.opt_present("height") || matches.opt_present("width");
+    let seed = parse_seed(&matches, "nl", (height, width), size_given)?;
+    let (height, width) = (seed.height, seed.width);
+    let symmetry = parse_symmetry(&matches)?;
+    if !numberlink::is_symmetry_feasible(height, width, symmetry) {
+        return Err(String::from(
+            "90-degree symmetry is impossible for squares of odd size",
+        ));
+    }
+    let corner = match matches.opt_str("corner") {
+        Some(corner) => {
+            let range = corner
+                .split(',')
+                .map(|s| s.parse::<i32>())
+                .collect::<Result<Vec<_>, _>>();
+            match range.as_ref().map(|r| r.as_slice()) {
+                Ok(&[lo, hi]) if 0 <= lo && lo <= hi => Some((lo, hi)),
+                _ => return Err(format!("invalid value for --corner: {}", corner)),
+            }
+        }
+        None => None,
+    };
+    let time_limit = parse_opt_maybe(&matches, "time-limit")?.map(Duration::from_secs_f64);
+
+    let mut rng = seed.rng();
+    let endpoint_constraint = numberlink::generate_endpoint_constraint(
+        height,
+        width,
+        parse_opt(&matches, "empty-width", 0)?,
+        corner,
+        symmetry,
+        &mut rng,
+    );
+    let opt = numberlink::GeneratorOption {
+        chain_threshold: parse_opt(&matches, "chain-threshold", 3)?,
+        endpoint_constraint: Some(&endpoint_constraint),
+        forbid_adjacent_clue: matches.opt_present("forbid-adjacent-clue"),
+        symmetry,
+        clue_limit: parse_opt_maybe(&matches, "max-clues")?,
+        prioritized_extension: matches.opt_present("prioritized-extension"),
+    };
+    let difficulty = numberlink::DifficultyOption {
+        min_steps: parse_opt(&matches, "min-steps", 0)?,
+        max_steps: parse_opt_maybe(&matches, "max-steps")?,
+        min_clues: parse_opt_maybe(&matches, "min-clues")?,
+        time_limit,
+    };
+    let mut generator = numberlink::PlacementGenerator::new(height, width);
+    let (problem, n_steps) = generator
+        .generate_with_difficulty(&opt, &difficulty, &mut rng)
+        .ok_or_else(|| String::from("no problem was generated in time"))?;
+
+    println!("% seed {}", seed);
+    println!("% {}", crate::encode_url_numlin(&problem));
+    println!("% steps {}", n_steps);
+    numberlink::write_penciloid_problem(&mut io::stdout(), &problem).map_err(|e| e.to_string())
+}
+
+/// Opens `path`, or stdin if `path` is `None`.
+fn open_input(path: Option<&String>) -> Result<Box<dyn io::BufRead>, String> {
+    match path {
+        Some(path) => {
+            let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+            Ok(Box::new(BufReader::new(file)))
+        }
+        None => Ok(Box::new(BufReader::new(io::stdin()))),
+    }
+}
+
+/// Reads a problem from `path`, or stdin if `path` is `None`, with `pzprv3` if the input is in
+/// the pzprv3 format and with `penciloid` otherwise.
+fn read_problem<T, F, G>(path: Option<&String>, penciloid: F, pzprv3: G) -> Result<T, String>
+where
+    F: FnOnce(&mut Box<dyn io::BufRead>) -> Result<T, ReadError>,
+    G: FnOnce(&mut Box<dyn io::BufRead>) -> Result<T, ReadError>,
+{
+    let mut input = open_input(path)?;
+    let problem = if is_pzprv3(&mut input).map_err(|e| e.to_string())? {
+        pzprv3(&mut input)
+    } else {
+        penciloid(&mut input)
+    };
+    problem.map_err(|e| e.to_string())
+}
+
+/// Creates the file `path` and writes its contents with `write`, if `path` is given.
+fn write_file<F>(path: Option<String>, write: F) -> Result<(), String>
+where
+    F: FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+{
+    if let Some(path) = path {
+        let file = File::create(&path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)
+            .and_then(|_| writer.flush())
+            .map_err(|e| format!("{}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+fn numlin_solve(args: &[String]) -> Result<(), String> {
+    let mut opts = Options::new();
+    opts.optopt("", "url", "read the problem from a puzz.link URL", "URL");
+    opts.optopt("", "limit", "maximum number of answers (default: 2)", "N");
+    opts.optflag("", "every-cell", "require every cell to be used");
+    opts.optopt("", "svg", "write the first answer as an SVG image", "FILE");
+    opts.optopt(
+        "",
+        "pzprv3",
+        "write the problem and the first answer as a pzprv3 file",
+        "FILE",
+    );
+
+    let matches = opts.parse(args).map_err(|e| e.to_string())?;
+    let problem = match matches.opt_str("url") {
+        Some(url) => {
+            crate::parse_url_numlin_internal(url).ok_or_else(|| String::from("invalid URL"))?
+        }
+        None => read_problem(
+            matches.free.first(),
+            numberlink::read_penciloid_problem,
+            numberlink::read_pzprv3_problem,
+        )?,
+    };
+    let opt = numberlink::SolverOption {
+        limit: Some(parse_opt(&matches, "limit", 2)?),
+        every_cell_used: matches.opt_present("every-cell"),
+        terminate_on_not_fully_filled: false,
+    };
+
+    let ans = numberlink::solve2(&problem, &opt);
+    println!("% {} answer(s)", ans.len());
+    for answer in &ans.answers {
+        println!();
+        print!("{}", answer.to_ascii(&problem));
+    }
+
+    if let Some(path) = matches.opt_str("svg") {
+        let answer = ans
+            .answers
+            .first()
+            .ok_or_else(|| String::from("no answer"))?;
+        std::fs::write(&path, answer.to_svg(&problem)).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    write_file(matches.opt_str("pzprv3"), |writer| {
+        numberlink::write_pzprv3(writer, &problem, ans.answers.first())
+    })
+}
+
+fn render(args: &[String]) -> Result<(), String> {
+    let mut opts = Options::new();
+    opts.optopt(
+        "",
+        "genre",
+        "dblchoco (or dbchoco), slither or numlin",
+        "GENRE",
+    );
+    opts.optopt("", "url", "read the problem from a puzz.link URL", "URL");
+    opts.optflag("", "solve", "draw the answer together with the problem");
+    opts.optopt(
+        "",
+        "depth",
+        "depth of trial and error for Double Choco (default: 2)",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "out",
+        "output file; PNG if it ends with .png, SVG otherwise",
+        "FILE",
+    );
+    opts.optopt(
+        "",
+        "pzprv3",
+        "also write the problem and the drawn answer as a pzprv3 file",
+        "FILE",
+    );
+
+    let matches = opts.parse(args).map_err(|e| e.to_string())?;
+    let url = matches.opt_str("url");
+    let input = matches.free.first();
+    let solve = matches.opt_present("solve");
+    let pzprv3 = matches.opt_str("pzprv3");
+    let invalid_url = || String::from("invalid URL");
+
+    let picture = match matches.opt_str("genre").as_deref() {
+        Some("dblchoco") | Some("dbchoco") => {
+            let (color, clue) = match url {
+                Some(url) => crate::parse_url_dblchoco_internal(&url).ok_or_else(invalid_url)?,
+                None => doublechoco::read_pzprv3_problem(&mut open_input(input)?)
+                    .map_err(|e| e.to_string())?,
+            };
+            let answer = if solve {
+                let mut field = doublechoco::Field::new(&color, &clue);
+                field.trial_and_error(parse_opt(&matches, "depth", 2)?);
+                Some(field)
+            } else {
+                None
+            };
+            write_file(pzprv3, |writer| {
+                doublechoco::write_pzprv3(writer, &color, &clue, answer.as_ref())
+            })?;
+            render::doublechoco(&color, &clue, answer.as_ref(), true)
+        }
+        Some("slither") => {
+            let (clue, givens) = match url {
+                Some(url) => slitherlink::parse_url_with_givens(&url).ok_or_else(invalid_url)?,
+                None => read_problem(
+                    input,
+                    slitherlink::read_penciloid_problem_with_givens,
+                    slitherlink::read_pzprv3_problem,
+                )?,
+            };
+            let dic = slitherlink::Dictionary::shared();
+            let mut field = slitherlink::Field::with_givens(&clue, &givens, dic);
+            if solve {
+                field.check_all_cell();
+            }
+            write_file(pzprv3, |writer| {
+                let mut edges =
+                    Grid::new(2 * clue.height() + 1, 2 * clue.width() + 1, Edge::Undecided);
+                for y in 0..edges.height() {
+                    for x in 0..edges.width() {
+                        if y % 2 != x % 2 {
+                            edges[LP(y, x)] = field.get_edge(LP(y, x));
+                        }
+                    }
+                }
+                slitherlink::write_pzprv3(writer, &clue, &edges)
+            })?;
+            render::slitherlink(&clue, Some(&field))
+        }
+        Some("numlin") => {
+            let clue = match url {
+                Some(url) => crate::parse_url_numlin_internal(url).ok_or_else(invalid_url)?,
+                None => read_problem(
+                    input,
+                    numberlink::read_penciloid_problem,
+                    numberlink::read_pzprv3_problem,
+                )?,
+            };
+            let answer = if solve {
+                let opt = numberlink::SolverOption {
+                    limit: Some(1),
+                    ..Default::default()
+                };
+                let ans = numberlink::solve2(&clue, &opt);
+                Some(
+                    ans.answers
+                        .into_iter()
+                        .next()
+                        .ok_or_else(|| String::from("no answer"))?,
+                )
+            } else {
+                None
+            };
+            write_file(pzprv3, |writer| {
+                numberlink::write_pzprv3(writer, &clue, answer.as_ref())
+            })?;
+            render::numberlink(&clue, answer.as_ref())
+        }
+        Some(genre) => return Err(format!("unknown genre: {}", genre)),
+        None => return Err(String::from("--genre is required")),
+    };
+
+    match matches.opt_str("out") {
+        Some(path) => {
+            let data = if path.ends_with(".png") {
+                encode_png(&picture)?
+            } else {
+                picture.to_svg().into_bytes()
+            };
+            std::fs::write(&path, data).map_err(|e| format!("{}: {}", path, e))
+        }
+        None => {
+            print!("{}", picture.to_svg());
+            Ok(())
+        }
+    }
+}
+
+fn minimize(args: &[String]) -> Result<(), String> {
+    let mut opts = Options::new();
+    opts.optopt(
+        "",
+        "genre",
+        "dblchoco (or dbchoco), slither or numlin",
+        "GENRE",
+    );
+    opts.optopt("", "url", "read the problem from a puzz.link URL", "URL");
+    opts.optopt(
+        "",
+        "symmetry",
+        "comma-separated list of dyad, tetrad, horizontal and vertical",
+        "LIST",
+    );
+    opts.optopt(
+        "",
+        "seed",
+        "reproduce the result of a seed such as XX-HxW-HEX",
+        "SEED",
+    );
+    opts.optflag(
+        "",
+        "every-cell",
+        "count only the Numberlink answers using every cell",
+    );
+
+    let matches = opts.parse(args).map_err(|e| e.to_string())?;
+    let url = matches.opt_str("url");
+    let input = matches.free.first();
+    let symmetry = parse_symmetry(&matches)?;
+    let seed_for =
+        |genre: &str, height: i32, width: i32| parse_seed(&matches, genre, (height, width), true);
+    let invalid_url = || String::from("invalid URL");
+    let not_unique = || String::from("the problem does not have a unique answer");
+
+    let (minimized, seed) = match matches.opt_str("genre").as_deref() {
+        Some("dblchoco") | Some("dbchoco") => {
+            let (color, clue) = match url {
+                Some(url) => crate::parse_url_dblchoco_internal(&url).ok_or_else(invalid_url)?,
+                None => doublechoco::read_pzprv3_problem(&mut open_input(input)?)
+                    .map_err(|e| e.to_string())?,
+            };
+            let seed = seed_for("dc", clue.height(), clue.width())?;
+            let clue = doublechoco::minimize(&color, &clue, symmetry, &mut seed.rng())
+                .ok_or_else(not_unique)?;
+            (crate::encode_url_dblchoco(&color, &clue), seed)
+        }
+        Some("slither") => {
+            let clue = match url {
+                Some(url) => slitherlink::parse_url(&url).ok_or_else(invalid_url)?,
+                None => read_problem(input, slitherlink::read_penciloid_problem, |reader| {
+                    slitherlink::read_pzprv3_problem(reader).map(|(clue, _)| clue)
+                })?,
+            };
+            let seed = seed_for("sl", clue.height(), clue.width())?;
+            let dic = slitherlink::Dictionary::shared();
+            let clue = slitherlink::minimize(&clue, symmetry, dic, &mut seed.rng())
+                .ok_or_else(not_unique)?;
+            (slitherlink::encode_url(&clue), seed)
+        }
+        Some("numlin") => {
+            let clue = match url {
+                Some(url) => crate::parse_url_numlin_internal(url).ok_or_else(invalid_url)?,
+                None => read_problem(
+                    input,
+                    numberlink::read_penciloid_problem,
+                    numberlink::read_pzprv3_problem,
+                )?,
+            };
+            let seed = seed_for("nl", clue.height(), clue.width())?;
+            let every_cell_used = matches.opt_present("every-cell");
+            let clue = numberlink::minimize(&clue, symmetry, every_cell_used, &mut seed.rng())
+                .ok_or_else(not_unique)?;
+            (crate::encode_url_numlin(&clue), seed)
+        }
+        Some(genre) => return Err(format!("unknown genre: {}", genre)),
+        None => return Err(String::from("--genre is required")),
+    };
+
+    println!("% seed {}", seed);
+    println!("{}", minimized);
+    Ok(())
+}
+
+/// The contents of `index.json` written by `generate-pack`.
+#[derive(Serialize)]
+struct PackIndex<'a> {
+    genre: &'static str,
+    height: i32,
+    width: i32,
+    seed: String,
+    puzzles: &'a [PackEntry],
+}
+
+fn generate_pack(args: &[String]) -> Result<(), String> {
+    let mut opts = Options::new();
+    opts.optopt("", "genre", "numlin, slither or endview", "GENRE");
+    opts.optopt("", "count", "number of problems (default: 10)", "N");
+    opts.optopt("", "size", "size of the problems (default: 10x10)", "HxW");
+    opts.optopt(
+        "",
+        "letters",
+        "number of letters of Endview problems (default: 3)",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "symmetry",
+        "comma-separated list of dyad, tetrad, horizontal and vertical (not for Endview)",
+        "LIST",
+    );
+    opts.optopt(
+        "",
+        "chain-threshold",
+        "minimum length of lines of Numberlink problems (default: 3)",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "clues",
+        "number of clues of Slitherlink problems (default: 40% of the cells)",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "placement-trials",
+        "maximum number of clue placements tried for each Slitherlink problem (default: 100)",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "trials",
+        "maximum number of generated problems including duplicates (default: 100 per problem)",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "seed",
+        "reproduce the problem of a seed such as XX-HxW-HEX",
+        "SEED",
+    );
+    opts.optopt(
+        "",
+        "out",
+        "directory to write index.json into (created if missing)",
+        "DIR",
+    );
+
+    let matches = opts.parse(args).map_err(|e| e.to_string())?;
+    let genre = match matches.opt_str("genre") {
+        Some(name) => Genre::from_name(&name).ok_or_else(|| format!("unknown genre: {}", name))?,
+        None => return Err(String::from("--genre is required")),
+    };
+    let out = matches
+        .opt_str("out")
+        .ok_or_else(|| String::from("--out is required"))?;
+    let size = matches
+        .opt_str("size")
+        .unwrap_or_else(|| String::from("10x10"));
+    let (height, width) = match size.split('x').collect::<Vec<_>>().as_slice() {
+        &[h, w] => match (h.parse::<i32>(), w.parse::<i32>()) {
+            (Ok(h), Ok(w)) if h > 0 && w > 0 => (h, w),
+            _ => return Err(format!("invalid value for --size: {}", size)),
+        },
+        _ => return Err(format!("invalid value for --size: {}", size)),
+    };
+    let size_given = matches.opt_present("size");
+    let seed = parse_seed(&matches, genre.seed_genre(), (height, width), size_given)?;
+    let (height, width) = (seed.height, seed.width);
+    if genre == Genre::Endview && height != width {
+        return Err(String::from("Endview problems must be square"));
+    }
+    let count = parse_opt(&matches, "count", 10)?;
+    let n_alpha = parse_opt(&matches, "letters", 3)?;
+    if genre == Genre::Endview && (n_alpha < 2 || n_alpha > height) {
+        return Err(format!("invalid value for --letters: {}", n_alpha));
+    }
+    let symmetry = parse_symmetry(&matches)?;
+    if genre == Genre::Numberlink && !numberlink::is_symmetry_feasible(height, width, symmetry) {
+        return Err(String::from(
+            "90-degree symmetry is impossible for squares of odd size",
+        ));
+    }
+
+    let mut rng = seed.rng();
+    let opt = pack::PackOption {
+        genre,
+        height,
+        width,
+        count,
+        n_alpha,
+        symmetry,
+        chain_threshold: parse_opt(&matches, "chain-threshold", 3)?,
+        num_clues: parse_opt(&matches, "clues", height * width * 2 / 5)?,
+        placement_trials: parse_opt(&matches, "placement-trials", 100)?,
+        max_trials: parse_opt(&matches, "trials", count * 100)?,
+    };
+    let puzzles = pack::generate_pack(&opt, &mut rng);
+
+    let index = PackIndex {
+        genre: genre.name(),
+        height,
+        width,
+        seed: seed.to_string(),
+        puzzles: &puzzles,
+    };
+    std::fs::create_dir_all(&out).map_err(|e| format!("{}: {}", out, e))?;
+    let path = std::path::Path::new(&out).join("index.json");
+    let json = serde_json::to_string_pretty(&index).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    println!("% seed {}", seed);
+    println!(
+        "% {} problem(s) written to {}",
+        puzzles.len(),
+        path.display()
+    );
+    if puzzles.len() < count {
+        return Err(format!(
+            "only {} distinct problems were generated out of {}",
+            puzzles.len(),
+            count
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "png")]
+fn encode_png(picture: &render::Picture) -> Result<Vec<u8>, String> {
+    Ok(picture.to_png())
+}
+
+#[cfg(not(feature = "png"))]
+fn encode_png(_: &render::Picture) -> Result<Vec<u8>, String> {
+    Err(String::from("PNG output requires the `png` feature"))
+}
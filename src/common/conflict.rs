@@ -0,0 +1,62 @@
+use super::P;
+
+/// The reason why a solver found its field inconsistent.
+///
+/// Solvers record the first contradiction they encounter; the ones found afterwards are mostly
+/// consequences of it. `T` is a decision of the solver, such as a border and its state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Contradiction<T> {
+    /// A short description of the violated rule.
+    pub rule: &'static str,
+    /// The cells at which the violation was detected.
+    pub cells: Vec<P>,
+    /// The decisions which led to the violation. Each solver documents which of its decisions
+    /// are recorded.
+    pub decisions: Vec<T>,
+}
+
+/// Shrinks `items`, which are conflicting as a whole, to a minimal subset which still conflicts.
+///
+/// `is_conflicting` tells whether a subset of `items` conflicts; it must be monotone, that is,
+/// a superset of a conflicting subset must be conflicting as well. The result is minimal in the
+/// sense that removing any single item resolves the conflict. An empty result means that the
+/// conflict does not depend on `items` at all.
+pub fn minimize_conflict<T: Clone, F: FnMut(&[T]) -> bool>(
+    items: &[T],
+    mut is_conflicting: F,
+) -> Vec<T> {
+    let mut kept = items.to_vec();
+    let mut i = 0;
+    while i < kept.len() {
+        let mut trial = kept.clone();
+        trial.remove(i);
+        if is_conflicting(&trial) {
+            kept = trial;
+        } else {
+            i += 1;
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimize_conflict() {
+        // Conflicting iff both 2 and 5 are contained
+        let items = [1, 2, 3, 4, 5, 6];
+        let ret = minimize_conflict(&items, |s| s.contains(&2) && s.contains(&5));
+        assert_eq!(ret, vec![2, 5]);
+
+        // Conflicting iff the sum is at least 10
+        let ret = minimize_conflict(&items, |s| s.iter().sum::<i32>() >= 10);
+        assert!(ret.iter().sum::<i32>() >= 10);
+        for i in 0..ret.len() {
+            assert!(ret.iter().sum::<i32>() - ret[i] < 10);
+        }
+
+        assert_eq!(minimize_conflict(&items, |_| true), Vec::<i32>::new());
+    }
+}
@@ -1,11 +1,17 @@
 use std::ops::{Index, IndexMut};
 
+mod conflict;
 mod graph_separation;
 mod pos;
+mod regions;
+mod timer;
+pub use self::conflict::*;
 pub use self::graph_separation::*;
 pub use self::pos::*;
+pub use self::regions::*;
+pub use self::timer::*;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Grid<T: Clone> {
     height: i32,
     width: i32,
@@ -177,6 +183,78 @@ impl Symmetry {
             vertical: false,
         }
     }
+    /// Parses a comma-separated list of `dyad`, `tetrad`, `horizontal` and `vertical`.
+    /// An empty string or `none` means no symmetry.
+    pub fn from_names(names: &str) -> Option<Symmetry> {
+        let mut ret = Symmetry::none();
+        for name in names.split(',') {
+            match name.trim() {
+                "" | "none" => (),
+                "dyad" => ret.dyad = true,
+                "tetrad" => ret.tetrad = true,
+                "horizontal" => ret.horizontal = true,
+                "vertical" => ret.vertical = true,
+                _ => return None,
+            }
+        }
+        Some(ret)
+    }
+    /// Returns the cells which are mapped from `pos` by the symmetry in a grid of
+    /// `height` x `width`, starting with `pos` itself.
+    /// `tetrad` implies `dyad`, and is ignored unless the grid is square.
+    pub fn orbit(&self, pos: P, height: i32, width: i32) -> Vec<P> {
+        fn visit(pos: P, symmetry: Symmetry, visited: &mut Grid<bool>, ret: &mut Vec<P>) {
+            if visited[pos] {
+                return;
+            }
+            visited[pos] = true;
+            ret.push(pos);
+
+            let P(y, x) = pos;
+            let height = visited.height();
+            let width = visited.width();
+            if symmetry.tetrad {
+                visit(P(height - 1 - x, y), symmetry, visited, ret);
+            } else if symmetry.dyad {
+                visit(P(height - 1 - y, width - 1 - x), symmetry, visited, ret);
+            }
+            if symmetry.horizontal {
+                visit(P(height - 1 - y, x), symmetry, visited, ret);
+            }
+            if symmetry.vertical {
+                visit(P(y, width - 1 - x), symmetry, visited, ret);
+            }
+        }
+
+        let mut symmetry = *self;
+        symmetry.dyad |= symmetry.tetrad;
+        symmetry.tetrad &= height == width;
+
+        let mut visited = Grid::new(height, width, false);
+        let mut ret = vec![];
+        visit(pos, symmetry, &mut visited, &mut ret);
+        ret
+    }
+    /// Splits `cells` into groups of cells which are mapped to each other by the symmetry.
+    pub fn group_cells(&self, cells: &[P], height: i32, width: i32) -> Vec<Vec<P>> {
+        let mut grouped = Grid::new(height, width, false);
+        let mut ret = vec![];
+        for &pos in cells {
+            if grouped[pos] {
+                continue;
+            }
+            let group = self
+                .orbit(pos, height, width)
+                .into_iter()
+                .filter(|p| cells.contains(p))
+                .collect::<Vec<_>>();
+            for &p in &group {
+                grouped[p] = true;
+            }
+            ret.push(group);
+        }
+        ret
+    }
 }
 
 #[cfg(test)]
@@ -221,4 +299,22 @@ mod tests {
         assert_eq!(grid[P(2, 1)], 0);
         assert_eq!(grid[4], 4);
     }
+
+    #[test]
+    fn test_symmetry_orbit() {
+        let dyad = Symmetry::from_names("dyad").unwrap();
+        assert_eq!(dyad.orbit(P(0, 1), 3, 4), vec![P(0, 1), P(2, 2)]);
+        assert_eq!(dyad.orbit(P(1, 1), 3, 3), vec![P(1, 1)]);
+
+        let tetrad = Symmetry::from_names("tetrad").unwrap();
+        assert_eq!(
+            tetrad.orbit(P(0, 1), 3, 3),
+            vec![P(0, 1), P(1, 0), P(2, 1), P(1, 2)]
+        );
+        // Only 180-degree rotation is possible for non-square grids
+        assert_eq!(tetrad.orbit(P(0, 1), 3, 4), vec![P(0, 1), P(2, 2)]);
+
+        let groups = dyad.group_cells(&[P(0, 0), P(0, 1), P(2, 2)], 3, 3);
+        assert_eq!(groups, vec![vec![P(0, 0), P(2, 2)], vec![P(0, 1)]]);
+    }
 }
\ No newline at end of file
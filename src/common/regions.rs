@@ -0,0 +1,224 @@
+use super::{Grid, D, LP, P};
+
+const NO_AREA: usize = !0;
+
+/// A partition of (a subset of) the cells of a rectangular grid into irregular regions.
+///
+/// Cells which do not belong to any region are treated as holes (e.g. black cells in Dosufuwa).
+/// Borders are represented in the same coordinates as `doublechoco::Field::border`:
+/// a grid of size `(2 * height - 1) * (2 * width - 1)` in which `LP(2y, 2x)` is the cell `P(y, x)`.
+#[derive(Debug, Clone)]
+pub struct Regions {
+    area_id: Grid<usize>,
+    areas: Vec<Vec<P>>,
+}
+
+impl Regions {
+    pub fn from_areas(height: i32, width: i32, areas: &[Vec<P>]) -> Regions {
+        let mut area_id = Grid::new(height, width, NO_AREA);
+        for (i, area) in areas.iter().enumerate() {
+            for &p in area {
+                assert_eq!(area_id[p], NO_AREA, "cell {:?} belongs to several areas", p);
+                area_id[p] = i;
+            }
+        }
+        Regions {
+            area_id,
+            areas: areas.to_vec(),
+        }
+    }
+    /// Builds regions from a border bitmap in which `true` means that a border exists.
+    /// Every cell belongs to some region.
+    pub fn from_borders(border: &Grid<bool>) -> Regions {
+        let height = (border.height() + 1) / 2;
+        let width = (border.width() + 1) / 2;
+        let mut area_id = Grid::new(height, width, NO_AREA);
+        let mut areas = vec![];
+
+        for y in 0..height {
+            for x in 0..width {
+                if area_id[P(y, x)] != NO_AREA {
+                    continue;
+                }
+                let id = areas.len();
+                let mut area = vec![P(y, x)];
+                area_id[P(y, x)] = id;
+                let mut i = 0;
+                while i < area.len() {
+                    let pos = area[i];
+                    i += 1;
+                    for &d in &super::FOUR_NEIGHBOURS {
+                        let pos2 = pos + d;
+                        if area_id.is_valid_p(pos2)
+                            && area_id[pos2] == NO_AREA
+                            && !border[LP::of_vertex(pos) + d]
+                        {
+                            area_id[pos2] = id;
+                            area.push(pos2);
+                        }
+                    }
+                }
+                area.sort_by_key(|&P(y, x)| (y, x));
+                areas.push(area);
+            }
+        }
+
+        Regions { area_id, areas }
+    }
+    pub fn height(&self) -> i32 {
+        self.area_id.height()
+    }
+    pub fn width(&self) -> i32 {
+        self.area_id.width()
+    }
+    pub fn num_areas(&self) -> usize {
+        self.areas.len()
+    }
+    pub fn area(&self, id: usize) -> &[P] {
+        &self.areas[id]
+    }
+    pub fn areas(&self) -> &Vec<Vec<P>> {
+        &self.areas
+    }
+    /// Returns the id of the region containing `pos`, or `None` if `pos` is a hole.
+    pub fn area_id(&self, pos: P) -> Option<usize> {
+        match self.area_id[pos] {
+            NO_AREA => None,
+            id => Some(id),
+        }
+    }
+    pub fn is_hole(&self, pos: P) -> bool {
+        self.area_id[pos] == NO_AREA
+    }
+    /// Returns whether there is a border between two adjacent cells `pos` and `pos + d`.
+    pub fn has_border(&self, pos: P, d: D) -> bool {
+        self.area_id[pos] != self.area_id[pos + d]
+    }
+    /// Returns the border bitmap of this partition.
+    /// A hole is separated from every adjacent cell.
+    pub fn to_borders(&self) -> Grid<bool> {
+        let height = self.height();
+        let width = self.width();
+        let mut ret = Grid::new(height * 2 - 1, width * 2 - 1, false);
+        for y in 0..height {
+            for x in 0..width {
+                let pos = P(y, x);
+                if y < height - 1 {
+                    ret[LP::of_vertex(pos) + D(1, 0)] =
+                        self.is_hole(pos) || self.has_border(pos, D(1, 0));
+                }
+                if x < width - 1 {
+                    ret[LP::of_vertex(pos) + D(0, 1)] =
+                        self.is_hole(pos) || self.has_border(pos, D(0, 1));
+                }
+            }
+        }
+        ret
+    }
+    /// Returns the ids of the regions adjacent to the region `id`, in increasing order.
+    pub fn neighbors(&self, id: usize) -> Vec<usize> {
+        let mut ret = vec![];
+        for &pos in &self.areas[id] {
+            for &d in &super::FOUR_NEIGHBOURS {
+                let pos2 = pos + d;
+                if self.area_id.is_valid_p(pos2) {
+                    let id2 = self.area_id[pos2];
+                    if id2 != NO_AREA && id2 != id {
+                        ret.push(id2);
+                    }
+                }
+            }
+        }
+        ret.sort();
+        ret.dedup();
+        ret
+    }
+    pub fn is_adjacent(&self, id1: usize, id2: usize) -> bool {
+        self.neighbors(id1).binary_search(&id2).is_ok()
+    }
+    /// Decodes the puzz.link border bitfield at the beginning of `body`.
+    /// Returns the regions and the number of consumed characters.
+    pub fn decode_url(height: i32, width: i32, body: &str) -> Option<(Regions, usize)> {
+        let (border, consumed) = crate::url::decode_border_bitfield(height, width, body)?;
+        Some((Regions::from_borders(&border), consumed))
+    }
+    /// Encodes the borders of this partition as a puzz.link border bitfield.
+    pub fn encode_url(&self) -> String {
+        crate::url::encode_border_bitfield(&self.to_borders())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn areas_from_ids(ids: &[&[i32]]) -> Vec<Vec<P>> {
+        let mut ret = vec![];
+        for (y, row) in ids.iter().enumerate() {
+            for (x, &id) in row.iter().enumerate() {
+                if id >= 0 {
+                    while ret.len() as i32 <= id {
+                        ret.push(vec![]);
+                    }
+                    ret[id as usize].push(P(y as i32, x as i32));
+                }
+            }
+        }
+        ret
+    }
+
+    #[test]
+    fn test_regions_borders() {
+        let areas = areas_from_ids(&[&[0, 0, 1], &[2, 0, 1], &[2, 2, 1]]);
+        let regions = Regions::from_areas(3, 3, &areas);
+
+        assert_eq!(regions.num_areas(), 3);
+        assert_eq!(regions.area_id(P(1, 1)), Some(0));
+        assert!(regions.has_border(P(0, 1), D(0, 1)));
+        assert!(!regions.has_border(P(0, 0), D(0, 1)));
+
+        let border = regions.to_borders();
+        assert!(!border[LP(0, 1)]);
+        assert!(border[LP(0, 3)]);
+        assert!(border[LP(1, 0)]);
+        assert!(!border[LP(3, 0)]);
+
+        let regions2 = Regions::from_borders(&border);
+        assert_eq!(regions2.areas(), regions.areas());
+
+        assert_eq!(regions.neighbors(0), vec![1, 2]);
+        assert_eq!(regions.neighbors(1), vec![0, 2]);
+        assert!(regions.is_adjacent(2, 1));
+    }
+
+    #[test]
+    fn test_regions_holes() {
+        let areas = areas_from_ids(&[&[0, -1, 1], &[0, 0, 1]]);
+        let regions = Regions::from_areas(2, 3, &areas);
+
+        assert!(regions.is_hole(P(0, 1)));
+        assert_eq!(regions.area_id(P(0, 1)), None);
+        assert_eq!(regions.neighbors(0), vec![1]);
+
+        let border = regions.to_borders();
+        assert!(border[LP(0, 1)]);
+        assert!(border[LP(1, 2)]);
+    }
+
+    #[test]
+    fn test_regions_url() {
+        let areas = areas_from_ids(&[&[0, 0, 1], &[2, 0, 1], &[2, 2, 1]]);
+        let regions = Regions::from_areas(3, 3, &areas);
+        let encoded = regions.encode_url();
+
+        // vertical borders: 01110 1(0000), horizontal borders: 10001 0(0000)
+        assert_eq!(encoded, "egh0");
+
+        let (decoded, consumed) = Regions::decode_url(3, 3, "egh01234").unwrap();
+        assert_eq!(consumed, 4);
+        assert_eq!(decoded.areas(), regions.areas());
+
+        assert!(Regions::decode_url(3, 3, "egh").is_none());
+        assert!(Regions::decode_url(3, 3, "eg!0").is_none());
+    }
+}
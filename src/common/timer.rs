@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+/// Measures the time elapsed since its creation.
+///
+/// `std::time::Instant` is not available on `wasm32-unknown-unknown`, so the clock of
+/// the JavaScript host is used there instead.
+#[derive(Clone, Copy, Debug)]
+pub struct Timer {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: std::time::Instant,
+    #[cfg(target_arch = "wasm32")]
+    start: f64,
+}
+
+impl Timer {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn start() -> Timer {
+        Timer {
+            start: std::time::Instant::now(),
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    pub fn start() -> Timer {
+        Timer {
+            start: js_sys::Date::now(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+    #[cfg(target_arch = "wasm32")]
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_micros(((js_sys::Date::now() - self.start).max(0.0) * 1000.0) as u64)
+    }
+}
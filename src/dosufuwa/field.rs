@@ -1,5 +1,5 @@
-use super::super::{Grid, D, P};
-use super::Cell;
+use super::super::{minimize_conflict, Contradiction, Grid, Regions, P};
+use super::{Cell, Decision};
 
 #[derive(Clone)]
 struct Area {
@@ -14,10 +14,17 @@ pub struct Field {
     maybe_balloon: Grid<bool>,
     maybe_iron: Grid<bool>,
     area_cells: Vec<Vec<P>>,
+    /// Whether each area must contain exactly one balloon and one iron; this is false only
+    /// for the areas ignored in `explain_inconsistency`.
+    required: Vec<bool>,
     areas_balloon: Vec<Area>,
     areas_iron: Vec<Area>,
     num_decided: i32,
     inconsistent: bool,
+    contradiction: Option<Contradiction<(P, Decision)>>,
+    /// Decisions made by trial and error, in order: assumptions of probes and decisions
+    /// forced by contradictory assumptions.
+    trail: Vec<(P, Decision)>,
 }
 
 impl Field {
@@ -55,12 +62,27 @@ impl Field {
             maybe_balloon: maybe_balloon_iron.clone(),
             maybe_iron: maybe_balloon_iron.clone(),
             area_cells: areas.clone(),
+            required: vec![true; areas.len()],
             areas_balloon: areas_balloon_iron.clone(),
             areas_iron: areas_balloon_iron.clone(),
             num_decided,
             inconsistent: false,
+            contradiction: None,
+            trail: vec![],
         }
     }
+    /// Creates a field from `regions`; cells outside any region are black.
+    pub fn from_regions(regions: &Regions) -> Field {
+        let height = regions.height();
+        let width = regions.width();
+        let mut is_black = Grid::new(height, width, false);
+        for y in 0..height {
+            for x in 0..width {
+                is_black[P(y, x)] = regions.is_hole(P(y, x));
+            }
+        }
+        Field::new(&is_black, regions.areas())
+    }
 
     pub fn height(&self) -> i32 {
         self.cell.height()
@@ -72,8 +94,63 @@ impl Field {
         self.inconsistent
     }
     pub fn set_inconsistent(&mut self) {
+        self.set_contradiction("marked inconsistent by the caller", vec![]);
+    }
+    /// Returns the first contradiction found. Its decisions are the ones made by
+    /// trial and error before it was found.
+    pub fn contradiction(&self) -> Option<&Contradiction<(P, Decision)>> {
+        self.contradiction.as_ref()
+    }
+    fn set_contradiction(&mut self, rule: &'static str, cells: Vec<P>) {
+        if !self.inconsistent {
+            self.contradiction = Some(Contradiction {
+                rule,
+                cells,
+                decisions: self.trail.clone(),
+            });
+        }
         self.inconsistent = true;
     }
+    /// Applies `decision` to `pos` as a step of trial and error, recording it in the trail.
+    fn decide_by_trial(&mut self, pos: P, decision: Decision) {
+        self.trail.push((pos, decision));
+        match decision {
+            Decision::Balloon => self.decide_balloon(pos),
+            Decision::NoBalloon => self.decide_no_balloon(pos),
+            Decision::Iron => self.decide_iron(pos),
+            Decision::NoIron => self.decide_no_iron(pos),
+        }
+    }
+    /// Returns the cells of a minimal set of areas whose conditions (one balloon and one iron
+    /// in each area) are contradictory by themselves under `trial_and_error(depth)`,
+    /// or `None` if the areas do not contradict without the cells decided so far.
+    pub fn explain_inconsistency(&self, depth: i32) -> Option<Vec<P>> {
+        let mut is_black = Grid::new(self.height(), self.width(), false);
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                is_black[P(y, x)] = self.cell[P(y, x)] == Cell::Black;
+            }
+        }
+        let ids = (0..self.area_cells.len()).collect::<Vec<_>>();
+        let is_conflicting = |ids: &[usize]| {
+            let mut field = Field::new(&is_black, &self.area_cells);
+            field.required = vec![false; self.area_cells.len()];
+            for &id in ids {
+                field.required[id] = true;
+            }
+            field.inspect_initial();
+            field.trial_and_error(depth);
+            field.inconsistent()
+        };
+        if !is_conflicting(&ids) {
+            return None;
+        }
+        let mut ret = vec![];
+        for id in minimize_conflict(&ids, is_conflicting) {
+            ret.extend_from_slice(&self.area_cells[id]);
+        }
+        Some(ret)
+    }
     pub fn fully_solved(&self) -> bool {
         self.num_decided == self.height() * self.width()
     }
@@ -82,9 +159,13 @@ impl Field {
     }
 
     fn inspect_area_balloon(&mut self, id: usize) {
+        if !self.required[id] {
+            return;
+        }
         let area = &self.areas_balloon[id];
         if area.num_cand == 0 {
-            self.set_inconsistent();
+            let cells = self.area_cells[id].clone();
+            self.set_contradiction("no place for the balloon in an area", cells);
             return;
         } else if area.num_cand == 1 {
             self.decide_balloon(self.cell.p(area.xor_cand));
@@ -96,17 +177,19 @@ impl Field {
             return;
         }
         if cell != Cell::Undecided {
-            self.set_inconsistent();
+            self.set_contradiction("balloon in a decided cell", vec![pos]);
             return;
         }
         self.cell[pos] = Cell::Balloon;
         self.num_decided += 1;
         self.decide_no_iron(pos);
         let area_id = self.area_id[pos];
-        for i in 0..self.area_cells[area_id].len() {
-            let p = self.area_cells[area_id][i];
-            if p != pos {
-                self.decide_no_balloon(p);
+        if self.required[area_id] {
+            for i in 0..self.area_cells[area_id].len() {
+                let p = self.area_cells[area_id][i];
+                if p != pos {
+                    self.decide_no_balloon(p);
+                }
             }
         }
         let P(y, x) = pos;
@@ -138,9 +221,13 @@ impl Field {
     }
 
     fn inspect_area_iron(&mut self, id: usize) {
+        if !self.required[id] {
+            return;
+        }
         let area = &self.areas_iron[id];
         if area.num_cand == 0 {
-            self.set_inconsistent();
+            let cells = self.area_cells[id].clone();
+            self.set_contradiction("no place for the iron in an area", cells);
             return;
         } else if area.num_cand == 1 {
             self.decide_iron(self.cell.p(area.xor_cand));
@@ -152,7 +239,7 @@ impl Field {
             return;
         }
         if cell != Cell::Undecided {
-            self.set_inconsistent();
+            self.set_contradiction("iron in a decided cell", vec![pos]);
             return;
         }
         self.cell[pos] = Cell::Iron;
@@ -160,10 +247,12 @@ impl Field {
         self.decide_no_balloon(pos);
 
         let area_id = self.area_id[pos];
-        for i in 0..self.area_cells[area_id].len() {
-            let p = self.area_cells[area_id][i];
-            if p != pos {
-                self.decide_no_iron(p);
+        if self.required[area_id] {
+            for i in 0..self.area_cells[area_id].len() {
+                let p = self.area_cells[area_id][i];
+                if p != pos {
+                    self.decide_no_iron(p);
+                }
             }
         }
         let P(y, x) = pos;
@@ -201,7 +290,9 @@ impl Field {
             for x in 0..width {
                 let mut y2 = y + 1;
                 while y2 < height && self.cell[P(y2, x)] != Cell::Black {
-                    if self.area_id[P(y, x)] == self.area_id[P(y2, x)] {
+                    if self.area_id[P(y, x)] == self.area_id[P(y2, x)]
+                        && self.required[self.area_id[P(y, x)]]
+                    {
                         self.decide_no_iron(P(y, x));
                         self.decide_no_balloon(P(y2, x));
                     }
@@ -214,15 +305,22 @@ impl Field {
         // do nothing
     }
     pub fn trial_and_error(&mut self, depth: i32) {
-        let height = self.height();
-        let width = self.width();
-
         if depth == 0 {
             self.solve();
             return;
         }
         self.trial_and_error(depth - 1);
 
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        self.probe_parallel(depth);
+        #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+        self.probe_sequential(depth);
+    }
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    fn probe_sequential(&mut self, depth: i32) {
+        let height = self.height();
+        let width = self.width();
+
         loop {
             let mut updated = false;
             for y in 0..height {
@@ -234,23 +332,23 @@ impl Field {
                     if self.maybe_balloon[pos] {
                         {
                             let mut field_balloon = self.clone();
-                            field_balloon.decide_balloon(pos);
+                            field_balloon.decide_by_trial(pos, Decision::Balloon);
                             field_balloon.trial_and_error(depth - 1);
 
                             if field_balloon.inconsistent() {
                                 updated = true;
-                                self.decide_no_balloon(pos);
+                                self.decide_by_trial(pos, Decision::NoBalloon);
                                 self.trial_and_error(depth - 1);
                             }
                         }
                         {
                             let mut field_no_balloon = self.clone();
-                            field_no_balloon.decide_no_balloon(pos);
+                            field_no_balloon.decide_by_trial(pos, Decision::NoBalloon);
                             field_no_balloon.trial_and_error(depth - 1);
 
                             if field_no_balloon.inconsistent() {
                                 updated = true;
-                                self.decide_balloon(pos);
+                                self.decide_by_trial(pos, Decision::Balloon);
                                 self.trial_and_error(depth - 1);
                             }
                         }
@@ -258,23 +356,23 @@ impl Field {
                     if self.maybe_iron[pos] {
                         {
                             let mut field_iron = self.clone();
-                            field_iron.decide_iron(pos);
+                            field_iron.decide_by_trial(pos, Decision::Iron);
                             field_iron.trial_and_error(depth - 1);
 
                             if field_iron.inconsistent() {
                                 updated = true;
-                                self.decide_no_iron(pos);
+                                self.decide_by_trial(pos, Decision::NoIron);
                                 self.trial_and_error(depth - 1);
                             }
                         }
                         {
                             let mut field_no_iron = self.clone();
-                            field_no_iron.decide_no_iron(pos);
+                            field_no_iron.decide_by_trial(pos, Decision::NoIron);
                             field_no_iron.trial_and_error(depth - 1);
 
                             if field_no_iron.inconsistent() {
                                 updated = true;
-                                self.decide_iron(pos);
+                                self.decide_by_trial(pos, Decision::Iron);
                                 self.trial_and_error(depth - 1);
                             }
                         }
@@ -289,6 +387,59 @@ impl Field {
             }
         }
     }
+    /// Probes all undecided cells in parallel and merges the forced decisions after each round.
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    fn probe_parallel(&mut self, depth: i32) {
+        use rayon::prelude::*;
+
+        loop {
+            let mut positions = vec![];
+            for y in 0..self.height() {
+                for x in 0..self.width() {
+                    if self.cell[P(y, x)] == Cell::Undecided {
+                        positions.push(P(y, x));
+                    }
+                }
+            }
+
+            let base: &Field = self;
+            let forced = positions
+                .into_par_iter()
+                .flat_map(|pos| {
+                    let mut probes = vec![];
+                    if base.maybe_balloon[pos] {
+                        probes.push((Decision::Balloon, Decision::NoBalloon));
+                        probes.push((Decision::NoBalloon, Decision::Balloon));
+                    }
+                    if base.maybe_iron[pos] {
+                        probes.push((Decision::Iron, Decision::NoIron));
+                        probes.push((Decision::NoIron, Decision::Iron));
+                    }
+                    let mut ret = vec![];
+                    for (assumed, other) in probes {
+                        let mut field = base.clone();
+                        field.decide_by_trial(pos, assumed);
+                        field.trial_and_error(depth - 1);
+                        if field.inconsistent() {
+                            ret.push((pos, other));
+                        }
+                    }
+                    ret
+                })
+                .collect::<Vec<_>>();
+
+            if forced.is_empty() {
+                return;
+            }
+            for (pos, decision) in forced {
+                self.decide_by_trial(pos, decision);
+            }
+            self.trial_and_error(depth - 1);
+            if self.inconsistent() {
+                return;
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -341,7 +492,7 @@ mod tests {
             let mut field = Field::new(&is_black, &areas);
             field.inspect_initial();
 
-            assert_eq!(field.inconsistent(), false);
+            assert!(!field.inconsistent());
             assert_eq!(field.fully_solved(), true);
         }
         {
@@ -359,17 +510,45 @@ mod tests {
                 [-1, 11, 11, -1, 8, 8, 8, -1],
             ];
             let areas = to_areas(&cell_id);
-            let is_black = extract_is_black(height, width, &areas);
+            let regions = Regions::from_areas(height, width, &areas);
 
-            let mut field = Field::new(&is_black, &areas);
+            let mut field = Field::from_regions(&regions);
             field.inspect_initial();
 
-            assert_eq!(field.inconsistent(), false);
+            assert!(!field.inconsistent());
             assert_eq!(field.fully_solved(), false);
 
             field.trial_and_error(1);
-            assert_eq!(field.inconsistent(), false);
+            assert!(!field.inconsistent());
             assert_eq!(field.fully_solved(), true);
         }
     }
+
+    #[test]
+    fn test_explain_inconsistency() {
+        let cell_id = [[0, 0, -1, 1], [2, 2, 2, 2]];
+        let areas = to_areas(&cell_id);
+        let is_black = extract_is_black(2, 4, &areas);
+
+        let mut field = Field::new(&is_black, &areas);
+        field.inspect_initial();
+        field.trial_and_error(1);
+        assert!(field.inconsistent());
+        let contradiction = field.contradiction().unwrap();
+        assert_eq!(contradiction.cells, vec![P(0, 3)]);
+        // Found after a decision forced by trial and error
+        assert!(!contradiction.decisions.is_empty());
+        assert_eq!(field.explain_inconsistency(1), Some(vec![P(0, 3)]));
+
+        let cell_id = [[0, 0, -1, 1], [2, 2, 2, 1]];
+        let areas = to_areas(&cell_id);
+        let is_black = extract_is_black(2, 4, &areas);
+
+        let mut field = Field::new(&is_black, &areas);
+        field.inspect_initial();
+        field.trial_and_error(1);
+        assert!(!field.inconsistent());
+        assert_eq!(field.contradiction(), None);
+        assert_eq!(field.explain_inconsistency(1), None);
+    }
 }
\ No newline at end of file
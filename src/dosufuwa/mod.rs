@@ -7,4 +7,13 @@ pub enum Cell {
     Empty,
     Balloon,
     Iron,
+}
+
+/// A decision on a cell made by trial and error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Balloon,
+    NoBalloon,
+    Iron,
+    NoIron,
 }
\ No newline at end of file
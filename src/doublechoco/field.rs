@@ -1,6 +1,15 @@
 use super::*;
 use std::cell::Cell;
-use crate::common::{Grid, D, FOUR_NEIGHBOURS, LP, P};
+use crate::common::{
+    minimize_conflict, Contradiction, Grid, Regions, Symmetry, D, FOUR_NEIGHBOURS, LP, P,
+};
+use crate::StableRng;
+use rand::Rng;
+
+/// The maximum number of candidates for a part of a block enumerated by `Field::inspect`
+const BLOCK_ENUMERATION_LIMIT: usize = 1000;
+/// Blocks are enumerated only if the range of the possible sizes is narrower than this
+const BLOCK_ENUMERATION_SIZE_RANGE: i32 = 4;
 
 #[derive(Clone)]
 pub struct Field {
@@ -10,8 +19,15 @@ pub struct Field {
     size_low: Grid<i32>,
     size_high: Grid<i32>,
     frozen: Grid<bool>,
+    /// The area which the last block enumeration of each cell depends on, until something in
+    /// the area is updated
+    enumerated: Grid<Option<(P, P)>>,
     num_decided_borders: i32,
     inconsistent: bool,
+    contradiction: Option<Contradiction<(LP, Border)>>,
+    /// Decisions made by trial and error, in order: assumptions of probes and decisions
+    /// forced by contradictory assumptions.
+    trail: Vec<(LP, Border)>,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -61,8 +77,11 @@ impl Field {
             size_low,
             size_high,
             frozen: Grid::new(height, width, false),
+            enumerated: Grid::new(height, width, None),
             num_decided_borders: 0,
             inconsistent: false,
+            contradiction: None,
+            trail: vec![],
         }
     }
     pub fn height(&self) -> i32 {
@@ -75,20 +94,85 @@ impl Field {
         self.inconsistent
     }
     pub fn set_inconsistent(&mut self) {
+        self.set_contradiction("marked inconsistent by the caller", vec![]);
+    }
+    /// Returns the first contradiction found. Its decisions are the borders decided by
+    /// trial and error before it was found.
+    pub fn contradiction(&self) -> Option<&Contradiction<(LP, Border)>> {
+        self.contradiction.as_ref()
+    }
+    fn set_contradiction(&mut self, rule: &'static str, cells: Vec<P>) {
+        if !self.inconsistent {
+            self.contradiction = Some(Contradiction {
+                rule,
+                cells,
+                decisions: self.trail.clone(),
+            });
+        }
         self.inconsistent = true;
     }
+    /// Decides the border at `pos` as a step of trial and error, recording it in the trail.
+    fn decide_by_trial(&mut self, pos: LP, border: Border) {
+        self.trail.push((pos, border));
+        self.decide_border(pos, border);
+    }
+    /// Returns the cells on both sides of the border at `pos`.
+    fn border_cells(pos: LP) -> Vec<P> {
+        let LP(y, x) = pos;
+        vec![P(y / 2, x / 2), P((y + 1) / 2, (x + 1) / 2)]
+    }
+    /// Returns a minimal set of clue cells which are contradictory by themselves under
+    /// `trial_and_error(depth)`, or `None` if the clues do not contradict without the borders
+    /// decided so far.
+    pub fn explain_inconsistency(&self, depth: i32) -> Option<Vec<P>> {
+        let mut clues = vec![];
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                if self.clue[P(y, x)] != NO_CLUE {
+                    clues.push(P(y, x));
+                }
+            }
+        }
+        let is_conflicting = |clues: &[P]| {
+            let mut clue = Grid::new(self.height(), self.width(), NO_CLUE);
+            for &pos in clues {
+                clue[pos] = self.clue[pos];
+            }
+            let mut field = Field::new(&self.color, &clue);
+            field.trial_and_error(depth);
+            field.inconsistent()
+        };
+        if !is_conflicting(&clues) {
+            return None;
+        }
+        Some(minimize_conflict(&clues, is_conflicting))
+    }
     pub fn border(&self, pos: LP) -> Border {
         self.border[pos]
     }
+    /// Returns the blocks separated by the borders which are decided to be `Line`.
+    pub fn regions(&self) -> Regions {
+        let mut border = Grid::new(self.border.height(), self.border.width(), false);
+        for i in 0..(border.height() * border.width()) as usize {
+            border[i] = self.border[i] == Border::Line;
+        }
+        Regions::from_borders(&border)
+    }
     pub fn decide_border(&mut self, pos: LP, border: Border) {
         if self.border[pos] != Border::Undecided {
             if self.border[pos] != border {
-                self.set_inconsistent();
+                self.set_contradiction(
+                    "border decided both as line and blank",
+                    Field::border_cells(pos),
+                );
             }
             return;
         }
         self.border[pos] = border;
         self.num_decided_borders += 1;
+        for cell in Field::border_cells(pos) {
+            self.invalidate_enumeration(cell);
+        }
 
         let LP(y, x) = pos;
         self.ensure_no_broken_border(P((y + 1) / 2, (x + 1) / 2));
@@ -116,7 +200,10 @@ impl Field {
 
         if num_line == 1 {
             if num_undecided == 0 {
-                self.set_inconsistent();
+                self.set_contradiction(
+                    "broken border",
+                    vec![P(y - 1, x - 1), P(y - 1, x), P(y, x - 1), P(y, x)],
+                );
                 return;
             } else if num_undecided == 1 {
                 for &d in &FOUR_NEIGHBOURS {
@@ -133,11 +220,17 @@ impl Field {
             }
         }
     }
-    fn check_connected_components_dfs(&self, pos: P, visited: &mut Grid<bool>) -> i32 {
+    fn check_connected_components_dfs(
+        &self,
+        pos: P,
+        visited: &mut Grid<bool>,
+        part: &mut Vec<P>,
+    ) -> i32 {
         if !self.color.is_valid_p(pos) || visited[pos] {
             return 0;
         }
         visited[pos] = true;
+        part.push(pos);
         let mut ret = match self.color[pos] {
             Color::Black => 1,
             Color::White => -1,
@@ -145,7 +238,7 @@ impl Field {
         for &d in &FOUR_NEIGHBOURS {
             if self.color.is_valid_p(pos + d) && self.border[LP::of_vertex(pos) + d] != Border::Line
             {
-                ret += self.check_connected_components_dfs(pos + d, visited);
+                ret += self.check_connected_components_dfs(pos + d, visited, part);
             }
         }
         ret
@@ -158,14 +251,127 @@ impl Field {
         for y in 0..height {
             for x in 0..width {
                 if !visited[P(y, x)] {
-                    if self.check_connected_components_dfs(P(y, x), &mut visited) != 0 {
-                        self.set_inconsistent();
+                    let mut part = vec![];
+                    if self.check_connected_components_dfs(P(y, x), &mut visited, &mut part) != 0 {
+                        self.set_contradiction(
+                            "unequal numbers of black and white cells in a part",
+                            part,
+                        );
                         return;
                     }
                 }
             }
         }
     }
+    /// Returns the chains of undecided borders which are connected through vertices with no
+    /// lines and exactly two undecided borders. All borders in a chain are decided alike, because
+    /// a vertex with exactly one line is a broken border.
+    fn border_chains(&self) -> Vec<Vec<LP>> {
+        let mut visited = Grid::new(self.border.height(), self.border.width(), false);
+        let mut ret = vec![];
+        for start in self.undecided_borders() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut chain = vec![];
+            let mut stack = vec![start];
+            while let Some(pos) = stack.pop() {
+                chain.push(pos);
+                let LP(y, x) = pos;
+                // The vertices at both ends of the border
+                let ends = if y % 2 == 0 {
+                    [LP(y - 1, x), LP(y + 1, x)]
+                } else {
+                    [LP(y, x - 1), LP(y, x + 1)]
+                };
+                for &vertex in &ends {
+                    if !self.border.is_valid_lp(vertex) {
+                        continue;
+                    }
+                    let mut undecided = vec![];
+                    let mut num_line = 0;
+                    for &d in &FOUR_NEIGHBOURS {
+                        match self.border[vertex + d] {
+                            Border::Undecided => undecided.push(vertex + d),
+                            Border::Line => num_line += 1,
+                            Border::Blank => (),
+                        }
+                    }
+                    if num_line == 0 && undecided.len() == 2 {
+                        for next in undecided {
+                            if !visited[next] {
+                                visited[next] = true;
+                                stack.push(next);
+                            }
+                        }
+                    }
+                }
+            }
+            ret.push(chain);
+        }
+        ret
+    }
+    /// Returns whether a part with unequal numbers of black and white cells would be separated
+    /// if all borders in `chain` were lines.
+    fn cuts_unbalanced_part(&self, chain: &[LP]) -> bool {
+        let mut line = Grid::new(self.border.height(), self.border.width(), false);
+        for i in 0..(line.height() * line.width()) as usize {
+            line[i] = self.border[i] == Border::Line;
+        }
+        for &pos in chain {
+            line[pos] = true;
+        }
+        let mut visited = Grid::new(self.height(), self.width(), false);
+        for &pos in chain {
+            for start in Field::border_cells(pos) {
+                if visited[start] {
+                    continue;
+                }
+                visited[start] = true;
+                let mut stack = vec![start];
+                let mut balance = 0;
+                while let Some(p) = stack.pop() {
+                    balance += match self.color[p] {
+                        Color::Black => 1,
+                        Color::White => -1,
+                    };
+                    for &d in &FOUR_NEIGHBOURS {
+                        let p2 = p + d;
+                        if self.color.is_valid_p(p2) && !visited[p2] && !line[LP::of_vertex(p) + d]
+                        {
+                            visited[p2] = true;
+                            stack.push(p2);
+                        }
+                    }
+                }
+                if balance != 0 {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+    /// Decides the chains of borders which would separate a part with unequal numbers of black
+    /// and white cells as blank.
+    fn check_border_chains(&mut self) {
+        for chain in self.border_chains() {
+            if chain
+                .iter()
+                .any(|&pos| self.border[pos] != Border::Undecided)
+            {
+                continue;
+            }
+            if self.cuts_unbalanced_part(&chain) {
+                for pos in chain {
+                    self.decide_border(pos, Border::Blank);
+                }
+                if self.inconsistent() {
+                    return;
+                }
+            }
+        }
+    }
     fn expand_block_size_dfs1(
         &self,
         pos: P,
@@ -413,6 +619,294 @@ impl Field {
 
         false
     }
+    /// Enumerates the connected sets of at most `max_size` cells allowed by `allowed` which
+    /// extend `current` without crossing lines, in the manner of Redelmeier's algorithm.
+    /// `blocked` marks the cells which are in `current` or `frontier`, or excluded already.
+    /// Returns `false` if the sets are more than `BLOCK_ENUMERATION_LIMIT`.
+    fn enumerate_halves(
+        &self,
+        max_size: usize,
+        allowed: &Grid<bool>,
+        current: &mut Vec<P>,
+        mut frontier: Vec<P>,
+        blocked: &mut Grid<bool>,
+        halves: &mut Vec<Vec<P>>,
+    ) -> bool {
+        if halves.len() >= BLOCK_ENUMERATION_LIMIT {
+            return false;
+        }
+        halves.push(current.clone());
+        if current.len() == max_size {
+            return true;
+        }
+        while let Some(pos) = frontier.pop() {
+            let mut next_frontier = frontier.clone();
+            let mut added = vec![];
+            for &d in &FOUR_NEIGHBOURS {
+                let pos2 = pos + d;
+                if allowed.is_valid_p(pos2)
+                    && allowed[pos2]
+                    && !blocked[pos2]
+                    && self.border[LP::of_vertex(pos) + d] != Border::Line
+                {
+                    blocked[pos2] = true;
+                    next_frontier.push(pos2);
+                    added.push(pos2);
+                }
+            }
+            current.push(pos);
+            let ok =
+                self.enumerate_halves(max_size, allowed, current, next_frontier, blocked, halves);
+            current.pop();
+            for pos2 in added {
+                blocked[pos2] = false;
+            }
+            if !ok {
+                return false;
+            }
+        }
+        true
+    }
+    /// Returns whether `block` can be a block as far as the decided borders are concerned:
+    /// it contains `same`, and there is no line inside it and no blank on its boundary.
+    /// `in_block` must be all `false`, and is restored before returning.
+    fn is_possible_block(&self, block: &[P], same: &[P], in_block: &mut Grid<bool>) -> bool {
+        for &pos in block {
+            in_block[pos] = true;
+        }
+        let mut ret = same.iter().all(|&pos| in_block[pos]);
+        'check: for &pos in block {
+            if !ret {
+                break;
+            }
+            for &d in &FOUR_NEIGHBOURS {
+                let pos2 = pos + d;
+                if !self.color.is_valid_p(pos2) {
+                    continue;
+                }
+                let border = self.border[LP::of_vertex(pos) + d];
+                if (in_block[pos2] && border == Border::Line)
+                    || (!in_block[pos2] && border == Border::Blank)
+                {
+                    ret = false;
+                    break 'check;
+                }
+            }
+        }
+        for &pos in block {
+            in_block[pos] = false;
+        }
+        ret
+    }
+    /// Enumerates the blocks which `base` can belong to, unless the candidates for the part of
+    /// the color of `base` are too many. The cells in every block become `Same`, and those in
+    /// no block become `Different`.
+    ///
+    /// Returns the corners of the area which the result depends on, or `None` if there is
+    /// no block.
+    fn enumerate_blocks(
+        &self,
+        base: P,
+        size_low: i32,
+        size_high: i32,
+        affinity: &mut Grid<CellAffinity>,
+    ) -> Option<(P, P)> {
+        let height = self.height();
+        let width = self.width();
+        let base_color = self.color[base];
+
+        let mut same = vec![];
+        for y in 0..height {
+            for x in 0..width {
+                if affinity[P(y, x)] == CellAffinity::Same {
+                    same.push(P(y, x));
+                }
+            }
+        }
+
+        let mut allowed = Grid::new(height, width, false);
+        for y in 0..height {
+            for x in 0..width {
+                let pos = P(y, x);
+                allowed[pos] = self.color[pos] == base_color
+                    && affinity[pos] != CellAffinity::Different
+                    && self.size_low[pos] <= size_high
+                    && size_low <= self.size_high[pos];
+            }
+        }
+        let mut blocked = Grid::new(height, width, false);
+        blocked[base] = true;
+        let mut frontier = vec![];
+        for &d in &FOUR_NEIGHBOURS {
+            let pos = base + d;
+            if allowed.is_valid_p(pos)
+                && allowed[pos]
+                && self.border[LP::of_vertex(base) + d] != Border::Line
+            {
+                blocked[pos] = true;
+                frontier.push(pos);
+            }
+        }
+        let mut halves = vec![];
+        let complete = self.enumerate_halves(
+            size_high as usize,
+            &allowed,
+            &mut vec![base],
+            frontier,
+            &mut blocked,
+            &mut halves,
+        );
+        // The counterparts are within the size of the halves from them, and the borders
+        // around them are looked at
+        let margin = if complete {
+            halves.iter().map(|half| half.len()).max().unwrap() as i32
+        } else {
+            1
+        };
+        let mut area = (base, base);
+        for &P(y, x) in halves.iter().flatten() {
+            let (P(y0, x0), P(y1, x1)) = area;
+            area = (P(y0.min(y), x0.min(x)), P(y1.max(y), x1.max(x)));
+        }
+        let (P(y0, x0), P(y1, x1)) = area;
+        let area = (
+            P((y0 - margin).max(0), (x0 - margin).max(0)),
+            P((y1 + margin).min(height - 1), (x1 + margin).min(width - 1)),
+        );
+        if !complete {
+            return Some(area);
+        }
+
+        // Whether the cell can be in a block of the size
+        let fits = |pos: P, size: i32| {
+            affinity[pos] != CellAffinity::Different
+                && (self.clue[pos] == NO_CLUE || self.clue[pos] == size)
+                && self.size_low[pos] <= size
+                && size <= self.size_high[pos]
+        };
+        let mut num_blocks = 0;
+        let mut num_in = Grid::new(height, width, 0);
+        let mut in_block = Grid::new(height, width, false);
+        let mut shape = vec![];
+        let mut offsets = vec![];
+        let mut block = vec![];
+        for half in &halves {
+            let size = half.len() as i32;
+            if size < size_low
+                || !half.iter().all(|&pos| fits(pos, size))
+                || same
+                    .iter()
+                    .any(|pos| self.color[*pos] == base_color && !half.contains(pos))
+            {
+                continue;
+            }
+            // The cells which are joined to `half` by blanks are in the block, so they must
+            // be in the counterpart
+            let mut required = same
+                .iter()
+                .filter(|&&pos| self.color[pos] != base_color)
+                .cloned()
+                .collect::<Vec<_>>();
+            let mut is_closed = true;
+            for &pos in half {
+                for &d in &FOUR_NEIGHBOURS {
+                    let pos2 = pos + d;
+                    if self.color.is_valid_p(pos2)
+                        && self.border[LP::of_vertex(pos) + d] == Border::Blank
+                        && !half.contains(&pos2)
+                    {
+                        if self.color[pos2] == base_color {
+                            is_closed = false;
+                        } else {
+                            required.push(pos2);
+                        }
+                    }
+                }
+            }
+            if !is_closed {
+                continue;
+            }
+
+            for mode in 0..8 {
+                shape.clear();
+                shape.extend(half.iter().map(|&P(y, x)| {
+                    let y = if (mode & 4) != 0 { -y } else { y };
+                    let x = if (mode & 2) != 0 { -x } else { x };
+                    if (mode & 1) != 0 {
+                        P(x, y)
+                    } else {
+                        P(y, x)
+                    }
+                }));
+                // The counterpart covers the required cells if any, and is attached to `half`
+                // in any case
+                offsets.clear();
+                if let Some(&pos) = required.first() {
+                    for &cell in &shape {
+                        offsets.push(pos - cell);
+                    }
+                } else {
+                    for &pos in half {
+                        for &d in &FOUR_NEIGHBOURS {
+                            let pos2 = pos + d;
+                            if self.color.is_valid_p(pos2) && self.color[pos2] != base_color {
+                                for &cell in &shape {
+                                    offsets.push(pos2 - cell);
+                                }
+                            }
+                        }
+                    }
+                    offsets.sort_by_key(|&D(y, x)| (y, x));
+                    offsets.dedup();
+                }
+                for &d in &offsets {
+                    if !shape.iter().all(|&cell| {
+                        let pos = cell + d;
+                        self.color.is_valid_p(pos)
+                            && self.color[pos] != base_color
+                            && fits(pos, size)
+                    }) {
+                        continue;
+                    }
+                    block.clear();
+                    block.extend_from_slice(half);
+                    block.extend(shape.iter().map(|&cell| cell + d));
+                    if !self.is_possible_block(&block, &same, &mut in_block) {
+                        continue;
+                    }
+                    num_blocks += 1;
+                    for &pos in &block {
+                        num_in[pos] += 1;
+                    }
+                }
+            }
+        }
+        if num_blocks == 0 {
+            return None;
+        }
+        for y in 0..height {
+            for x in 0..width {
+                let pos = P(y, x);
+                if num_in[pos] == num_blocks {
+                    affinity[pos] = CellAffinity::Same;
+                } else if num_in[pos] == 0 {
+                    affinity[pos] = CellAffinity::Different;
+                }
+            }
+        }
+        Some(area)
+    }
+    /// Forgets the results of block enumeration which depend on `pos`.
+    fn invalidate_enumeration(&mut self, pos: P) {
+        let P(y, x) = pos;
+        for i in 0..(self.height() * self.width()) as usize {
+            if let Some((P(y0, x0), P(y1, x1))) = self.enumerated[i] {
+                if y0 <= y && y <= y1 && x0 <= x && x <= x1 {
+                    self.enumerated[i] = None;
+                }
+            }
+        }
+    }
     fn set_initial_affinity(&self, pos: P, affinity: &mut Grid<CellAffinity>) {
         let P(y, x) = pos;
         if !self.color.is_valid_p(pos) || affinity[pos] != CellAffinity::Undecided {
@@ -432,7 +926,10 @@ impl Field {
             }
         }
     }
-    fn inspect(&mut self, base: P) {
+    /// Deduces the block of `base`. Candidates of the block are enumerated only if `enumerate`
+    /// is `true` and the size of the block is almost known, since it is far more expensive than
+    /// the other rules.
+    fn inspect(&mut self, base: P, enumerate: bool) {
         if self.frozen[base] {
             return;
         }
@@ -459,12 +956,24 @@ impl Field {
             }
         }
 
+        // The cells known to be in the same block as `base`
+        let block_cells = |affinity: &Grid<CellAffinity>| {
+            let mut ret = vec![];
+            for y in 0..height {
+                for x in 0..width {
+                    if affinity[P(y, x)] == CellAffinity::Same {
+                        ret.push(P(y, x));
+                    }
+                }
+            }
+            ret
+        };
         if self.expand_block_size(base, &mut affinity) {
-            self.set_inconsistent();
+            self.set_contradiction("no room for the block", block_cells(&affinity));
             return;
         }
         if self.find_companion(base, &mut affinity) {
-            self.set_inconsistent();
+            self.set_contradiction("no counterpart for the block", block_cells(&affinity));
             return;
         }
 
@@ -487,7 +996,7 @@ impl Field {
                     if unit_clue == NO_CLUE {
                         unit_clue = self.clue[pos];
                     } else if unit_clue != self.clue[pos] {
-                        self.set_inconsistent();
+                        self.set_contradiction("different clues in a block", vec![base, pos]);
                         return;
                     }
                 }
@@ -498,13 +1007,25 @@ impl Field {
         }
         size_high = size_high.min(max_size);
         if size_low > size_high {
-            self.set_inconsistent();
+            self.set_contradiction("no possible block size", block_cells(&affinity));
             return;
         }
         size_low = size_low.max(unit_size);
+        if enumerate
+            && size_high - size_low < BLOCK_ENUMERATION_SIZE_RANGE
+            && self.enumerated[base].is_none()
+        {
+            match self.enumerate_blocks(base, size_low, size_high, &mut affinity) {
+                Some(area) => self.enumerated[base] = Some(area),
+                None => {
+                    self.set_contradiction("no possible block", block_cells(&affinity));
+                    return;
+                }
+            }
+        }
         if unit_clue != NO_CLUE {
             if unit_size > unit_clue {
-                self.set_inconsistent();
+                self.set_contradiction("block larger than its clue", block_cells(&affinity));
                 return;
             } else if unit_size == unit_clue {
                 for y in 0..height {
@@ -531,10 +1052,14 @@ impl Field {
             for x in 0..width {
                 let aff = affinity[P(y, x)];
                 if aff == CellAffinity::Same {
+                    let bounds = (self.size_low[P(y, x)], self.size_high[P(y, x)]);
                     self.size_low[P(y, x)] = self.size_low[P(y, x)].max(size_low);
-                    self.size_high[P(y, x)] = self.size_high[P(y, x)].max(size_high);
+                    self.size_high[P(y, x)] = self.size_high[P(y, x)].min(size_high);
+                    if bounds != (self.size_low[P(y, x)], self.size_high[P(y, x)]) {
+                        self.invalidate_enumeration(P(y, x));
+                    }
                     if self.size_low[P(y, x)] > self.size_high[P(y, x)] {
-                        self.set_inconsistent();
+                        self.set_contradiction("no possible block size", vec![P(y, x)]);
                         return;
                     }
                     if is_frozen {
@@ -563,6 +1088,7 @@ impl Field {
     pub fn solve(&mut self) {
         let height = self.height();
         let width = self.width();
+        let mut enumerate = false;
         loop {
             let last_num_decided = self.num_decided_borders;
 
@@ -570,107 +1096,622 @@ impl Field {
             if self.inconsistent() {
                 return;
             }
+            self.check_border_chains();
+            if self.inconsistent() {
+                return;
+            }
             for y in 0..height {
                 for x in 0..width {
-                    self.inspect(P(y, x));
+                    self.inspect(P(y, x), enumerate);
                     if self.inconsistent() {
                         return;
                     }
                 }
             }
 
-            if last_num_decided == self.num_decided_borders {
+            if last_num_decided != self.num_decided_borders {
+                enumerate = false;
+            } else if enumerate {
                 break;
+            } else {
+                // The other rules are stuck; try enumerating the blocks once
+                enumerate = true;
             }
         }
     }
     pub fn trial_and_error(&mut self, depth: i32) -> bool {
-        let height = self.height();
-        let width = self.width();
-
-        fn is_finished(border: &Grid<Border>, width: i32, height: i32) -> bool {
-            for y in 0..(height * 2 - 1) {
-                for x in 0..(width * 2 - 1) {
-                    if x%2 == y%2 {
-                        continue;
-                    }
-
-                    if border[LP(y, x)] == Border::Undecided {
-                        return false;
-                    }
-                }
-            }
-
-            return true;
-        }
-
         if depth == 0 {
             self.solve();
-            
+
             if self.inconsistent() {
                 return false;
             }
-
-            return is_finished(&self.border, width, height);
+            if !self.is_finished() {
+                return false;
+            }
+            if let Some(cells) = self.invalid_block() {
+                self.set_contradiction("invalid answer", cells);
+                return false;
+            }
+            return true;
         }
-        
+
         if self.trial_and_error(depth - 1) {
             return true;
         }
 
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        {
+            self.probe_parallel(depth)
+        }
+        #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+        {
+            self.probe_sequential(depth)
+        }
+    }
+    fn is_finished(&self) -> bool {
+        for y in 0..(self.height() * 2 - 1) {
+            for x in 0..(self.width() * 2 - 1) {
+                if x % 2 != y % 2 && self.border[LP(y, x)] == Border::Undecided {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+    /// Verifies that the decided borders form a valid answer, and returns the cells of a block
+    /// violating the rules if they do not. A border inside a block makes the block invalid.
+    /// Every border must be decided before calling this.
+    fn invalid_block(&self) -> Option<Vec<P>> {
+        let regions = self.regions();
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let pos = P(y, x);
+                for &d in &[D(0, 1), D(1, 0)] {
+                    if self.color.is_valid_p(pos + d)
+                        && self.border[LP::of_vertex(pos) + d] == Border::Line
+                        && !regions.has_border(pos, d)
+                    {
+                        // `Regions::from_borders` makes no holes
+                        return Some(regions.area(regions.area_id(pos).unwrap()).to_vec());
+                    }
+                }
+            }
+        }
+
+        regions
+            .areas()
+            .iter()
+            .find(|area| !is_valid_block(&self.color, &self.clue, area))
+            .cloned()
+    }
+    fn undecided_borders(&self) -> Vec<LP> {
+        let mut ret = vec![];
+        for y in 0..(self.height() * 2 - 1) {
+            for x in 0..(self.width() * 2 - 1) {
+                if x % 2 != y % 2 && self.border[LP(y, x)] == Border::Undecided {
+                    ret.push(LP(y, x));
+                }
+            }
+        }
+        ret
+    }
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    fn probe_sequential(&mut self, depth: i32) -> bool {
         loop {
             let mut updated = false;
-            for y in 0..(height * 2 - 1) {
-                for x in 0..(width * 2 - 1) {
-                    if y % 2 == x % 2 {
-                        continue;
-                    }
-                    let pos = LP(y, x);
-                    if self.border[pos] != Border::Undecided {
-                        continue;
+            for pos in self.undecided_borders() {
+                if self.border[pos] != Border::Undecided {
+                    continue;
+                }
+                {
+                    let mut field_line = self.clone();
+                    field_line.decide_by_trial(pos, Border::Line);
+                    if field_line.trial_and_error(depth - 1) {
+                        *self = field_line;
+                        return true;
                     }
-                    {
-                        let mut field_line = self.clone();
-                        field_line.decide_border(pos, Border::Line);
-                        if field_line.trial_and_error(depth - 1) {
-                            return true;
-                        }
 
-                        if field_line.inconsistent() {
-                            updated = true;
-                            self.decide_border(pos, Border::Blank);
-                            if self.trial_and_error(depth - 1) {
-                                return true;
-                            }
+                    if field_line.inconsistent() {
+                        updated = true;
+                        self.decide_by_trial(pos, Border::Blank);
+                        if self.trial_and_error(depth - 1) {
+                            return true;
                         }
                     }
-                    {
-                        let mut field_blank = self.clone();
-                        field_blank.decide_border(pos, Border::Blank);
-                        if field_blank.trial_and_error(depth - 1) {
+                }
+                {
+                    let mut field_blank = self.clone();
+                    field_blank.decide_by_trial(pos, Border::Blank);
+                    if field_blank.trial_and_error(depth - 1) {
+                        *self = field_blank;
+                        return true;
+                    }
+
+                    if field_blank.inconsistent() {
+                        updated = true;
+                        self.decide_by_trial(pos, Border::Line);
+                        if self.trial_and_error(depth - 1) {
                             return true;
                         }
+                    }
+                }
+            }
+            if !updated {
+                break;
+            }
+        }
 
-                        if field_blank.inconsistent() {
-                            updated = true;
-                            self.decide_border(pos, Border::Line);
-                            if self.trial_and_error(depth - 1) {
-                                return true;
+        false
+    }
+    /// Probes all undecided borders in parallel and merges the forced decisions after each round.
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    fn probe_parallel(&mut self, depth: i32) -> bool {
+        use rayon::prelude::*;
+
+        enum Probe {
+            Solved(Box<Field>),
+            Forced(LP, Border),
+            Unknown,
+        }
+
+        loop {
+            let base: &Field = self;
+            let results = base
+                .undecided_borders()
+                .into_par_iter()
+                .map(|pos| {
+                    let mut forced = None;
+                    let probes = [(Border::Line, Border::Blank), (Border::Blank, Border::Line)];
+                    for &(assumed, other) in &probes {
+                        let mut field = base.clone();
+                        field.decide_by_trial(pos, assumed);
+                        if field.trial_and_error(depth - 1) {
+                            return Probe::Solved(Box::new(field));
+                        }
+                        if field.inconsistent() {
+                            if forced.is_some() {
+                                return Probe::Forced(pos, Border::Undecided);
                             }
+                            forced = Some(other);
                         }
                     }
+                    match forced {
+                        Some(border) => Probe::Forced(pos, border),
+                        None => Probe::Unknown,
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let mut updated = false;
+            for res in results {
+                match res {
+                    Probe::Solved(field) => {
+                        *self = *field;
+                        return true;
+                    }
+                    Probe::Forced(pos, Border::Undecided) => {
+                        self.set_contradiction(
+                            "border can be neither line nor blank",
+                            Field::border_cells(pos),
+                        );
+                        return false;
+                    }
+                    Probe::Forced(pos, border) => {
+                        updated = true;
+                        self.decide_by_trial(pos, border);
+                    }
+                    Probe::Unknown => (),
                 }
             }
             if !updated {
-                break;
+                return false;
+            }
+            if self.trial_and_error(depth - 1) {
+                return true;
+            }
+            if self.inconsistent() {
+                return false;
             }
         }
+    }
+}
 
+/// Checks whether `area` forms a valid block: it consists of a black part and a white part
+/// which are connected, congruent (rotations and reflections allowed) and of the size given
+/// by the clues in the block.
+pub(super) fn is_valid_block(color: &Grid<Color>, clue: &Grid<Clue>, area: &[P]) -> bool {
+    fn is_connected(cells: &[P]) -> bool {
+        let mut visited = vec![false; cells.len()];
+        let mut stack = vec![0];
+        visited[0] = true;
+        let mut n_visited = 1;
+        while let Some(i) = stack.pop() {
+            for j in 0..cells.len() {
+                let D(dy, dx) = cells[i] - cells[j];
+                if !visited[j] && dy.abs() + dx.abs() == 1 {
+                    visited[j] = true;
+                    n_visited += 1;
+                    stack.push(j);
+                }
+            }
+        }
+        n_visited == cells.len()
+    }
+    fn normalize(cells: &[P], mode: i32) -> Vec<(i32, i32)> {
+        let mut ret = cells
+            .iter()
+            .map(|&P(y, x)| {
+                let y = if (mode & 4) != 0 { -y } else { y };
+                let x = if (mode & 2) != 0 { -x } else { x };
+                if (mode & 1) != 0 {
+                    (x, y)
+                } else {
+                    (y, x)
+                }
+            })
+            .collect::<Vec<_>>();
+        let y_lo = ret.iter().map(|&(y, _)| y).min().unwrap();
+        let x_lo = ret.iter().map(|&(_, x)| x).min().unwrap();
+        for p in &mut ret {
+            *p = (p.0 - y_lo, p.1 - x_lo);
+        }
+        ret.sort();
+        ret
+    }
+
+    let black = area
+        .iter()
+        .filter(|&&p| color[p] == Color::Black)
+        .cloned()
+        .collect::<Vec<_>>();
+    let white = area
+        .iter()
+        .filter(|&&p| color[p] == Color::White)
+        .cloned()
+        .collect::<Vec<_>>();
+    if black.len() != white.len() || !is_connected(&black) || !is_connected(&white) {
+        return false;
+    }
+    if area
+        .iter()
+        .any(|&p| clue[p] != NO_CLUE && clue[p] != black.len() as i32)
+    {
         return false;
     }
+    let white = normalize(&white, 0);
+    (0..8).any(|mode| normalize(&black, mode) == white)
+}
+
+/// Solves the problem with `trial_and_error(depth)` starting from the borders decided by
+/// the player, which are given in `marks` indexed in the same way as `Field::border`.
+///
+/// The returned field is not necessarily fully solved. If the marks are found to be
+/// contradictory, returns a minimal set of the marked borders which contradict together instead.
+/// The set is empty if the problem itself is contradictory.
+pub fn solve_from(
+    color: &Grid<Color>,
+    clue: &Grid<Clue>,
+    marks: &Grid<Border>,
+    depth: i32,
+) -> Result<Field, Vec<LP>> {
+    let mut decided = vec![];
+    for y in 0..marks.height() {
+        for x in 0..marks.width() {
+            if x % 2 != y % 2 && marks[LP(y, x)] != Border::Undecided {
+                decided.push(LP(y, x));
+            }
+        }
+    }
+    let solve = |decided: &[LP]| {
+        let mut field = Field::new(color, clue);
+        for &pos in decided {
+            field.decide_border(pos, marks[pos]);
+        }
+        field.trial_and_error(depth);
+        field
+    };
+
+    let field = solve(&decided);
+    if !field.inconsistent() {
+        return Ok(field);
+    }
+    Err(minimize_conflict(&decided, |decided| {
+        solve(decided).inconsistent()
+    }))
+}
+
+/// Counts the answers of the problem, stopping as soon as `limit` answers are found.
+///
+/// Unlike `trial_and_error`, which accepts the first answer found during probing,
+/// this branches on every undecided border after `solve`.
+pub fn count_solutions(color: &Grid<Color>, clue: &Grid<Clue>, limit: usize) -> usize {
+    fn count(mut field: Field, limit: usize) -> usize {
+        if field.trial_and_error(0) {
+            return 1;
+        }
+        if field.inconsistent() {
+            return 0;
+        }
+        let pos = field.undecided_borders()[0];
+        let mut ret = 0;
+        for &border in &[Border::Line, Border::Blank] {
+            if ret < limit {
+                let mut field = field.clone();
+                field.decide_border(pos, border);
+                ret += count(field, limit - ret);
+            }
+        }
+        ret
+    }
+    count(Field::new(color, clue), limit)
+}
+
+/// Removes clues of the problem one after another, in a random order, as long as it has
+/// a unique answer. Clues which are mapped to each other by `symmetry` are removed together.
+/// Returns `None` if the problem does not have a unique answer in the first place.
+pub fn minimize<R: Rng>(
+    color: &Grid<Color>,
+    clue: &Grid<Clue>,
+    symmetry: Symmetry,
+    rng: &mut R,
+) -> Option<Grid<Clue>> {
+    let height = clue.height();
+    let width = clue.width();
+
+    let mut clues = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            if clue[P(y, x)] != NO_CLUE {
+                clues.push(P(y, x));
+            }
+        }
+    }
+    let mut groups = symmetry.group_cells(&clues, height, width);
+    rng.stable_shuffle(&mut groups);
+
+    let clue_of = |groups: &[Vec<P>]| {
+        let mut ret = Grid::new(height, width, NO_CLUE);
+        for group in groups {
+            for &pos in group {
+                ret[pos] = clue[pos];
+            }
+        }
+        ret
+    };
+    let is_unique = |groups: &[Vec<P>]| count_solutions(color, &clue_of(groups), 2) == 1;
+    if !is_unique(&groups) {
+        return None;
+    }
+    Some(clue_of(&minimize_conflict(&groups, is_unique)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-}
\ No newline at end of file
+
+    fn solve_url(url: &str, depth: i32) -> Field {
+        let (color, clue) = crate::parse_url_dblchoco_internal(url).unwrap();
+        let mut field = Field::new(&color, &clue);
+        assert!(field.trial_and_error(depth));
+        field
+    }
+
+    fn border_rows(field: &Field) -> Vec<String> {
+        let mut ret = vec![];
+        for y in 0..(field.height() * 2 - 1) {
+            let mut row = String::new();
+            for x in 0..(field.width() * 2 - 1) {
+                row.push(if y % 2 == 0 && x % 2 == 0 {
+                    '.'
+                } else if y % 2 == 1 && x % 2 == 1 {
+                    '+'
+                } else {
+                    match field.border(LP(y, x)) {
+                        Border::Undecided => '?',
+                        Border::Line if y % 2 == 0 => '|',
+                        Border::Line => '-',
+                        Border::Blank => ' ',
+                    }
+                });
+            }
+            ret.push(row);
+        }
+        ret
+    }
+
+    #[test]
+    fn test_solve_8x8() {
+        // Used to require trial and error before the chains of borders were checked and
+        // the candidates of blocks were enumerated
+        let field = solve_url(
+            "https://puzz.link/p?dbchoco/8/8/1tkv17m0f4pheu1j2j6j62g2u3i1p",
+            0,
+        );
+        assert_eq!(
+            border_rows(&field),
+            vec![
+                ". . .|.|.|.|. .",
+                " +-+-+ + + + +-",
+                ".|. . .|.|.|.|.",
+                " +-+-+-+ +-+ + ",
+                ". .|. .|. .|.|.",
+                " +-+ + +-+-+-+-",
+                ".|. . . . . .|.",
+                " +-+ + + + +-+ ",
+                ".|.|. . . .|.|.",
+                "-+ +-+-+-+-+ + ",
+                ". .|.|. .|. .|.",
+                " +-+ +-+ +-+ + ",
+                ".|. . .|.|.|.|.",
+                "-+ +-+-+ + + +-",
+                ". .|. .|.|.|. .",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_solve_6x6() {
+        // Used to yield a wrong answer because the size upper bounds were never tightened
+        let field = solve_url("https://puzz.link/p?dbchoco/6/6/poc4f1tgj6zl3h3g", 1);
+        assert_eq!(
+            border_rows(&field),
+            vec![
+                ". . . .|. .",
+                "-+-+-+-+ + ",
+                ".|. . . . .",
+                " + +-+-+ + ",
+                ".|.|.|.|. .",
+                " +-+ + + + ",
+                ". .|.|.|. .",
+                "-+-+ +-+-+-",
+                ". .|.|. . .",
+                "-+-+ + + + ",
+                ". . .|. . .",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_regions() {
+        let (color, clue) = crate::parse_url_dblchoco_internal(
+            "https://puzz.link/p?dbchoco/8/8/0c5hu1vlvn4hgm45h6h5p6g5o6p5i5g5i5j2",
+        )
+        .unwrap();
+        let mut field = Field::new(&color, &clue);
+        assert!(field.trial_and_error(1));
+
+        let regions = field.regions();
+        let n_cells: usize = regions.areas().iter().map(|a| a.len()).sum();
+        assert_eq!(n_cells, 64);
+        for id in 0..regions.num_areas() {
+            let area = regions.area(id);
+            let n_black = area.iter().filter(|&&p| color[p] == Color::Black).count();
+            assert_eq!(n_black * 2, area.len());
+            assert!(!regions.neighbors(id).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_solve_from() {
+        let url = "https://puzz.link/p?dbchoco/6/6/poc4f1tgj6zl3h3g";
+        let (color, clue) = crate::parse_url_dblchoco_internal(url).unwrap();
+        let answer = solve_url(url, 2);
+
+        let mut marks = Grid::new(11, 11, Border::Undecided);
+        marks[LP(1, 0)] = Border::Line;
+        let field = solve_from(&color, &clue, &marks, 2).unwrap();
+        assert_eq!(border_rows(&field), border_rows(&answer));
+
+        marks[LP(0, 1)] = Border::Line;
+        assert_eq!(answer.border(LP(0, 1)), Border::Blank);
+        assert_eq!(solve_from(&color, &clue, &marks, 2).err(), Some(vec![LP(0, 1)]));
+    }
+
+    #[test]
+    fn test_propagation_time_16x16() {
+        // Enumerating the blocks on every pass used to make this several times slower
+        let url = "https://puzz.link/p?dbchoco/16/16/vf9cnls83q419uuv0pgaocl06um0fb1i0pgfnnuo2055f6ulr9bgh41g2j2k3r3g3k2g22g2q5h5i1l5l5l2k5j4i2g5n51m5o4p5j4g2g5n51j2j5k4l5l5k1g1h5h5j2l2j2i1n11o32p3g";
+        let (color, clue) = crate::parse_url_dblchoco_internal(url).unwrap();
+        let start = std::time::Instant::now();
+        let mut field = Field::new(&color, &clue);
+        field.solve();
+        let elapsed = start.elapsed();
+        assert!(!field.inconsistent());
+        assert!(
+            elapsed < std::time::Duration::from_secs(3),
+            "took {:?}",
+            elapsed
+        );
+    }
+
+    #[test]
+    fn test_count_solutions() {
+        let url = "https://puzz.link/p?dbchoco/6/6/poc4f1tgj6zl3h3g";
+        let (color, clue) = crate::parse_url_dblchoco_internal(url).unwrap();
+        assert_eq!(count_solutions(&color, &clue, 2), 1);
+    }
+
+    #[test]
+    fn test_minimize() {
+        let mut rng = crate::SeedRng::new(42);
+        let mut color = Grid::new(2, 2, Color::White);
+        color[P(0, 1)] = Color::Black;
+        color[P(1, 1)] = Color::Black;
+        let mut clue = Grid::new(2, 2, NO_CLUE);
+        clue[P(0, 0)] = 1;
+        clue[P(1, 1)] = 1;
+
+        let minimized = minimize(&color, &clue, Symmetry::none(), &mut rng).unwrap();
+        assert_eq!(
+            (minimized[P(0, 0)] == NO_CLUE) as i32 + (minimized[P(1, 1)] == NO_CLUE) as i32,
+            1
+        );
+        assert!(Field::new(&color, &minimized).trial_and_error(2));
+
+        let dyad = Symmetry::from_names("dyad").unwrap();
+        let minimized = minimize(&color, &clue, dyad, &mut rng).unwrap();
+        assert_eq!(minimized, clue);
+
+        let no_clue = Grid::new(2, 2, NO_CLUE);
+        assert_eq!(count_solutions(&color, &no_clue, 10), 2);
+        assert!(minimize(&color, &no_clue, Symmetry::none(), &mut rng).is_none());
+    }
+
+    #[test]
+    fn test_explain_inconsistency() {
+        let answer = solve_url("https://puzz.link/p?dbchoco/6/6/poc4f1tgj6zl3h3g", 2);
+        assert_eq!(answer.contradiction(), None);
+        assert_eq!(answer.explain_inconsistency(2), None);
+
+        // A 2x2 grid can be split into either one block of size 2 or two blocks of size 1
+        let mut color = Grid::new(2, 2, Color::White);
+        color[P(0, 1)] = Color::Black;
+        color[P(1, 1)] = Color::Black;
+        let mut clue = Grid::new(2, 2, NO_CLUE);
+        clue[P(0, 0)] = 1;
+        clue[P(1, 0)] = 1;
+        clue[P(1, 1)] = 2;
+
+        let mut field = Field::new(&color, &clue);
+        assert!(!field.trial_and_error(2));
+        assert!(field.inconsistent());
+        assert!(field.contradiction().is_some());
+        assert_eq!(field.explain_inconsistency(2), Some(vec![P(1, 0), P(1, 1)]));
+    }
+
+    #[test]
+    fn test_contradiction() {
+        // A part without black cells
+        let color = Grid::new(1, 2, Color::White);
+        let clue = Grid::new(1, 2, NO_CLUE);
+        let mut field = Field::new(&color, &clue);
+        field.solve();
+        let contradiction = field.contradiction().unwrap();
+        assert_eq!(
+            contradiction.rule,
+            "unequal numbers of black and white cells in a part"
+        );
+        assert_eq!(contradiction.cells, vec![P(0, 0), P(0, 1)]);
+
+        // Decisions of trial and error are kept in the trail
+        let mut color = Grid::new(2, 2, Color::White);
+        color[P(0, 1)] = Color::Black;
+        color[P(1, 1)] = Color::Black;
+        let clue = Grid::new(2, 2, NO_CLUE);
+        let mut field = Field::new(&color, &clue);
+        field.decide_by_trial(LP(0, 1), Border::Line);
+        field.decide_border(LP(0, 1), Border::Blank);
+        let contradiction = field.contradiction().unwrap();
+        assert_eq!(contradiction.cells, vec![P(0, 0), P(0, 1)]);
+        assert_eq!(contradiction.decisions, vec![(LP(0, 1), Border::Line)]);
+
+        let mut field = Field::new(&color, &clue);
+        field.set_inconsistent();
+        assert!(field.inconsistent());
+        assert_eq!(
+            field.contradiction().unwrap().rule,
+            "marked inconsistent by the caller"
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use std::io::{self, BufRead, Write};
+
+use super::*;
+use crate::common::{Grid, LP};
+use crate::io::{
+    read_pzprv3_cells, read_pzprv3_header, write_pzprv3_borders, write_pzprv3_cells,
+    write_pzprv3_header, Pzprv3Header, ReadError,
+};
+
+/// Reads a problem in the pzprv3 format of `dbchoco`.
+///
+/// The file has a cell block, in which a cell is a clue number or `.` (no clue) prefixed by `#`
+/// if the cell is gray (`Color::Black`), followed by a border block of borders, which is ignored.
+pub fn read_pzprv3_problem<T: BufRead>(
+    reader: &mut T,
+) -> Result<(Grid<Color>, Grid<Clue>), ReadError> {
+    let header = read_pzprv3_header(reader, "dbchoco")?;
+    let cells = read_pzprv3_cells(
+        reader,
+        header.height,
+        header.width,
+        |token: &str| {
+            let (color, token) = match token.strip_prefix('#') {
+                Some(rest) => (Color::Black, rest),
+                None => (Color::White, token),
+            };
+            if token == "." {
+                return Ok((color, NO_CLUE));
+            }
+            match token.parse::<Clue>() {
+                Ok(n) if n > 0 => Ok((color, n)),
+                _ => Err(ReadError::InvalidValue),
+            }
+        },
+        (Color::White, NO_CLUE),
+    )?;
+
+    let mut color = Grid::new(header.height, header.width, Color::White);
+    let mut clue = Grid::new(header.height, header.width, NO_CLUE);
+    for i in 0..((header.height * header.width) as usize) {
+        color[i] = cells[i].0;
+        clue[i] = cells[i].1;
+    }
+    Ok((color, clue))
+}
+
+/// Writes a problem in the pzprv3 format of `dbchoco`, together with the borders of `answer`.
+/// A border is `1`, `-1` (no border) or `0` (undecided).
+pub fn write_pzprv3<T: Write>(
+    writer: &mut T,
+    color: &Grid<Color>,
+    clue: &Grid<Clue>,
+    answer: Option<&Field>,
+) -> io::Result<()> {
+    let (height, width) = (color.height(), color.width());
+    let header = Pzprv3Header {
+        version: 0,
+        height,
+        width,
+    };
+    write_pzprv3_header(writer, "dbchoco", &header)?;
+
+    let mut cells = Grid::new(height, width, (Color::White, NO_CLUE));
+    for i in 0..((height * width) as usize) {
+        cells[i] = (color[i], clue[i]);
+    }
+    write_pzprv3_cells(writer, &cells, |&(c, n)| {
+        let prefix = if c == Color::Black { "#" } else { "" };
+        if n > 0 {
+            format!("{}{}", prefix, n)
+        } else {
+            format!("{}.", prefix)
+        }
+    })?;
+
+    let mut borders = Grid::new(2 * height - 1, 2 * width - 1, Border::Undecided);
+    if let Some(answer) = answer {
+        for y in 0..(2 * height - 1) {
+            for x in 0..(2 * width - 1) {
+                if y % 2 != x % 2 {
+                    borders[LP(y, x)] = answer.border(LP(y, x));
+                }
+            }
+        }
+    }
+    write_pzprv3_borders(writer, &borders, false, |&b| {
+        String::from(match b {
+            Border::Line => "1",
+            Border::Blank => "-1",
+            Border::Undecided => "0",
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::P;
+
+    #[test]
+    fn test_pzprv3() {
+        let url = "https://puzz.link/p?dbchoco/6/6/vj801ovgk4r4g4l2h3j2";
+        let (color, clue) = crate::parse_url_dblchoco_internal(url).unwrap();
+
+        let mut dst = vec![];
+        write_pzprv3(&mut dst, &color, &clue, None).unwrap();
+        let dst = String::from_utf8(dst).unwrap();
+        assert_eq!(dst.lines().nth(4), Some("#. #. #. #. #. #4 "));
+        assert_eq!(
+            read_pzprv3_problem(&mut dst.as_bytes()).unwrap(),
+            (color.clone(), clue.clone())
+        );
+
+        let mut field = Field::new(&color, &clue);
+        field.trial_and_error(2);
+        let mut dst = vec![];
+        write_pzprv3(&mut dst, &color, &clue, Some(&field)).unwrap();
+        let dst = String::from_utf8(dst).unwrap();
+        let borders = dst.lines().skip(4 + 6).collect::<Vec<_>>();
+        assert_eq!(borders.len(), 6 + 5);
+        assert!(borders
+            .iter()
+            .all(|l| l.split_whitespace().all(|t| t != "0")));
+
+        let src = "pzprv3\ndbchoco\n1\n2\n#3 . \n0 \n";
+        let (color, clue) = read_pzprv3_problem(&mut src.as_bytes()).unwrap();
+        assert_eq!(color[P(0, 0)], Color::Black);
+        assert_eq!(clue[P(0, 0)], 3);
+        assert_eq!(color[P(0, 1)], Color::White);
+        assert!(read_pzprv3_problem(&mut "pzprv3\ndbchoco\n1\n2\n#3 #x \n".as_bytes()).is_err());
+    }
+}
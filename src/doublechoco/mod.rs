@@ -1,6 +1,10 @@
 mod field;
+mod io;
+mod sat;
 
 pub use self::field::*;
+pub use self::io::*;
+pub use self::sat::*;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum Color {
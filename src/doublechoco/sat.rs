@@ -0,0 +1,289 @@
+use super::*;
+use crate::common::{Grid, Regions, D, FOUR_NEIGHBOURS, LP, P};
+use crate::sat::{LazyConstraint, Model, Sat};
+use varisat::Lit;
+
+/// Requires that the borders divide the grid into valid blocks.
+struct Blocks {
+    color: Grid<Color>,
+    clue: Grid<Clue>,
+    line: Grid<Option<Lit>>,
+    size: Grid<Vec<Lit>>,
+}
+impl Blocks {
+    fn border_lit(&self, p: P, q: P) -> Lit {
+        self.line[LP::of_vertex(p) + (q - p)].unwrap()
+    }
+    /// Returns the literals which are true iff the size assigned to `p` differs from that in `model`.
+    fn size_differs(&self, model: &Model, p: P) -> Vec<Lit> {
+        self.size[p]
+            .iter()
+            .map(|&b| if model.value(b) { !b } else { b })
+            .collect()
+    }
+    fn size_value(&self, model: &Model, p: P) -> i32 {
+        self.size[p]
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| (model.value(b) as i32) << i)
+            .sum()
+    }
+}
+impl LazyConstraint for Blocks {
+    fn check(&self, model: &Model) -> Vec<Vec<Lit>> {
+        let height = self.color.height();
+        let width = self.color.width();
+        let mut border = Grid::new(height * 2 - 1, width * 2 - 1, false);
+        for i in 0..(border.height() * border.width()) as usize {
+            if let Some(l) = self.line[i] {
+                border[i] = model.value(l);
+            }
+        }
+        let regions = Regions::from_borders(&border);
+
+        let mut ret = vec![];
+        let mut has_inner_line = vec![false; regions.num_areas()];
+        for y in 0..height {
+            for x in 0..width {
+                let p = P(y, x);
+                let id = regions.area_id(p).unwrap();
+                for &d in &[D(1, 0), D(0, 1)] {
+                    let q = p + d;
+                    if !self.color.is_valid_p(q)
+                        || regions.area_id(q) != Some(id)
+                        || !model.value(self.border_lit(p, q))
+                    {
+                        continue;
+                    }
+                    // A line inside a block; some border on a path from `p` to `q` must be a line
+                    has_inner_line[id] = true;
+                    let mut prev = Grid::new(height, width, None);
+                    let mut queue = vec![p];
+                    prev[p] = Some(p);
+                    let mut i = 0;
+                    while prev[q].is_none() {
+                        let r = queue[i];
+                        i += 1;
+                        for &d in &FOUR_NEIGHBOURS {
+                            let s = r + d;
+                            if self.color.is_valid_p(s)
+                                && prev[s].is_none()
+                                && !model.value(self.border_lit(r, s))
+                            {
+                                prev[s] = Some(r);
+                                queue.push(s);
+                            }
+                        }
+                    }
+                    let mut clause = vec![!self.border_lit(p, q)];
+                    let mut r = q;
+                    while r != p {
+                        let s = prev[r].unwrap();
+                        clause.push(self.border_lit(s, r));
+                        r = s;
+                    }
+                    ret.push(clause);
+                }
+            }
+        }
+
+        for (id, area) in regions.areas().iter().enumerate() {
+            let size = self.size_value(model, area[0]);
+            let mut count_violated = false;
+            for &color in &[Color::Black, Color::White] {
+                let n = area.iter().filter(|&&p| self.color[p] == color).count() as i32;
+                if n > size {
+                    // Cells of `color` connected to `area[0]` are too many for the size
+                    let mut prev = Grid::new(height, width, None);
+                    let mut queue = vec![area[0]];
+                    prev[area[0]] = Some(area[0]);
+                    let mut clause = self.size_differs(model, area[0]);
+                    let mut n = (self.color[area[0]] == color) as i32;
+                    let mut i = 0;
+                    while n <= size {
+                        let r = queue[i];
+                        i += 1;
+                        for &d in &FOUR_NEIGHBOURS {
+                            let s = r + d;
+                            if n <= size
+                                && self.color.is_valid_p(s)
+                                && prev[s].is_none()
+                                && regions.area_id(s) == Some(id)
+                            {
+                                prev[s] = Some(r);
+                                queue.push(s);
+                                clause.push(self.border_lit(r, s));
+                                n += (self.color[s] == color) as i32;
+                            }
+                        }
+                    }
+                    ret.push(clause);
+                    count_violated = true;
+                } else if n < size {
+                    // The area is closed, so the block containing it is too small for the size
+                    let mut clause = self.size_differs(model, area[0]);
+                    for &p in area {
+                        for &d in &FOUR_NEIGHBOURS {
+                            let q = p + d;
+                            if self.color.is_valid_p(q) && regions.area_id(q) != Some(id) {
+                                clause.push(!self.border_lit(p, q));
+                            }
+                        }
+                    }
+                    ret.push(clause);
+                    count_violated = true;
+                }
+            }
+            if has_inner_line[id] || count_violated || is_valid_block(&self.color, &self.clue, area)
+            {
+                continue;
+            }
+            // Forbid exactly this block
+            let mut clause = vec![];
+            for &p in area {
+                for &d in &FOUR_NEIGHBOURS {
+                    let q = p + d;
+                    if !self.color.is_valid_p(q) {
+                        continue;
+                    }
+                    if regions.area_id(q) != Some(id) {
+                        clause.push(!self.border_lit(p, q));
+                    } else if d == D(1, 0) || d == D(0, 1) {
+                        clause.push(self.border_lit(p, q));
+                    }
+                }
+            }
+            ret.push(clause);
+        }
+        ret
+    }
+}
+
+/// Enumerates up to `limit` answers of the problem with the SAT solver.
+///
+/// Unlike `Field::trial_and_error`, this is a complete solver, so it can be used to check the
+/// uniqueness of an answer. Each answer is indexed in the same way as `Field::border`.
+pub fn solve_sat(color: &Grid<Color>, clue: &Grid<Clue>, limit: usize) -> Vec<Grid<Border>> {
+    let height = color.height();
+    let width = color.width();
+
+    let mut sat = Sat::new();
+    let mut line = Grid::new(height * 2 - 1, width * 2 - 1, None);
+    let mut keys = vec![];
+    for y in 0..(height * 2 - 1) {
+        for x in 0..(width * 2 - 1) {
+            if y % 2 != x % 2 {
+                let l = sat.new_lit();
+                line[LP(y, x)] = Some(l);
+                keys.push(l);
+            }
+        }
+    }
+
+    // No border ends at an inner vertex
+    for y in 0..(height - 1) {
+        for x in 0..(width - 1) {
+            let around = FOUR_NEIGHBOURS
+                .iter()
+                .map(|&d| line[LP(y * 2 + 1, x * 2 + 1) + d].unwrap())
+                .collect::<Vec<_>>();
+            sat.not_exactly_one(&around);
+        }
+    }
+
+    // Cells in the same block share the size of the halves of the block
+    let max_size = ((height * width / 2) as u64).max(1);
+    let n_bits = 64 - max_size.leading_zeros() as usize;
+    let mut size = Grid::new(height, width, vec![]);
+    for y in 0..height {
+        for x in 0..width {
+            let bits = sat.new_bits(n_bits);
+            sat.forbid_bits(&bits, 0);
+            for v in (max_size + 1)..(1 << n_bits) {
+                sat.forbid_bits(&bits, v);
+            }
+            if clue[P(y, x)] != NO_CLUE {
+                sat.fix_bits(&bits, clue[P(y, x)] as u64);
+            }
+            size[P(y, x)] = bits;
+        }
+    }
+    for y in 0..height {
+        for x in 0..width {
+            let p = P(y, x);
+            for &q in &[P(y + 1, x), P(y, x + 1)] {
+                if color.is_valid_p(q) {
+                    let l = line[LP::of_vertex(p) + (q - p)].unwrap();
+                    sat.equal_bits_if(!l, &size[p], &size[q]);
+                }
+            }
+        }
+    }
+
+    sat.add_lazy(Box::new(Blocks {
+        color: color.clone(),
+        clue: clue.clone(),
+        line: line.clone(),
+        size,
+    }));
+
+    let mut ret = vec![];
+    while ret.len() < limit {
+        let model = match sat.solve() {
+            Some(model) => model,
+            None => break,
+        };
+        let mut answer = Grid::new(height * 2 - 1, width * 2 - 1, Border::Undecided);
+        for i in 0..(answer.height() * answer.width()) as usize {
+            if let Some(l) = line[i] {
+                answer[i] = if model.value(l) {
+                    Border::Line
+                } else {
+                    Border::Blank
+                };
+            }
+        }
+        ret.push(answer);
+        sat.block(&model, &keys);
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_sat() {
+        {
+            let url = "https://puzz.link/p?dbchoco/6/6/poc4f1tgj6zl3h3g";
+            let (color, clue) = crate::parse_url_dblchoco_internal(url).unwrap();
+            let answers = solve_sat(&color, &clue, 2);
+            assert_eq!(answers.len(), 1);
+
+            let mut field = Field::new(&color, &clue);
+            assert!(field.trial_and_error(2));
+            for y in 0..(color.height() * 2 - 1) {
+                for x in 0..(color.width() * 2 - 1) {
+                    if y % 2 != x % 2 {
+                        assert_eq!(answers[0][LP(y, x)], field.border(LP(y, x)));
+                    }
+                }
+            }
+        }
+        {
+            // The whole grid, or the two rows
+            let mut color = Grid::new(2, 2, Color::White);
+            color[P(0, 1)] = Color::Black;
+            color[P(1, 1)] = Color::Black;
+            let mut clue = Grid::new(2, 2, NO_CLUE);
+            assert_eq!(solve_sat(&color, &clue, 3).len(), 2);
+
+            clue[P(0, 0)] = 2;
+            assert_eq!(solve_sat(&color, &clue, 3).len(), 1);
+
+            clue[P(1, 0)] = 1;
+            assert_eq!(solve_sat(&color, &clue, 3).len(), 0);
+        }
+    }
+}
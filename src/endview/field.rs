@@ -140,22 +140,28 @@ impl Field {
             }
         }
     }
-    pub fn trial_and_error(&mut self) {
+    /// Alternates `apply_methods` and trials of each candidate of each cell until neither
+    /// makes progress. Returns the number of times a trial left only one candidate of a cell.
+    pub fn trial_and_error(&mut self) -> i32 {
+        let mut n_trials = 0;
         loop {
             self.apply_methods();
             if self.inconsistent() {
                 break;
             }
-            if !self.trial_and_error_step() {
+            let n = self.trial_and_error_step();
+            if n == 0 {
                 break;
             }
+            n_trials += n;
         }
+        n_trials
     }
-    fn trial_and_error_step(&mut self) -> bool {
+    fn trial_and_error_step(&mut self) -> i32 {
         let size = self.size;
         let n_alpha = self.n_alpha;
 
-        let mut is_update = false;
+        let mut n_updates = 0;
         for y in 0..size {
             for x in 0..size {
                 let pos = P(y, x);
@@ -187,18 +193,18 @@ impl Field {
                     }
                 }
 
-                if valid_cands.len() == 0 {
+                if valid_cands.is_empty() {
                     self.inconsistent = true;
-                    return false;
+                    return 0;
                 }
                 if valid_cands.len() == 1 {
                     let only_cand = valid_cands.pop().unwrap();
                     *self = only_cand.1;
-                    is_update = true;
+                    n_updates += 1;
                 }
             }
         }
-        is_update
+        n_updates
     }
     /// Returns `pos`-th cell of group `gid`.
     fn group(&self, gid: i32, pos: i32) -> P {
@@ -1,6 +1,7 @@
 use super::super::P;
 use super::*;
 
+use crate::StableRng;
 use rand::Rng;
 
 pub fn generate<R: Rng>(size: i32, n_alpha: i32, rng: &mut R) -> Option<Problem> {
@@ -22,7 +23,7 @@ pub fn generate<R: Rng>(size: i32, n_alpha: i32, rng: &mut R) -> Option<Problem>
             }
         }
 
-        rng.shuffle(&mut update_cand);
+        rng.stable_shuffle(&mut update_cand);
 
         for &(loc, i, nxt) in &update_cand {
             let current_clue = current_problem.get_clue(loc, i);
@@ -39,7 +40,7 @@ pub fn generate<R: Rng>(size: i32, n_alpha: i32, rng: &mut R) -> Option<Problem>
             } else {
                 current_score = compute_score(&current_problem, &field);
                 keep_update = prev_score > current_score
-                    || rng.gen::<f64>() < ((prev_score - current_score) / temperature).exp();
+                    || rng.stable_f64() < ((prev_score - current_score) / temperature).exp();
             }
 
             if keep_update {
@@ -1,5 +1,5 @@
 use super::common::GraphSeparation;
-use super::{FiniteSearchQueue, Grid, D, LP, P};
+use super::{Contradiction, FiniteSearchQueue, Grid, D, LP, P};
 use std::iter::IntoIterator;
 use std::ops::{Deref, DerefMut, Index, IndexMut};
 
@@ -31,6 +31,7 @@ struct GridLoopItem {
 pub struct GridLoop {
     grid: Grid<GridLoopItem>,
     inconsistent: bool,
+    contradiction: Option<Contradiction<(LP, Edge)>>,
     fully_solved: bool,
     decided_line: i32,
     decided_edge: i32,
@@ -88,6 +89,7 @@ impl GridLoop {
         let mut ret = GridLoop {
             grid: grid,
             inconsistent: false,
+            contradiction: None,
             fully_solved: false,
             decided_line: 0,
             decided_edge: 0,
@@ -131,6 +133,11 @@ impl GridLoop {
     pub fn inconsistent(&self) -> bool {
         self.inconsistent
     }
+    /// Returns the first contradiction found. Its decisions are the edges which were decided
+    /// around the place of the violation, that is, in the 3x3 area of the lattice centered at it.
+    pub fn contradiction(&self) -> Option<&Contradiction<(LP, Edge)>> {
+        self.contradiction.as_ref()
+    }
     pub fn fully_solved(&self) -> bool {
         self.fully_solved
     }
@@ -175,12 +182,48 @@ impl GridLoop {
 
     // public modifier
     pub fn set_inconsistent(&mut self) {
+        self.record_contradiction("marked inconsistent by the caller", vec![], vec![]);
+    }
+    /// Marks `self` inconsistent because `rule` is violated around `pos`.
+    /// The cells adjacent to `pos` (or the cell itself) are recorded as the place of the violation.
+    pub fn set_contradiction(&mut self, rule: &'static str, pos: LP) {
+        let LP(y, x) = pos;
+        let mut cells = vec![];
+        let mut decisions = vec![];
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let p = LP(y + dy, x + dx);
+                if !self.is_valid_lp(p) {
+                    continue;
+                }
+                if p.0 % 2 == 1 && p.1 % 2 == 1 {
+                    cells.push(P(p.0 / 2, p.1 / 2));
+                } else if p.is_edge() && self.get_edge(p) != Edge::Undecided {
+                    decisions.push((p, self.get_edge(p)));
+                }
+            }
+        }
+        self.record_contradiction(rule, cells, decisions);
+    }
+    fn record_contradiction(
+        &mut self,
+        rule: &'static str,
+        cells: Vec<P>,
+        decisions: Vec<(LP, Edge)>,
+    ) {
+        if !self.inconsistent {
+            self.contradiction = Some(Contradiction {
+                rule,
+                cells,
+                decisions,
+            });
+        }
         self.inconsistent = true;
     }
     pub fn decide_edge<T: GridLoopField>(field: &mut T, pos: LP, status: Edge) {
         if !field.grid_loop().is_valid_lp(pos) {
             if status != Edge::Blank {
-                field.grid_loop().inconsistent = true;
+                field.grid_loop().set_contradiction("line outside the grid", pos);
             }
             return;
         }
@@ -192,7 +235,7 @@ impl GridLoop {
             return;
         }
         if current_status != Edge::Undecided {
-            field.grid_loop().inconsistent = true;
+            field.grid_loop().set_contradiction("edge decided both as line and blank", pos);
             return;
         }
 
@@ -300,7 +343,7 @@ impl GridLoop {
 
                     if n_lines_dbl > 0 {
                         if n_lines_dbl != grid.num_decided_lines() * 2 {
-                            grid.set_inconsistent();
+                            grid.set_contradiction("lines cannot be connected", LP(y, x));
                         }
                         return;
                     }
@@ -644,7 +687,8 @@ impl GridLoop {
             return;
         }
         if current_status != Edge::Undecided {
-            field.grid_loop().inconsistent = true;
+            let pos = field.grid_loop().grid.lp(id.0);
+            field.grid_loop().set_contradiction("edge decided both as line and blank", pos);
             return;
         }
 
@@ -742,7 +786,8 @@ impl GridLoop {
                 return;
             }
             _ => {
-                field.grid_loop().inconsistent = true;
+                let pos = field.grid_loop().grid.lp(origin.0);
+                field.grid_loop().set_contradiction("line and blank joined into a chain", pos);
                 return;
             }
         }
@@ -758,7 +803,8 @@ impl GridLoop {
                 }
             } else if status == Edge::Line {
                 if field.grid_loop().decided_line != item1.chain_size + item2.chain_size {
-                    field.grid_loop().inconsistent = true;
+                    let pos = field.grid_loop().grid.lp(origin.0);
+                    field.grid_loop().set_contradiction("loop closed leaving other lines", pos);
                     return;
                 } else {
                     field.grid_loop().fully_solved = true;
@@ -813,7 +859,7 @@ impl GridLoop {
         }
 
         if line.len() >= 3 {
-            field.grid_loop().inconsistent = true;
+            field.grid_loop().set_contradiction("branching line", pos);
             return;
         }
 
@@ -853,7 +899,7 @@ impl GridLoop {
             }
 
             if cand == -1 {
-                field.grid_loop().inconsistent = true;
+                field.grid_loop().set_contradiction("dead end", pos);
             } else if cand != -2 {
                 GridLoop::join(field, eid, EdgeId(cand as usize));
             }
@@ -1030,6 +1076,7 @@ mod tests {
         assert_eq!(grid_loop.num_decided_edges(), expected_decided_edge);
         assert_eq!(grid_loop.num_decided_lines(), expected_decided_line);
         assert_eq!(grid_loop.inconsistent(), inconsistent);
+        assert_eq!(grid_loop.contradiction().is_some(), inconsistent);
     }
 
     #[test]
@@ -1220,6 +1267,25 @@ mod tests {
         GridLoop::apply_inout_rule(&mut field);
 
         assert_eq!(field.inconsistent(), true);
+        assert!(field.contradiction().is_some());
+    }
+
+    #[test]
+    fn test_contradiction() {
+        let mut field = GridLoop::new(3, 3);
+        GridLoop::decide_edge(&mut field, LP(0, 1), Edge::Line);
+        GridLoop::decide_edge(&mut field, LP(0, 1), Edge::Blank);
+        let contradiction = field.contradiction().unwrap();
+        assert_eq!(contradiction.rule, "edge decided both as line and blank");
+        assert_eq!(contradiction.cells, vec![P(0, 0)]);
+        assert!(contradiction.decisions.contains(&(LP(0, 1), Edge::Line)));
+
+        let mut field = GridLoop::new(3, 3);
+        field.set_inconsistent();
+        assert_eq!(
+            field.contradiction().unwrap().rule,
+            "marked inconsistent by the caller"
+        );
     }
 
     #[test]
@@ -1,9 +1,12 @@
 use std::error;
 use std::fmt::{self, Display};
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, Write};
 
 use crate::common::{Grid, P};
 
+mod pzprv3;
+pub use self::pzprv3::*;
+
 /// The type for errors occurring in reading puzrs data.
 #[derive(Debug)]
 pub enum ReadError {
@@ -86,23 +89,53 @@ where
             .map_err(|_| ReadError::InvalidValue)?;
     }
 
-    let mut ret = Grid::new(height, width, default);
+    if height <= 0 || width <= 0 {
+        return Err(ReadError::InvalidValue);
+    }
 
-    for y in 0..height {
+    // The grid is allocated only after all cells are read so that a bogus header
+    // does not cause a huge allocation.
+    let mut cells = vec![];
+
+    for _ in 0..height {
         next_valid_line(reader, &mut buffer)?;
         let mut row = buffer.trim_end().split(' ');
 
-        for x in 0..width {
+        for _ in 0..width {
             let elem = row.next().ok_or(ReadError::InvalidFormat)?;
             let converted_elem = converter(elem.as_ref())?;
 
-            ret[P(y, x)] = converted_elem;
+            cells.push(converted_elem);
         }
     }
 
+    let mut ret = Grid::new(height, width, default);
+    for (i, elem) in cells.into_iter().enumerate() {
+        ret[i] = elem;
+    }
+
     Ok(ret)
 }
 
+/// Writes `grid` in the format accepted by `read_grid`.
+pub fn write_grid<W, F, T>(writer: &mut W, grid: &Grid<T>, converter: F) -> io::Result<()>
+where
+    W: Write,
+    F: Fn(&T) -> String,
+    T: Clone,
+{
+    writeln!(writer, "{} {}", grid.height(), grid.width())?;
+
+    for y in 0..grid.height() {
+        let row = (0..grid.width())
+            .map(|x| converter(&grid[P(y, x)]))
+            .collect::<Vec<_>>();
+        writeln!(writer, "{}", row.join(" "))?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -123,4 +156,25 @@ p q r s
         assert_eq!(grid.width(), 4);
         assert_eq!(grid[P(1, 2)], "z".to_string());
     }
+
+    #[test]
+    fn test_write_grid() {
+        let mut src = "2 3\n1 2 3\n4 5 6\n".as_bytes();
+        let grid = read_grid(&mut src, |s| s.parse::<i32>().map_err(|_| ReadError::InvalidValue), 0)
+            .unwrap();
+
+        let mut dest = vec![];
+        write_grid(&mut dest, &grid, |n| n.to_string()).unwrap();
+        assert_eq!(String::from_utf8(dest).unwrap(), "2 3\n1 2 3\n4 5 6\n");
+    }
+
+    #[test]
+    fn test_read_grid_invalid_size() {
+        // Found by fuzzing: these used to panic or allocate a huge grid
+        let mut src = "-1 3\n".as_bytes();
+        assert!(read_grid(&mut src, |s| Ok(s.to_string()), String::new()).is_err());
+
+        let mut src = "40000 40000\n1\n".as_bytes();
+        assert!(read_grid(&mut src, |s| Ok(s.to_string()), String::new()).is_err());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,287 @@
+//! Primitives of the pzprv3 format, in which pzpr.js and puzz.link save problems together with
+//! their progress.
+//!
+//! A file begins with the header
+//!
+//! ```text
+//! pzprv3[.<version>]
+//! <genre>
+//! <height>
+//! <width>
+//! ```
+//!
+//! which is followed by blocks specific to the genre:
+//! - a cell block has `height` rows of `width` tokens;
+//! - a border block has the borders between horizontally adjacent cells in `height` rows,
+//!   followed by the borders between vertically adjacent cells. If outer borders are included,
+//!   the rows are `width + 1` and `height + 1` respectively; otherwise, `width - 1` and
+//!   `height - 1`.
+//!
+//! Each token is followed by a space. Anything after the blocks, such as the history of moves,
+//! is ignored by the readers.
+
+use std::io::{self, BufRead, Write};
+
+use super::{next_valid_line, ReadError};
+use crate::common::{Grid, LP};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pzprv3Header {
+    pub version: i32,
+    pub height: i32,
+    pub width: i32,
+}
+
+/// Returns whether the input starts with a pzprv3 header, without consuming it.
+pub fn is_pzprv3<R: BufRead + ?Sized>(reader: &mut R) -> io::Result<bool> {
+    Ok(reader.fill_buf()?.starts_with(b"pzprv3"))
+}
+
+/// Reads the header of a file of `genre`.
+pub fn read_pzprv3_header<R: BufRead>(
+    reader: &mut R,
+    genre: &str,
+) -> Result<Pzprv3Header, ReadError> {
+    let mut buffer = String::new();
+
+    next_valid_line(reader, &mut buffer)?;
+    let version = match buffer.trim().strip_prefix("pzprv3") {
+        Some("") => 0,
+        Some(v) => v
+            .strip_prefix('.')
+            .ok_or(ReadError::InvalidFormat)?
+            .parse::<i32>()
+            .map_err(|_| ReadError::InvalidValue)?,
+        None => return Err(ReadError::InvalidFormat),
+    };
+
+    next_valid_line(reader, &mut buffer)?;
+    if buffer.trim() != genre {
+        return Err(ReadError::InvalidFormat);
+    }
+
+    let mut size = [0; 2];
+    for s in &mut size {
+        next_valid_line(reader, &mut buffer)?;
+        *s = buffer
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| ReadError::InvalidValue)?;
+        if *s <= 0 {
+            return Err(ReadError::InvalidValue);
+        }
+    }
+
+    Ok(Pzprv3Header {
+        version,
+        height: size[0],
+        width: size[1],
+    })
+}
+
+pub fn write_pzprv3_header<W: Write>(
+    writer: &mut W,
+    genre: &str,
+    header: &Pzprv3Header,
+) -> io::Result<()> {
+    if header.version == 0 {
+        writeln!(writer, "pzprv3")?;
+    } else {
+        writeln!(writer, "pzprv3.{}", header.version)?;
+    }
+    writeln!(writer, "{}", genre)?;
+    writeln!(writer, "{}", header.height)?;
+    writeln!(writer, "{}", header.width)
+}
+
+/// Reads rows of tokens, whose lengths are given by `row_lengths`, and converts the tokens.
+fn read_rows<R, I, F, T>(reader: &mut R, row_lengths: I, converter: F) -> Result<Vec<T>, ReadError>
+where
+    R: BufRead,
+    I: Iterator<Item = i32>,
+    F: Fn(&str) -> Result<T, ReadError>,
+{
+    let mut buffer = String::new();
+    let mut ret = vec![];
+    for len in row_lengths {
+        next_valid_line(reader, &mut buffer)?;
+        let mut tokens = buffer.split_whitespace();
+        for _ in 0..len {
+            ret.push(converter(tokens.next().ok_or(ReadError::InvalidFormat)?)?);
+        }
+    }
+    Ok(ret)
+}
+
+fn write_rows<W, F, T>(
+    writer: &mut W,
+    grid: &Grid<T>,
+    rows: &[Vec<LP>],
+    converter: F,
+) -> io::Result<()>
+where
+    W: Write,
+    F: Fn(&T) -> String,
+    T: Clone,
+{
+    for row in rows {
+        let line = row
+            .iter()
+            .map(|&pos| converter(&grid[pos]) + " ")
+            .collect::<String>();
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Cells in the order of a cell block. `LP(y, x)` of a grid of cells is the cell `P(y, x)`.
+fn cell_rows(height: i32, width: i32) -> Vec<Vec<LP>> {
+    (0..height)
+        .map(|y| (0..width).map(|x| LP(y, x)).collect())
+        .collect()
+}
+
+/// Borders in the order of a border block. If `outer` is `true`, `LP(2y + 1, 2x + 1)` is the
+/// cell `P(y, x)`; otherwise, `LP(2y, 2x)` is.
+fn border_rows(height: i32, width: i32, outer: bool) -> Vec<Vec<LP>> {
+    let o = if outer { 1 } else { 0 };
+    let mut ret = vec![];
+    for y in 0..height {
+        ret.push(
+            ((1 - o)..(width + o))
+                .map(|x| LP(2 * y + o, 2 * x - 1 + o))
+                .collect(),
+        );
+    }
+    for y in (1 - o)..(height + o) {
+        ret.push((0..width).map(|x| LP(2 * y - 1 + o, 2 * x + o)).collect());
+    }
+    ret
+}
+
+/// Reads a cell block.
+pub fn read_pzprv3_cells<R, F, T>(
+    reader: &mut R,
+    height: i32,
+    width: i32,
+    converter: F,
+    default: T,
+) -> Result<Grid<T>, ReadError>
+where
+    R: BufRead,
+    F: Fn(&str) -> Result<T, ReadError>,
+    T: Clone,
+{
+    // The grid is allocated only after all cells are read, as in `read_grid`
+    let cells = read_rows(reader, (0..height).map(|_| width), converter)?;
+    let mut ret = Grid::new(height, width, default);
+    for (i, cell) in cells.into_iter().enumerate() {
+        ret[i] = cell;
+    }
+    Ok(ret)
+}
+
+pub fn write_pzprv3_cells<W, F, T>(writer: &mut W, grid: &Grid<T>, converter: F) -> io::Result<()>
+where
+    W: Write,
+    F: Fn(&T) -> String,
+    T: Clone,
+{
+    write_rows(
+        writer,
+        grid,
+        &cell_rows(grid.height(), grid.width()),
+        converter,
+    )
+}
+
+/// Reads a border block of a grid of `height` rows and `width` columns.
+///
+/// The returned grid is indexed by `LP`, in which the cell `P(y, x)` is `LP(2y + 1, 2x + 1)` of
+/// a grid of size `(2 * height + 1) * (2 * width + 1)` if `outer` is `true`, and `LP(2y, 2x)` of
+/// a grid of size `(2 * height - 1) * (2 * width - 1)` otherwise.
+pub fn read_pzprv3_borders<R, F, T>(
+    reader: &mut R,
+    height: i32,
+    width: i32,
+    outer: bool,
+    converter: F,
+    default: T,
+) -> Result<Grid<T>, ReadError>
+where
+    R: BufRead,
+    F: Fn(&str) -> Result<T, ReadError>,
+    T: Clone,
+{
+    let o = if outer { 1 } else { 0 };
+    let row_lengths = (0..height)
+        .map(|_| width - 1 + 2 * o)
+        .chain(((1 - o)..(height + o)).map(|_| width));
+    let borders = read_rows(reader, row_lengths, converter)?;
+    let mut ret = Grid::new(2 * height - 1 + 2 * o, 2 * width - 1 + 2 * o, default);
+    for (&pos, border) in border_rows(height, width, outer)
+        .iter()
+        .flatten()
+        .zip(borders)
+    {
+        ret[pos] = border;
+    }
+    Ok(ret)
+}
+
+/// Writes a border block of `grid`, which is indexed as described in `read_pzprv3_borders`.
+pub fn write_pzprv3_borders<W, F, T>(
+    writer: &mut W,
+    grid: &Grid<T>,
+    outer: bool,
+    converter: F,
+) -> io::Result<()>
+where
+    W: Write,
+    F: Fn(&T) -> String,
+    T: Clone,
+{
+    let o = if outer { 1 } else { -1 };
+    let height = (grid.height() - o) / 2;
+    let width = (grid.width() - o) / 2;
+    write_rows(writer, grid, &border_rows(height, width, outer), converter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pzprv3_header() {
+        let mut src = "pzprv3.1\nslither\n3\n2\n".as_bytes();
+        assert!(is_pzprv3(&mut src).unwrap());
+        let header = read_pzprv3_header(&mut src, "slither").unwrap();
+        assert_eq!(
+            header,
+            Pzprv3Header {
+                version: 1,
+                height: 3,
+                width: 2
+            }
+        );
+
+        let mut dst = vec![];
+        write_pzprv3_header(&mut dst, "slither", &header).unwrap();
+        assert_eq!(String::from_utf8(dst).unwrap(), "pzprv3.1\nslither\n3\n2\n");
+
+        assert!(!is_pzprv3(&mut "3 2\n".as_bytes()).unwrap());
+        for src in &[
+            "pzprv3\nnumlin\n3\n2\n",
+            "pzprv3.\nslither\n3\n2\n",
+            "pzprv4\nslither\n3\n2\n",
+            "pzprv3\nslither\n0\n2\n",
+            "pzprv3\nslither\n3\n",
+        ] {
+            assert!(
+                read_pzprv3_header(&mut src.as_bytes(), "slither").is_err(),
+                "{}",
+                src
+            );
+        }
+    }
+}
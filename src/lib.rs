@@ -1,14 +1,26 @@
 use wasm_bindgen::prelude::*;
-use wasm_bindgen::{Clamped, JsCast};
+use wasm_bindgen::JsCast;
 use serde::{Serialize, Deserialize};
 
-mod common;
-mod io;
-
-mod doublechoco;
-mod numberlink;
-mod grid_loop;
-mod slitherlink;
+pub mod common;
+pub mod io;
+
+pub mod doublechoco;
+pub mod dosufuwa;
+pub mod endview;
+pub mod numberlink;
+pub mod grid_loop;
+pub mod slitherlink;
+pub mod logic;
+pub mod sat;
+pub mod pack;
+pub mod render;
+pub mod seed;
+pub mod session;
+pub mod url;
+pub mod cli;
+
+pub use seed::{Seed, SeedRng, StableRng};
 
 use common::*;
 use doublechoco::*;
@@ -34,69 +46,22 @@ struct DblchocoSol {
   decided_flag: bool
 }
 
-fn parse_url_dblchoco_internal(url: &str) -> (Grid<Color>, Grid<Clue>) {
-    let tokens = url.split("/").collect::<Vec<_>>();
-    let width = tokens[tokens.len() - 3].parse::<i32>().unwrap();
-    let height = tokens[tokens.len() - 2].parse::<i32>().unwrap();
-    let body = tokens[tokens.len() - 1].chars().collect::<Vec<char>>();
-
-    let mut color = Grid::new(height, width, Color::White);
-    let mut clue = Grid::new(height, width, NO_CLUE);
-
-    let mut idx = 0usize;
-    for i in 0..((height * width + 4) / 5) {
-        let v = body[idx];
-        idx += 1;
-        let bits = if '0' <= v && v <= '9' {
-            (v as i32) - ('0' as i32)
-        } else {
-            (v as i32) - ('a' as i32) + 10
-        };
-        for j in 0..5 {
-            let p = i * 5 + j;
-            let y = p / width;
-            let x = p % width;
-            if y < height {
-                color[P(y, x)] = if (bits & (1 << (4 - j))) != 0 {
-                    Color::Black
-                } else {
-                    Color::White
-                };
-            }
-        }
-    }
-    fn convert_hex(v: char) -> i32 {
-        if '0' <= v && v <= '9' {
-            (v as i32) - ('0' as i32)
-        } else {
-            (v as i32) - ('a' as i32) + 10
-        }
-    }
-    let mut pos = 0;
-    while idx < body.len() {
-        if 'g' <= body[idx] {
-            pos += (body[idx] as i32) - ('f' as i32);
-            idx += 1;
-        } else {
-            let val;
-            if body[idx] == '-' {
-                val = convert_hex(body[idx + 1]) * 16 + convert_hex(body[idx + 2]);
-                idx += 3;
-            } else {
-                val = convert_hex(body[idx]);
-                idx += 1;
-            }
-            clue[P(pos / width, pos % width)] = val;
-            pos += 1;
-        }
-    }
+/// Decodes a puzz.link URL of Double Choco. The format is described in `url::DoubleChoco`.
+pub fn parse_url_dblchoco_internal(url: &str) -> Option<(Grid<Color>, Grid<Clue>)> {
+    url::decode::<url::DoubleChoco>(url)
+}
 
-    (color, clue)
+/// Inverse of `parse_url_dblchoco_internal`.
+pub fn encode_url_dblchoco(color: &Grid<Color>, clue: &Grid<Clue>) -> String {
+    url::encode::<url::DoubleChoco>(&(color.clone(), clue.clone()))
 }
 
 #[wasm_bindgen]
 pub fn parse_url_dblchoco(url: &str) -> String {
-  let (color, clue) = parse_url_dblchoco_internal(url);
+  let (color, clue) = match parse_url_dblchoco_internal(url) {
+    Some(problem) => problem,
+    None => return "".to_string(),
+  };
 
   let mut color_vec = vec![];
 
@@ -130,7 +95,10 @@ pub fn parse_url_dblchoco(url: &str) -> String {
 
 #[wasm_bindgen]
 pub fn solve_dblchoco(url: &str, depth: i32) -> String {
-  let (color, clue) = parse_url_dblchoco_internal(url);
+  let (color, clue) = match parse_url_dblchoco_internal(url) {
+    Some(problem) => problem,
+    None => return "".to_string(),
+  };
 
   let height = color.height();
   let width = color.width();
@@ -187,7 +155,7 @@ pub fn solve_dblchoco(url: &str, depth: i32) -> String {
   https://puzz.link/p?dbchoco/12/12/7orhfgfc3i1ugsce2v0jgds3t3m7o2g1i3j2i3h51g1g3o5o5g6h4i3k61h4h32k2h3n4j3g7g6g2h4w5j7h23g3g35g3p3
   https://puzz.link/p?dbchoco/8/8/0c5hu1vlvn4hgm45h6h5p6g5o6p5i5g5i5j2
 
-  こける:
+  こける (1 以上なら O):
   https://puzz.link/p?dbchoco/6/6/poc4f1tgj6zl3h3g
 
 */
@@ -240,7 +208,7 @@ pub fn solve_numlin(url: String) -> String {
   let opt = parse_url_numlin_internal(url);
 
   if let Some(clue) = opt {
-    let ans = numberlink::solve2(&clue, None, false, false);
+    let ans = numberlink::solve2(&clue, &numberlink::SolverOption::default());
     let lines = ans.answers;
 
     let mut sol_vec: Vec<Vec<Vec<usize>>> = vec![];
@@ -258,9 +226,9 @@ pub fn solve_numlin(url: String) -> String {
         }
       }
 
-      for i in 0..width*(height-1) {
-        let col = i/(height-1);
-        let row = i%(height-1);
+      for i in 0..(height-1)*width {
+        let row = i/width;
+        let col = i%width;
 
         if line.down(P(row as i32, col as i32)) {
           sol_vec.push(vec![vec![row, col], vec![row+1, col]]);
@@ -278,128 +246,435 @@ pub fn solve_numlin(url: String) -> String {
   }
 }
 
-fn parse_url_numlin_internal(url: String) -> Option<Grid<numberlink::Clue>> {
-  let splitter = '/';
-  let params: Vec<String> = url.split(splitter).map(|s| s.to_string()).collect();
-  let length = params.len();
-
-  if length < 3 {
-      return None;
-  }
+/// Decodes a puzz.link URL of the form `.../numlin/<width>/<height>/<body>`.
+/// Walls and bridges are read from the sections described in `url::Numberlink`.
+pub fn parse_url_numlin_internal(url: String) -> Option<Grid<numberlink::Clue>> {
+  url::decode::<url::Numberlink>(&url)
+}
 
-  let width = params[length-3].parse().unwrap_or(0);
-  let height = params[length-2].parse().unwrap_or(0);
+/// Inverse of `parse_url_numlin_internal`.
+pub fn encode_url_numlin(clue: &Grid<numberlink::Clue>) -> String {
+  url::encode::<url::Numberlink>(clue)
+}
 
-  if width <= 0 || height <= 0 {
-      return None;
+fn main() {
+  let args = env::args().collect::<Vec<_>>();
+  if args.len() >= 2 {
+    if let Err(err) = cli::run(&args[1..]) {
+      eprintln!("{}", err);
+      std::process::exit(1);
+    }
+    return;
   }
 
-  let field_code = params[length-1].clone();
+  let url = "https://puzz.link/p?dbchoco/11/10/00333rpvsvufv3v0v07010i4i4j4h4g1h1l4k3o2q6o2g1i4i5u3i1t";
+  // https://puzz.link/p?dbchoco/8/8/1tkv17m0f4pheu1j2j6j62g2u3i1p (0 でも O)
 
-  if !is_valid_code(&field_code) {
-      return None;
-  }
+  let sol = solve_dblchoco(url, 1000);
 
-  decode_field(width, height, field_code)
+  println!("{:?}", sol);
+}
+#[derive(Serialize, Deserialize, Debug)]
+struct SlitherProblem {
+  clue: Vec<i32>,
+  width: usize,
+  height: usize,
+  url: String,
+  seed: String,
 }
 
-fn is_valid_code(code: &String) -> bool {
-  return code.chars().all(|ch| char::is_alphanumeric(ch) || ch == '-');
+/// Returns the seed parsed from `seed` if it is for `genre`.
+fn parse_seed_for(seed: &str, genre: &str) -> Option<Seed> {
+  seed.parse::<Seed>().ok().filter(|seed| seed.genre == genre)
 }
 
-fn decode_field(width: usize, height: usize, code: String) -> Option<Grid<numberlink::Clue>> {
-  let list: &Vec<char> = &code.chars().collect();
-  let mut index: usize = 0;
-  let mut i: usize = 0;
-  let mut j: usize = 0;
+/// Generates a Slitherlink problem with a unique solution from `seed` such as `sl-10x10-8a3f`,
+/// which also gives the size of the problem (see `seed::Seed`).
+/// `symmetry` is a comma-separated list of `dyad`, `tetrad`, `horizontal` and `vertical`.
+/// Returns an empty string if the arguments are invalid or no problem is found.
+#[wasm_bindgen]
+pub fn generate_slither(seed: &str, num_clues: i32, symmetry: &str) -> String {
+  let symmetry = match Symmetry::from_names(symmetry) {
+    Some(symmetry) => symmetry,
+    None => return "".to_string(),
+  };
+  let seed = match parse_seed_for(seed, "sl") {
+    Some(seed) => seed,
+    None => return "".to_string(),
+  };
+  let (height, width) = (seed.height, seed.width);
+
+  let mut rng = seed.rng();
+  let dic = slitherlink::Dictionary::shared();
+
+  match slitherlink::generate_problem(height, width, num_clues, symmetry, dic, 100, &mut rng) {
+    Some(problem) => {
+      let payload = SlitherProblem {
+        clue: (0..(height * width) as usize).map(|i| problem[i].0).collect(),
+        width: width as usize,
+        height: height as usize,
+        url: slitherlink::encode_url(&problem),
+        seed: seed.to_string(),
+      };
 
-  let mut res: Vec<Vec<usize>> = vec![vec![0; width as usize]; height as usize];
+      serde_json::to_string(&payload).unwrap()
+    },
+    None => "".to_string(),
+  }
+}
 
-  while index < list.len() {
-      while let Some(num) = get_num(&mut index, list) {
-          res[i as usize][j as usize] = num;
+/// Options of `generate_numlin`. Omitted fields take their default values.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+struct NumlinGenOption {
+  chain_threshold: i32,
+  forbid_adjacent_clue: bool,
+  symmetry: String,
+  clue_limit: Option<i32>,
+  prioritized_extension: bool,
+  empty_width: i32,
+  corner: Option<(i32, i32)>,
+  min_steps: u64,
+  max_steps: Option<u64>,
+  min_clues: Option<i32>,
+  time_limit_ms: Option<u64>,
+}
 
-          j += 1;
+impl Default for NumlinGenOption {
+  fn default() -> NumlinGenOption {
+    NumlinGenOption {
+      chain_threshold: 3,
+      forbid_adjacent_clue: false,
+      symmetry: String::new(),
+      clue_limit: None,
+      prioritized_extension: false,
+      empty_width: 0,
+      corner: None,
+      min_steps: 0,
+      max_steps: None,
+      min_clues: None,
+      time_limit_ms: Some(10000),
+    }
+  }
+}
 
-          if j >= width {
-              j = 0;
-              i += 1;
-          }
+#[derive(Serialize, Deserialize, Debug)]
+struct NumlinProblem {
+  clue: Vec<i32>,
+  width: usize,
+  height: usize,
+  url: String,
+  n_steps: u64,
+  seed: String,
+}
 
-          if index >= list.len() {
-              break;
-          }
-      }
+/// Generates a Numberlink problem with a unique solution whose difficulty, measured by
+/// the number of search steps of `numberlink::solve2`, is in the requested range.
+/// `seed` is such as `nl-10x10-8a3f` and also gives the size of the problem (see `seed::Seed`).
+///
+/// `options` is a JSON object with any of the following fields:
+/// `chain_threshold`, `forbid_adjacent_clue`, `symmetry` (as in `generate_slither`),
+/// `clue_limit`, `prioritized_extension`, `empty_width`, `corner` (`[low, high]`),
+/// `min_steps`, `max_steps`, `min_clues` and `time_limit_ms` (10 seconds by default).
+/// The clues are placed symmetrically if `symmetry` is given, but `tetrad` cannot be
+/// satisfied on squares of odd size (see `numberlink::is_symmetry_feasible`).
+/// Returns an empty string if the arguments are invalid or no problem is found in time.
+#[wasm_bindgen]
+pub fn generate_numlin(seed: &str, options: &str) -> String {
+  let options = match serde_json::from_str::<NumlinGenOption>(options) {
+    Ok(options) => options,
+    Err(_) => return "".to_string(),
+  };
+  let symmetry = match Symmetry::from_names(&options.symmetry) {
+    Some(symmetry) => symmetry,
+    None => return "".to_string(),
+  };
+  let seed = match parse_seed_for(seed, "nl") {
+    Some(seed) => seed,
+    None => return "".to_string(),
+  };
+  let (height, width) = (seed.height, seed.width);
+
+  let mut rng = seed.rng();
+  let endpoint_constraint = numberlink::generate_endpoint_constraint(
+    height, width, options.empty_width, options.corner, symmetry, &mut rng,
+  );
+  let opt = numberlink::GeneratorOption {
+    chain_threshold: options.chain_threshold,
+    endpoint_constraint: Some(&endpoint_constraint),
+    forbid_adjacent_clue: options.forbid_adjacent_clue,
+    symmetry,
+    clue_limit: options.clue_limit,
+    prioritized_extension: options.prioritized_extension,
+  };
+  let difficulty = numberlink::DifficultyOption {
+    min_steps: options.min_steps,
+    max_steps: options.max_steps,
+    min_clues: options.min_clues,
+    time_limit: options.time_limit_ms.map(std::time::Duration::from_millis),
+  };
+
+  let mut generator = numberlink::PlacementGenerator::new(height, width);
+  match generator.generate_with_difficulty(&opt, &difficulty, &mut rng) {
+    Some((problem, n_steps)) => {
+      let payload = NumlinProblem {
+        clue: (0..(height * width) as usize).map(|i| problem[i].0).collect(),
+        width: width as usize,
+        height: height as usize,
+        url: encode_url_numlin(&problem),
+        n_steps,
+        seed: seed.to_string(),
+      };
 
-      consume(&mut index, &mut i, &mut j, width, list);
+      serde_json::to_string(&payload).unwrap()
+    },
+    None => "".to_string(),
   }
+}
 
-  let mut clue = Grid::new(height as i32, width as i32, numberlink::Clue(0));
+#[derive(Deserialize, Debug)]
+struct DrawState {
+  url: String,
+  #[serde(default)]
+  sol: serde_json::Value,
+}
 
-  for i in 0..height {
-    for j in 0..width {
-      clue[i*width+j] = numberlink::Clue(res[i][j] as i32);
-    }
+/// Draws a problem, and its answer if given, on the canvas whose id is `canvas_id`.
+///
+/// `genre` is one of `dblchoco`, `slither` and `numlin`. `state` is a JSON object with the URL
+/// of the problem in `url` and optionally the answer in `sol`. For Double Choco and Numberlink,
+/// `sol` is in the same form as the one returned by `solve_dblchoco` and `solve_numlin`.
+/// For Slitherlink, `sol` is a string with a character for each edge of the
+/// `(2 * height + 1) * (2 * width + 1)` lattice in row-major order:
+/// `-` for a line, `x` for a blank and anything else for an undecided edge.
+/// Returns `false` if the arguments are invalid or the canvas is not found.
+#[wasm_bindgen]
+pub fn draw(genre: &str, canvas_id: &str, state: &str) -> bool {
+  match draw_picture(genre, state) {
+    Some(picture) => draw_on_canvas(&picture, canvas_id),
+    None => false,
   }
-
-  Some(clue)
 }
 
-fn get_num(index: &mut usize, list: &Vec<char>) -> Option<usize> {
-  let ch = list[*index as usize];
+/// Draws `picture` on the canvas whose id is `canvas_id`, scaled to fit the canvas.
+pub(crate) fn draw_on_canvas(picture: &render::Picture, canvas_id: &str) -> bool {
+  let canvas = web_sys::window()
+    .and_then(|window| window.document())
+    .and_then(|document| document.get_element_by_id(canvas_id))
+    .and_then(|element| element.dyn_into::<web_sys::HtmlCanvasElement>().ok());
+  let canvas = match canvas {
+    Some(canvas) => canvas,
+    None => return false,
+  };
+  let ctx = canvas.get_context("2d").ok()
+    .and_then(|ctx| ctx)
+    .and_then(|ctx| ctx.dyn_into::<web_sys::CanvasRenderingContext2d>().ok());
 
-  if ch == '-' {
-      *index += 1;
-      let mut res = 0;
+  match ctx {
+    Some(ctx) => picture.paint(&ctx, canvas.width() as f64, canvas.height() as f64).is_ok(),
+    None => false,
+  }
+}
 
-      while res*16 < 100 && *index < list.len() && list[*index].is_digit(16) {
-          res *= 16;
-          res += list[*index].to_digit(16).unwrap();
-          *index += 1;
+fn draw_picture(genre: &str, state: &str) -> Option<render::Picture> {
+  let state: DrawState = serde_json::from_str(state).ok()?;
+  let mut session = session::Session::open(genre, &state.url)?;
+  let height = session.height();
+  let width = session.width();
+  let has_answer = !state.sol.is_null();
+
+  if genre == "numlin" && has_answer {
+    let arcs: Vec<[[i32; 2]; 2]> = serde_json::from_value(state.sol).ok()?;
+    for &[[y1, x1], [y2, x2]] in &arcs {
+      if (y2 - y1).abs() + (x2 - x1).abs() != 1 || !session.set_edge(y1 + y2 + 1, x1 + x2 + 1, 1) {
+        return None;
       }
+    }
+  } else if let Some(sol) = state.sol.as_str() {
+    // Double Choco has no outer edges in `sol`
+    let (lo, y_hi, x_hi) = if genre == "dblchoco" {
+      (1, height * 2, width * 2)
+    } else {
+      (0, height * 2 + 1, width * 2 + 1)
+    };
+    let mut sol = sol.chars();
+    for y in lo..y_hi {
+      for x in lo..x_hi {
+        if y % 2 == x % 2 {
+          continue;
+        }
+        // Edges given in the problem cannot be set, and are drawn anyway
+        match sol.next()? {
+          '-' => session.set_edge(y, x, 1),
+          'x' => session.set_edge(y, x, 2),
+          _ => true,
+        };
+      }
+    }
+  }
 
-      return Some(res as usize);
-  } else if ch.is_digit(16) {
-      *index += 1;
-
-      return match ch.to_digit(16) {
-          Some(num) => Some(num as usize),
-          None => None,
-      };
+  if has_answer {
+    Some(session.answer_picture())
   } else {
-      return None;
+    Some(session.picture())
   }
 }
 
-fn consume(index: &mut usize, i: &mut usize, j: &mut usize, width: usize, list: &Vec<char>) {
-  let length = list.len();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-  while *index < length && !(list[*index as usize]).is_digit(16) {
-      let ch = list[*index as usize];
-      let value = (ch as i32) - ('f' as i32);
+    #[test]
+    fn test_dblchoco_url_round_trip() {
+        let url = "https://puzz.link/p?dbchoco/6/6/poc4f1tgj6zl3h3g";
+        let (color, clue) = parse_url_dblchoco_internal(url).unwrap();
+        assert_eq!(encode_url_dblchoco(&color, &clue), url);
 
-      if value <= 0 {
-          return;
-      }
+        let url = "https://puzz.link/p?dbchoco/2/2/0-1fi";
+        let (color, clue) = parse_url_dblchoco_internal(url).unwrap();
+        assert_eq!(clue[P(0, 0)], 31);
+        assert_eq!(encode_url_dblchoco(&color, &clue), url);
+    }
 
-      let value: usize = value as usize;
+    #[test]
+    fn test_dblchoco_url_invalid() {
+        // Found by fuzzing: malformed URLs must be rejected instead of panicking
+        for url in &[
+            "dbchoco/3/3/",
+            "dbchoco/x/3/00",
+            "dbchoco/-1/1/0",
+            "dbchoco/2/2/0-1",
+            "dbchoco/1/1/011",
+            "dbchoco/1/1/0{",
+            "dbchoco/1/1/w",
+            "/",
+        ] {
+            assert!(parse_url_dblchoco_internal(url).is_none(), "{}", url);
+        }
+    }
 
-      *j += value;
+    #[test]
+    fn test_numlin_url_round_trip() {
+        let url = "https://puzz.link/p?numlin/5/5/1g2h3i-10j1h2i3i";
+        let clue = parse_url_numlin_internal(url.to_string()).unwrap();
+        assert_eq!(clue[P(0, 0)], numberlink::Clue(1));
+        assert_eq!(clue[P(1, 4)], numberlink::Clue(16));
+        assert_eq!(encode_url_numlin(&clue), url);
+    }
 
-      if *j >= width {
-          *i += *j/width;
-          *j %= width;
-      }
+    #[test]
+    fn test_numlin_url_walls_and_bridges() {
+        let mut clue = Grid::new(2, 3, numberlink::NO_CLUE);
+        clue[P(0, 0)] = numberlink::Clue(1);
+        clue[P(0, 2)] = numberlink::UNUSED;
+        clue[P(1, 1)] = numberlink::BRIDGE;
+        let url = encode_url_numlin(&clue);
+        assert_eq!(url, "https://puzz.link/p?numlin/3/2/1k/m40/b10");
+        assert_eq!(parse_url_numlin_internal(url), Some(clue));
+    }
 
-      *index += 1;
-  }    
-}
+    #[test]
+    fn test_generate_numlin() {
+        let options = r#"{"clue_limit": 8, "min_clues": 4, "min_steps": 5}"#;
+        let payload = generate_numlin("nl-6x6-1", options);
+        let problem: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert!(problem["n_steps"].as_u64().unwrap() >= 5);
+        assert_eq!(problem["seed"], "nl-6x6-0000000000000001");
+        assert_eq!(generate_numlin("nl-6x6-0000000000000001", options), payload);
+        let n_clues = problem["clue"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c.as_i64().unwrap())
+            .max()
+            .unwrap();
+        assert!((4..=8).contains(&n_clues));
+        let url = problem["url"].as_str().unwrap().to_string();
+        assert!(parse_url_numlin_internal(url).is_some());
+
+        let payload = generate_numlin("nl-6x6-1", r#"{"symmetry": "tetrad"}"#);
+        let problem: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        let clue = problem["clue"].as_array().unwrap();
+        for y in 0..6 {
+            for x in 0..6 {
+                // rotated by 90 degrees
+                let is_clue = |i: usize| clue[i].as_i64().unwrap() != 0;
+                assert_eq!(is_clue(y * 6 + x), is_clue(x * 6 + 5 - y));
+            }
+        }
+        assert_eq!(generate_numlin("nl-7x7-1", r#"{"symmetry": "tetrad"}"#), "");
+
+        assert_eq!(generate_numlin("nl-6x6-1", r#"{"symmetry": "spiral"}"#), "");
+        assert_eq!(generate_numlin("nl-6x6-1", "{"), "");
+        assert_eq!(generate_numlin("sl-6x6-1", "{}"), "");
+        assert_eq!(generate_numlin("nl-6x6", "{}"), "");
+        let impossible = r#"{"min_steps": 18446744073709551615, "time_limit_ms": 100}"#;
+        assert_eq!(generate_numlin("nl-6x6-1", impossible), "");
+    }
 
-fn main() {
-  let url = "https://puzz.link/p?dbchoco/11/10/00333rpvsvufv3v0v07010i4i4j4h4g1h1l4k3o2q6o2g1i4i5u3i1t";
-  // https://puzz.link/p?dbchoco/8/8/1tkv17m0f4pheu1j2j6j62g2u3i1p (0 だとだめ 1 以上なら O)
+    #[test]
+    fn test_generate_slither() {
+        let payload = generate_slither("sl-5x6-2a", 12, "dyad");
+        let problem: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!((problem["height"].as_u64(), problem["width"].as_u64()), (Some(5), Some(6)));
+        assert_eq!(problem["seed"], "sl-5x6-000000000000002a");
+        let url = problem["url"].as_str().unwrap();
+        assert!(slitherlink::parse_url(url).is_some());
+        assert_eq!(generate_slither("sl-5x6-2a", 12, "dyad"), payload);
+
+        assert_eq!(generate_slither("nl-5x6-2a", 12, "dyad"), "");
+        assert_eq!(generate_slither("sl-5x6-2a", 12, "spiral"), "");
+    }
 
-  let sol = solve_dblchoco(url, 1000);
+    #[test]
+    fn test_draw_picture() {
+        let count_lines = |picture: &render::Picture| {
+            picture
+                .shapes()
+                .iter()
+                .filter(|s| matches!(s, render::Shape::Line { .. }))
+                .count()
+        };
 
-  println!("{:?}", sol);
-}
\ No newline at end of file
+        let state = r#"{"url": "https://puzz.link/p?numlin/3/2/1g1h"}"#;
+        let problem = draw_picture("numlin", state).unwrap();
+        let state = r#"{"url": "https://puzz.link/p?numlin/3/2/1g1h", "sol": [[[0, 0], [0, 1]], [[0, 1], [0, 2]]]}"#;
+        let answer = draw_picture("numlin", state).unwrap();
+        assert_eq!(count_lines(&answer), count_lines(&problem) + 2);
+
+        let url = "https://puzz.link/p?dbchoco/2/2/0-1fi";
+        let state = format!(r#"{{"url": "{}", "sol": "x-x-"}}"#, url);
+        let problem = draw_picture("dblchoco", &format!(r#"{{"url": "{}"}}"#, url)).unwrap();
+        let answer = draw_picture("dblchoco", &state).unwrap();
+        assert_eq!(count_lines(&answer), count_lines(&problem) + 2);
+        // Undecided borders of a partial answer are drawn as 2 dashes each
+        let state = format!(r#"{{"url": "{}", "sol": "x-  "}}"#, url);
+        let partial = draw_picture("dblchoco", &state).unwrap();
+        assert_eq!(count_lines(&partial), count_lines(&problem) + 1 + 2 * 2);
+
+        let state = r#"{"url": "https://puzz.link/p?slither/1/1/g", "sol": "----"}"#;
+        let answer = draw_picture("slither", state).unwrap();
+        assert_eq!(count_lines(&answer), 4);
+
+        assert!(draw_picture("dblchoco", r#"{"url": "https://puzz.link/p?dbchoco/2/2/0-1fi", "sol": "x"}"#).is_none());
+        assert!(draw_picture("numlin", r#"{"url": "https://puzz.link/p?numlin/3/2/1g1h", "sol": [[[0, 0], [1, 1]]]}"#).is_none());
+        assert!(draw_picture("unknown", r#"{"url": ""}"#).is_none());
+        assert!(draw_picture("slither", "").is_none());
+    }
+
+    #[test]
+    fn test_numlin_url_invalid() {
+        // Found by fuzzing: malformed URLs must be rejected instead of panicking or looping forever
+        for url in &[
+            "numlin/1/1/11",
+            "numlin/2/2/G",
+            "numlin/2/2/-1",
+            "numlin/1/1/z1",
+            "numlin/2/1/1g/mg",
+            "numlin/2/1/g/m1",
+            "numlin/2/1/g/q0",
+        ] {
+            assert!(parse_url_numlin_internal(url.to_string()).is_none(), "{}", url);
+        }
+    }
+}
@@ -1,9 +1,11 @@
-use super::super::{Grid, Symmetry, D, LP, P};
+use super::super::{minimize_conflict, Grid, Symmetry, Timer, D, LP, P};
 use super::*;
 
 extern crate rand;
 
+use crate::StableRng;
 use rand::Rng;
+use std::time::Duration;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Endpoint {
@@ -21,6 +23,38 @@ pub struct GeneratorOption<'a> {
     pub prioritized_extension: bool,
 }
 
+/// Requirements on the problems generated by `PlacementGenerator::generate_with_difficulty`
+/// in addition to `GeneratorOption`. The maximum number of clues is `GeneratorOption::clue_limit`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DifficultyOption {
+    /// The minimum `n_steps` of `solve2` to solve the problem.
+    pub min_steps: u64,
+    /// The maximum `n_steps` of `solve2` to solve the problem, if any.
+    pub max_steps: Option<u64>,
+    /// The minimum number of pairs of numbers, if any.
+    pub min_clues: Option<i32>,
+    /// The time to give up generation after, if any.
+    pub time_limit: Option<Duration>,
+}
+
+impl DifficultyOption {
+    fn accepts(&self, problem: &Grid<Clue>, n_steps: u64) -> bool {
+        if n_steps < self.min_steps || n_steps > self.max_steps.unwrap_or(u64::MAX) {
+            return false;
+        }
+        if let Some(min_clues) = self.min_clues {
+            let n_clues = (0..(problem.height() * problem.width()) as usize)
+                .map(|i| problem[i].0)
+                .max()
+                .unwrap_or(0);
+            if n_clues < min_clues {
+                return false;
+            }
+        }
+        true
+    }
+}
+
 pub fn generate_endpoint_constraint<R: Rng>(
     height: i32,
     width: i32,
@@ -47,7 +81,7 @@ pub fn generate_endpoint_constraint<R: Rng>(
             if corner_positions[i] != -1 {
                 continue;
             }
-            corner_positions[i] = rng.gen_range(lo, hi + 1);
+            corner_positions[i] = rng.stable_range(lo, hi + 1);
             if symmetry.tetrad || symmetry.vertical || (symmetry.dyad && symmetry.horizontal) {
                 corner_positions[i ^ 1] = corner_positions[i];
             }
@@ -86,6 +120,16 @@ enum FieldUpdate {
     Extend(LP),
 }
 
+/// Returns whether a placement whose endpoints have `symmetry` can exist on a `height` x `width` grid.
+///
+/// 90-degree symmetry is impossible on squares of odd size: color the cells like a checkerboard,
+/// then the color of the corners has one more cell than the other, which must be made up by
+/// lines with both ends on the former color. But a 90-degree rotation preserves the colors,
+/// so each color has a multiple of 4 ends and the difference made by the lines is always even.
+pub fn is_symmetry_feasible(height: i32, width: i32, symmetry: Symmetry) -> bool {
+    !(symmetry.tetrad && height == width && height % 2 == 1)
+}
+
 pub struct PlacementGenerator {
     pool: Vec<AnswerField>,
     active_fields: Vec<AnswerField>,
@@ -134,6 +178,9 @@ impl PlacementGenerator {
             tetrad: opt.symmetry.tetrad && (height == width),
             ..opt.symmetry
         };
+        if !is_symmetry_feasible(height, width, symmetry) {
+            return None;
+        }
         let mut endpoint_constraint = match opt.endpoint_constraint {
             Some(e) => e.clone(),
             None => Grid::new(height, width, Endpoint::Any),
@@ -142,16 +189,17 @@ impl PlacementGenerator {
             endpoint_constraint[P(height / 2, width / 2)] = Endpoint::Prohibited;
         }
         if opt.forbid_adjacent_clue {
+            // a clue next to the axis would be adjacent to its mirror image
             if symmetry.horizontal && height % 2 == 0 {
                 for x in 0..width {
+                    endpoint_constraint[P(height / 2 - 1, x)] = Endpoint::Prohibited;
                     endpoint_constraint[P(height / 2, x)] = Endpoint::Prohibited;
-                    endpoint_constraint[P(height / 2 + 1, x)] = Endpoint::Prohibited;
                 }
             }
-            if symmetry.horizontal && width % 2 == 0 {
+            if symmetry.vertical && width % 2 == 0 {
                 for y in 0..height {
+                    endpoint_constraint[P(y, width / 2 - 1)] = Endpoint::Prohibited;
                     endpoint_constraint[P(y, width / 2)] = Endpoint::Prohibited;
-                    endpoint_constraint[P(y, width / 2 + 1)] = Endpoint::Prohibited;
                 }
             }
             if symmetry.dyad {
@@ -187,7 +235,7 @@ impl PlacementGenerator {
                     break;
                 }
 
-                let id = rng.gen_range(0, fields.len());
+                let id = rng.stable_index(fields.len());
 
                 if fields[id].is_invalid() || !fields[id].has_seed() {
                     self.pool.push(fields.swap_remove(id));
@@ -257,17 +305,62 @@ impl PlacementGenerator {
         opt: &GeneratorOption,
         rng: &mut R,
     ) -> Option<Grid<Clue>> {
+        self.generate_and_measure(opt, rng)
+            .map(|(problem, _)| problem)
+    }
+    /// Same as `generate_and_test`, but also returns the difficulty of the problem,
+    /// measured by `AnswerDetail::n_steps` of `solve2` checking its uniqueness.
+    pub fn generate_and_measure<R: Rng>(
+        &mut self,
+        opt: &GeneratorOption,
+        rng: &mut R,
+    ) -> Option<(Grid<Clue>, u64)> {
         if let Some(placement) = self.generate(opt, rng) {
             if uniqueness_pretest(&placement) {
                 let problem = extract_problem(&placement, rng);
-                let ans = solve2(&problem, Some(2), false, true);
+                let ans = solve2(
+                    &problem,
+                    &SolverOption {
+                        limit: Some(2),
+                        every_cell_used: false,
+                        terminate_on_not_fully_filled: true,
+                    },
+                );
                 if ans.len() == 1 && !ans.found_not_fully_filled {
-                    return Some(problem);
+                    return Some((problem, ans.n_steps));
                 }
             }
         }
         None
     }
+    /// Repeats `generate_and_measure` until a problem meets `difficulty`.
+    ///
+    /// Returns the problem with its `n_steps`, or `None` if `difficulty.time_limit` expires first.
+    /// Without a time limit, this does not return until a problem is found,
+    /// except that `None` is returned at once if `opt.symmetry` is infeasible.
+    pub fn generate_with_difficulty<R: Rng>(
+        &mut self,
+        opt: &GeneratorOption,
+        difficulty: &DifficultyOption,
+        rng: &mut R,
+    ) -> Option<(Grid<Clue>, u64)> {
+        if !is_symmetry_feasible(self.height, self.width, opt.symmetry) {
+            return None;
+        }
+        let timer = Timer::start();
+        loop {
+            if let Some(limit) = difficulty.time_limit {
+                if timer.elapsed() >= limit {
+                    return None;
+                }
+            }
+            if let Some((problem, n_steps)) = self.generate_and_measure(opt, rng) {
+                if difficulty.accepts(&problem, n_steps) {
+                    return Some((problem, n_steps));
+                }
+            }
+        }
+    }
     fn check_invalidity(field: &mut AnswerField, opt: &GeneratorOption) {
         if field.is_invalid() {
             return;
@@ -294,14 +387,14 @@ impl PlacementGenerator {
         if field.count_neighbor(pos) == (0, 2) {
             let constraint = field.get_endpoint_constraint(pos_vtx);
 
-            if constraint != Endpoint::Forced && rng.gen::<f64>() < 0.9f64 {
+            if constraint != Endpoint::Forced && rng.stable_f64() < 0.9f64 {
                 FieldUpdate::Corner(nbs[0], nbs[1])
             } else {
-                let i = rng.gen_range(0, 2);
+                let i = rng.stable_index(2);
                 FieldUpdate::Endpoint(nbs[i], nbs[1 - i])
             }
         } else {
-            let i = rng.gen_range(0, nbs.len());
+            let i = rng.stable_index(nbs.len());
             FieldUpdate::Extend(nbs[i])
         }
     }
@@ -377,6 +470,94 @@ fn is_entangled(field: &AnswerField) -> bool {
     false
 }
 
+/// Removes numbers of `problem` one after another, in a random order, as long as the problem
+/// has a unique answer. Both ends of a number are removed together, and so are the numbers
+/// whose ends are mapped to each other by `symmetry`. Walls and bridges are kept.
+///
+/// If `every_cell_used` is true, only the answers using every cell are counted. Otherwise,
+/// answers leaving some cells unused are counted as well, as in
+/// `PlacementGenerator::generate_and_test`, so the unique answer must use every cell.
+/// Returns `None` if `problem` does not have a unique answer in the first place.
+pub fn minimize<R: Rng>(
+    problem: &Grid<Clue>,
+    symmetry: Symmetry,
+    every_cell_used: bool,
+    rng: &mut R,
+) -> Option<Grid<Clue>> {
+    let height = problem.height();
+    let width = problem.width();
+
+    let mut ends = vec![];
+    let mut max_clue = 0;
+    for y in 0..height {
+        for x in 0..width {
+            let Clue(c) = problem[P(y, x)];
+            if c > 0 {
+                ends.push(P(y, x));
+                max_clue = max_clue.max(c);
+            }
+        }
+    }
+
+    // Numbers whose ends are mapped to each other share the same root
+    let mut root = (0..=max_clue).collect::<Vec<_>>();
+    fn find(root: &mut Vec<i32>, c: i32) -> i32 {
+        if root[c as usize] != c {
+            let r = find(root, root[c as usize]);
+            root[c as usize] = r;
+        }
+        root[c as usize]
+    }
+    for group in symmetry.group_cells(&ends, height, width) {
+        for &pos in &group {
+            let r1 = find(&mut root, problem[group[0]].0);
+            let r2 = find(&mut root, problem[pos].0);
+            root[r2 as usize] = r1;
+        }
+    }
+    let mut groups: Vec<Vec<P>> = vec![];
+    let mut group_id: Vec<Option<usize>> = vec![None; (max_clue + 1) as usize];
+    for &pos in &ends {
+        let r = find(&mut root, problem[pos].0) as usize;
+        match group_id[r] {
+            Some(i) => groups[i].push(pos),
+            None => {
+                group_id[r] = Some(groups.len());
+                groups.push(vec![pos]);
+            }
+        }
+    }
+    rng.stable_shuffle(&mut groups);
+
+    let problem_of = |groups: &[Vec<P>]| {
+        let mut ret = problem.clone();
+        for &pos in &ends {
+            ret[pos] = NO_CLUE;
+        }
+        for group in groups {
+            for &pos in group {
+                ret[pos] = problem[pos];
+            }
+        }
+        ret
+    };
+    let is_unique = |groups: &[Vec<P>]| {
+        let ans = solve2(
+            &problem_of(groups),
+            &SolverOption {
+                limit: Some(2),
+                every_cell_used,
+                terminate_on_not_fully_filled: !every_cell_used,
+            },
+        );
+        ans.len() == 1 && !ans.found_not_fully_filled
+    };
+    if !is_unique(&groups) {
+        return None;
+    }
+    Some(problem_of(&minimize_conflict(&groups, is_unique)))
+}
+
 /// Extract a problem from `placement`.
 /// Clue numbers are randomly assigned using `rng`.
 pub fn extract_problem<R: Rng>(placement: &LinePlacement, rng: &mut R) -> Grid<Clue> {
@@ -398,7 +579,7 @@ pub fn extract_problem<R: Rng>(placement: &LinePlacement, rng: &mut R) -> Grid<C
     for i in 0..(max_id + 1) {
         shuffler[i as usize] = i;
     }
-    rng.shuffle(&mut shuffler);
+    rng.stable_shuffle(&mut shuffler);
 
     let mut ret = Grid::new(height, width, NO_CLUE);
     for y in 0..height {
@@ -503,7 +684,14 @@ fn uniqueness_pretest_horizontal(ids: &Grid<i32>) -> bool {
             }
         }
 
-        let ans = solve2(&screen_problem, Some(2), false, true);
+        let ans = solve2(
+            &screen_problem,
+            &SolverOption {
+                limit: Some(2),
+                every_cell_used: false,
+                terminate_on_not_fully_filled: true,
+            },
+        );
         if ans.len() >= 2 || ans.found_not_fully_filled {
             return false;
         }
@@ -615,4 +803,162 @@ fn fill_line_id(pos: P, field: &AnswerField, ids: &mut Grid<i32>, id: i32) {
             fill_line_id(pos + d, field, ids, id);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimize() {
+        let mut rng = crate::SeedRng::new(42);
+        let mut problem = Grid::new(2, 3, NO_CLUE);
+        problem[P(0, 0)] = Clue(1);
+        problem[P(1, 2)] = Clue(1);
+        problem[P(1, 0)] = Clue(2);
+        problem[P(1, 1)] = Clue(2);
+
+        // Either number alone determines the Hamiltonian path, but not if unused cells are allowed
+        let minimized = minimize(&problem, Symmetry::none(), true, &mut rng).unwrap();
+        let n_ends = (0..6)
+            .filter(|&i| minimized[P(i / 3, i % 3)] != NO_CLUE)
+            .count();
+        assert_eq!(n_ends, 2);
+        let minimized = minimize(&problem, Symmetry::none(), false, &mut rng).unwrap();
+        assert_eq!(minimized, problem);
+
+        problem[P(0, 0)] = NO_CLUE;
+        assert!(minimize(&problem, Symmetry::none(), true, &mut rng).is_none());
+    }
+
+    #[test]
+    fn test_generate_with_difficulty() {
+        let mut rng = crate::SeedRng::new(42);
+        let mut generator = PlacementGenerator::new(6, 6);
+        let opt = GeneratorOption {
+            chain_threshold: 3,
+            endpoint_constraint: None,
+            forbid_adjacent_clue: false,
+            symmetry: Symmetry::none(),
+            clue_limit: Some(6),
+            prioritized_extension: false,
+        };
+        let difficulty = DifficultyOption {
+            min_steps: 10,
+            max_steps: Some(1000),
+            min_clues: Some(4),
+            time_limit: Some(Duration::from_secs(60)),
+        };
+        let (problem, n_steps) = generator
+            .generate_with_difficulty(&opt, &difficulty, &mut rng)
+            .unwrap();
+        assert!((10..=1000).contains(&n_steps));
+        let n_clues = (0..36).map(|i| problem[i].0).max().unwrap();
+        assert!((4..=6).contains(&n_clues));
+        let ans = solve2(
+            &problem,
+            &SolverOption {
+                limit: Some(2),
+                ..Default::default()
+            },
+        );
+        assert_eq!(ans.len(), 1);
+
+        let impossible = DifficultyOption {
+            min_steps: u64::MAX,
+            time_limit: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        assert!(generator
+            .generate_with_difficulty(&opt, &impossible, &mut rng)
+            .is_none());
+    }
+
+    fn generate_symmetric(
+        height: i32,
+        width: i32,
+        symmetry: Symmetry,
+        forbid_adjacent_clue: bool,
+    ) -> Vec<Grid<Clue>> {
+        let mut rng = crate::SeedRng::new(42);
+        let mut generator = PlacementGenerator::new(height, width);
+        let opt = GeneratorOption {
+            chain_threshold: 3,
+            endpoint_constraint: None,
+            forbid_adjacent_clue,
+            symmetry,
+            clue_limit: None,
+            prioritized_extension: false,
+        };
+        let problems = (0..100)
+            .filter_map(|_| generator.generate_and_test(&opt, &mut rng))
+            .take(3)
+            .collect::<Vec<_>>();
+        assert!(!problems.is_empty(), "{}x{} {:?}", height, width, symmetry);
+        problems
+    }
+
+    fn assert_symmetric(problem: &Grid<Clue>, symmetry: Symmetry) {
+        let (height, width) = (problem.height(), problem.width());
+        for y in 0..height {
+            for x in 0..width {
+                let pos = P(y, x);
+                for q in symmetry.orbit(pos, height, width) {
+                    assert_eq!(
+                        problem[pos] == NO_CLUE,
+                        problem[q] == NO_CLUE,
+                        "{:?} and {:?}",
+                        pos,
+                        q
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_symmetric() {
+        let dyad = Symmetry {
+            dyad: true,
+            ..Symmetry::none()
+        };
+        for &(height, width) in &[(8, 8), (7, 9)] {
+            for problem in generate_symmetric(height, width, dyad, false) {
+                assert_symmetric(&problem, dyad);
+                if height % 2 == 1 && width % 2 == 1 {
+                    assert_eq!(problem[P(height / 2, width / 2)], NO_CLUE);
+                }
+            }
+        }
+
+        let tetrad = Symmetry {
+            tetrad: true,
+            ..Symmetry::none()
+        };
+        for &size in &[6, 8] {
+            for problem in generate_symmetric(size, size, tetrad, false) {
+                assert_symmetric(&problem, tetrad);
+            }
+        }
+        assert!(!is_symmetry_feasible(7, 7, tetrad));
+        assert!(is_symmetry_feasible(7, 7, dyad));
+        assert!(is_symmetry_feasible(7, 9, tetrad));
+
+        let horizontal = Symmetry {
+            horizontal: true,
+            ..Symmetry::none()
+        };
+        for problem in generate_symmetric(10, 9, horizontal, true) {
+            assert_symmetric(&problem, horizontal);
+            assert!((0..9).all(|x| problem[P(4, x)] == NO_CLUE && problem[P(5, x)] == NO_CLUE));
+        }
+        let vertical = Symmetry {
+            vertical: true,
+            ..Symmetry::none()
+        };
+        for problem in generate_symmetric(9, 10, vertical, true) {
+            assert_symmetric(&problem, vertical);
+            assert!((0..9).all(|y| problem[P(y, 4)] == NO_CLUE && problem[P(y, 5)] == NO_CLUE));
+        }
+    }
+}
@@ -3,6 +3,7 @@ use super::*;
 
 extern crate rand;
 
+use crate::StableRng;
 use rand::Rng;
 use std::fmt;
 
@@ -232,7 +233,7 @@ impl AnswerField {
 
     /// Returns a random seed using `rng`
     pub fn random_seed<R: Rng>(&self, rng: &mut R) -> LP {
-        let idx = rng.gen_range(0, self.seed_count);
+        let idx = rng.stable_index(self.seed_count);
         self.seeds[idx]
     }
 
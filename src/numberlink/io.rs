@@ -1,15 +1,24 @@
-use std::io::BufRead;
+use std::io::{self, BufRead, Write};
 
 use super::*;
-use crate::common::Grid;
-use crate::io::{read_grid, ReadError};
+use crate::common::{Grid, LP};
+use crate::io::{
+    read_grid, read_pzprv3_cells, read_pzprv3_header, write_grid, write_pzprv3_borders,
+    write_pzprv3_cells, write_pzprv3_header, Pzprv3Header, ReadError,
+};
 
+/// Reads a problem in the penciloid format.
+/// Each cell is a clue number, `.` (empty), `#` (wall) or `+` (bridge).
 pub fn read_penciloid_problem<T: BufRead>(reader: &mut T) -> Result<Grid<Clue>, ReadError> {
     read_grid(
         reader,
         |token: &str| {
             if token == "." {
                 Ok(NO_CLUE)
+            } else if token == "#" {
+                Ok(UNUSED)
+            } else if token == "+" {
+                Ok(BRIDGE)
             } else {
                 let n = token.parse::<i32>().map_err(|_| ReadError::InvalidValue)?;
                 if n <= 0 {
@@ -21,4 +30,133 @@ pub fn read_penciloid_problem<T: BufRead>(reader: &mut T) -> Result<Grid<Clue>,
         },
         NO_CLUE,
     )
-}
\ No newline at end of file
+}
+pub fn write_penciloid_problem<T: Write>(writer: &mut T, problem: &Grid<Clue>) -> io::Result<()> {
+    write_grid(writer, problem, |&c| match c {
+        UNUSED => String::from("#"),
+        BRIDGE => String::from("+"),
+        Clue(n) if n > 0 => n.to_string(),
+        _ => String::from("."),
+    })
+}
+
+/// Reads a problem in the pzprv3 format of `numlin`: a cell block of clue numbers and `.`
+/// (empty), followed by a border block of lines, which is ignored.
+pub fn read_pzprv3_problem<T: BufRead>(reader: &mut T) -> Result<Grid<Clue>, ReadError> {
+    let header = read_pzprv3_header(reader, "numlin")?;
+    read_pzprv3_cells(
+        reader,
+        header.height,
+        header.width,
+        |token: &str| {
+            if token == "." {
+                return Ok(NO_CLUE);
+            }
+            match token.parse::<i32>() {
+                Ok(n) if n > 0 => Ok(Clue(n)),
+                _ => Err(ReadError::InvalidValue),
+            }
+        },
+        NO_CLUE,
+    )
+}
+
+/// Writes `problem` in the pzprv3 format of `numlin`, together with the lines of `answer`.
+/// Walls and bridges cannot be written.
+pub fn write_pzprv3<T: Write>(
+    writer: &mut T,
+    problem: &Grid<Clue>,
+    answer: Option<&LinePlacement>,
+) -> io::Result<()> {
+    let (height, width) = (problem.height(), problem.width());
+    if (0..(height * width) as usize).any(|i| problem[i].0 < 0) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "walls and bridges are not supported in pzprv3",
+        ));
+    }
+
+    let header = Pzprv3Header {
+        version: 0,
+        height,
+        width,
+    };
+    write_pzprv3_header(writer, "numlin", &header)?;
+    write_pzprv3_cells(writer, problem, |&Clue(n)| {
+        if n > 0 {
+            n.to_string()
+        } else {
+            String::from(".")
+        }
+    })?;
+    let mut lines = Grid::new(2 * height - 1, 2 * width - 1, false);
+    if let Some(answer) = answer {
+        for y in 0..(2 * height - 1) {
+            for x in 0..(2 * width - 1) {
+                if y % 2 != x % 2 {
+                    lines[LP(y, x)] = answer.get(LP(y, x));
+                }
+            }
+        }
+    }
+    write_pzprv3_borders(writer, &lines, false, |&b| {
+        String::from(if b { "1" } else { "0" })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::P;
+
+    #[test]
+    fn test_penciloid_walls_and_bridges() {
+        let src = "2 3\n1 # .\n. + 1\n";
+        let problem = read_penciloid_problem(&mut src.as_bytes()).unwrap();
+        assert_eq!(problem[P(0, 1)], UNUSED);
+        assert_eq!(problem[P(1, 1)], BRIDGE);
+        assert_eq!(problem[P(1, 2)], Clue(1));
+
+        let mut dst = vec![];
+        write_penciloid_problem(&mut dst, &problem).unwrap();
+        assert_eq!(String::from_utf8(dst).unwrap(), src);
+    }
+
+    #[test]
+    fn test_pzprv3() {
+        let src = "pzprv3\nnumlin\n2\n3\n1 . . \n. . 1 \n0 0 \n0 0 \n0 0 0 \n";
+        let problem = read_pzprv3_problem(&mut src.as_bytes()).unwrap();
+        assert_eq!(problem[P(0, 0)], Clue(1));
+        assert_eq!(problem[P(1, 2)], Clue(1));
+        assert_eq!(problem[P(0, 1)], NO_CLUE);
+
+        let mut dst = vec![];
+        write_pzprv3(&mut dst, &problem, None).unwrap();
+        assert_eq!(String::from_utf8(dst).unwrap(), src);
+
+        let mut answer = LinePlacement::new(2, 3);
+        answer.set_right(P(0, 0), true);
+        answer.set_right(P(0, 1), true);
+        answer.set_down(P(0, 2), true);
+        let mut dst = vec![];
+        write_pzprv3(&mut dst, &problem, Some(&answer)).unwrap();
+        let dst = String::from_utf8(dst).unwrap();
+        let borders = dst.lines().skip(6).collect::<Vec<_>>();
+        assert_eq!(borders[0], "1 1 ");
+        assert_eq!(borders[1], "0 0 ");
+        assert_eq!(borders[2], "0 0 1 ");
+
+        let mut walls = problem.clone();
+        walls[P(0, 1)] = UNUSED;
+        assert!(write_pzprv3(&mut vec![], &walls, None).is_err());
+
+        for src in &[
+            "pzprv3\nslither\n1\n1\n. \n",
+            "pzprv3\nnumlin\n1\n1\n- \n",
+            "pzprv3\nnumlin\n2\n2\n1 . \n",
+            "numlin\n1\n1\n. \n",
+        ] {
+            assert!(read_pzprv3_problem(&mut src.as_bytes()).is_err(), "{}", src);
+        }
+    }
+}
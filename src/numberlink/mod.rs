@@ -3,18 +3,24 @@ use std::ops::Index;
 mod generator;
 mod generator_field;
 mod io;
+mod render;
+mod sat;
 mod solver2;
 
 pub use self::generator::*;
 use self::generator_field::*;
 pub use self::io::*;
+pub use self::sat::*;
 pub use self::solver2::*;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Clue(pub i32);
 
 pub const NO_CLUE: Clue = Clue(0);
+/// A blocked cell (wall), which no line passes through.
 pub const UNUSED: Clue = Clue(-1);
+/// A bridge, on which a horizontal line and a vertical line cross without being connected.
+pub const BRIDGE: Clue = Clue(-2);
 
 use super::{Grid, D, LP, P};
 use crate::common::FOUR_NEIGHBOURS;
@@ -72,7 +78,7 @@ impl LinePlacement {
             || self.down(pos + D(-1, 0))
             || self.down(pos))
     }
-    pub fn is_endpoint(&self, pos: P) -> bool {
+    fn degree(&self, pos: P) -> i32 {
         let mut n_lines = 0;
         let pos_vtx = LP::of_vertex(pos);
         for &d in &FOUR_NEIGHBOURS {
@@ -80,8 +86,18 @@ impl LinePlacement {
                 n_lines += 1;
             }
         }
-        n_lines == 1
+        n_lines
+    }
+    pub fn is_endpoint(&self, pos: P) -> bool {
+        self.degree(pos) == 1
+    }
+    /// Whether two lines cross at `pos`, which happens only on bridges.
+    pub fn is_crossing(&self, pos: P) -> bool {
+        self.degree(pos) == 4
     }
+    /// Assigns an id to each chain and returns the id of the chain passing through each cell.
+    /// A crossing cell has the id of the chain passing through it horizontally.
+    /// Returns `None` if the lines do not form valid chains.
     pub fn extract_chain_groups(&self) -> Option<Grid<i32>> {
         let height = self.height();
         let width = self.width();
@@ -97,6 +113,15 @@ impl LinePlacement {
                     let mut c = pos;
 
                     'traverse: loop {
+                        if self.is_crossing(c) {
+                            let d = c - l;
+                            if d.0 == 0 {
+                                ids[c] = last_id;
+                            }
+                            l = c;
+                            c = c + d;
+                            continue;
+                        }
                         ids[c] = last_id;
                         for &d in &FOUR_NEIGHBOURS {
                             if c + d != l && self.get_checked(LP::of_vertex(c) + d) {
@@ -118,7 +143,16 @@ impl LinePlacement {
                 if ids[pos] == -1 {
                     return None;
                 }
-                if y < height - 1 && (ids[pos] == ids[pos + D(1, 0)]) != self.down(pos) {
+                if self.is_crossing(pos) {
+                    if !(0 < y && y < height - 1 && ids[pos + D(-1, 0)] == ids[pos + D(1, 0)]) {
+                        return None;
+                    }
+                    continue;
+                }
+                if y < height - 1
+                    && !self.is_crossing(pos + D(1, 0))
+                    && (ids[pos] == ids[pos + D(1, 0)]) != self.down(pos)
+                {
                     return None;
                 }
                 if x < width - 1 && (ids[pos] == ids[pos + D(0, 1)]) != self.right(pos) {
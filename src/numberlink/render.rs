@@ -0,0 +1,139 @@
+use super::*;
+
+impl LinePlacement {
+    /// Renders the lines with box-drawing characters.
+    ///
+    /// Each cell is padded to the width of the largest clue and adjacent cells are separated
+    /// by `─` or `│` if they are connected, or by spaces otherwise. Walls are drawn as `#`.
+    /// Trailing spaces are omitted.
+    pub fn to_ascii(&self, clue: &Grid<Clue>) -> String {
+        let height = self.height();
+        let width = self.width();
+        let cell_width = (0..(height * width) as usize)
+            .filter(|&i| clue[i].0 > 0)
+            .map(|i| clue[i].0.to_string().len())
+            .max()
+            .unwrap_or(1);
+
+        let mut ret = String::new();
+        for y in 0..height {
+            let mut row = String::new();
+            let mut connector = String::new();
+            for x in 0..width {
+                let pos = P(y, x);
+                let left = self.right(pos + D(0, -1));
+                let glyph = match clue[pos] {
+                    Clue(n) if n > 0 => n.to_string(),
+                    UNUSED => String::from("#"),
+                    _ => self.glyph(pos).to_string(),
+                };
+                let pad = if left { '─' } else { ' ' };
+                for _ in glyph.chars().count()..cell_width {
+                    row.push(pad);
+                }
+                row.push_str(&glyph);
+                for _ in 1..cell_width {
+                    connector.push(' ');
+                }
+                connector.push(if self.down(pos) { '│' } else { ' ' });
+                if x != width - 1 {
+                    row.push(if self.right(pos) { '─' } else { ' ' });
+                    connector.push(' ');
+                }
+            }
+            ret.push_str(row.trim_end());
+            ret.push('\n');
+            if y != height - 1 {
+                ret.push_str(connector.trim_end());
+                ret.push('\n');
+            }
+        }
+        ret
+    }
+
+    fn glyph(&self, pos: P) -> char {
+        let up = self.down(pos + D(-1, 0));
+        let down = self.down(pos);
+        let left = self.right(pos + D(0, -1));
+        let right = self.right(pos);
+        match (up, down, left, right) {
+            (false, false, false, false) => ' ',
+            (true, true, false, false) => '│',
+            (false, false, true, true) => '─',
+            (false, true, false, true) => '┌',
+            (false, true, true, false) => '┐',
+            (true, false, false, true) => '└',
+            (true, false, true, false) => '┘',
+            (true, true, true, true) => '┼',
+            (true, true, false, true) => '├',
+            (true, true, true, false) => '┤',
+            (false, true, true, true) => '┬',
+            (true, false, true, true) => '┴',
+            (true, false, false, false) => '╵',
+            (false, true, false, false) => '╷',
+            (false, false, true, false) => '╴',
+            (false, false, false, true) => '╶',
+        }
+    }
+
+    /// Renders the problem and the lines as an SVG image.
+    ///
+    /// This is a shorthand of `render::numberlink`.
+    pub fn to_svg(&self, clue: &Grid<Clue>) -> String {
+        crate::render::numberlink(clue, Some(self)).to_svg()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn horizontal_answer() -> (Grid<Clue>, LinePlacement) {
+        // 1-1
+        // 2-2
+        let mut clue = Grid::new(2, 3, NO_CLUE);
+        clue[P(0, 0)] = Clue(1);
+        clue[P(0, 2)] = Clue(1);
+        clue[P(1, 0)] = Clue(12);
+        clue[P(1, 2)] = Clue(12);
+        let mut lines = LinePlacement::new(2, 3);
+        for y in 0..2 {
+            lines.set_right(P(y, 0), true);
+            lines.set_right(P(y, 1), true);
+        }
+        (clue, lines)
+    }
+
+    #[test]
+    fn test_to_ascii() {
+        let (clue, lines) = horizontal_answer();
+        assert_eq!(lines.to_ascii(&clue), " 1─────1\n\n12────12\n");
+
+        let mut clue = Grid::new(3, 3, NO_CLUE);
+        clue[P(0, 0)] = Clue(1);
+        clue[P(2, 2)] = Clue(1);
+        clue[P(1, 1)] = BRIDGE;
+        clue[P(2, 0)] = UNUSED;
+        let mut lines = LinePlacement::new(3, 3);
+        lines.set_right(P(0, 0), true);
+        lines.set_down(P(0, 1), true);
+        lines.set_down(P(1, 1), true);
+        lines.set_right(P(1, 0), true);
+        lines.set_right(P(1, 1), true);
+        lines.set_right(P(2, 1), true);
+        assert_eq!(lines.to_ascii(&clue), "1─┐\n  │\n╶─┼─╴\n  │\n# └─1\n");
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let (clue, lines) = horizontal_answer();
+        let svg = lines.to_svg(&clue);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        let color0 = crate::render::chain_color(0, 2).to_hex();
+        let color1 = crate::render::chain_color(1, 2).to_hex();
+        assert_eq!(svg.matches(&color0).count(), 4);
+        assert_eq!(svg.matches(&color1).count(), 4);
+        assert_eq!(svg.matches("<text").count(), 4);
+    }
+}
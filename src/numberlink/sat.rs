@@ -0,0 +1,150 @@
+use super::*;
+use crate::sat::Sat;
+
+/// Enumerates up to `limit` answers of `problem` with the SAT solver.
+///
+/// Unlike `solve2`, all answers are enumerated even if `every_cell_used` is not set, including
+/// the ones whose lines can be shortened. With `every_cell_used`, the answers are the same as
+/// those of `solve2` with `SolverOption::every_cell_used`.
+pub fn solve_sat(problem: &Grid<Clue>, every_cell_used: bool, limit: usize) -> Vec<LinePlacement> {
+    let height = problem.height();
+    let width = problem.width();
+    let n_cells = (height * width) as usize;
+
+    // A bridge has a vertex for the vertical line in addition to the one for the horizontal line
+    let vertex = |p: P, d: D| {
+        if problem[p] == BRIDGE && d.1 == 0 {
+            n_cells + problem.index_p(p)
+        } else {
+            problem.index_p(p)
+        }
+    };
+
+    let mut sat = Sat::new();
+    let mut line = Grid::new(height * 2 - 1, width * 2 - 1, None);
+    let mut edges = vec![];
+    let mut keys = vec![];
+    let mut incident = vec![vec![]; n_cells * 2];
+    for y in 0..height {
+        for x in 0..width {
+            let p = P(y, x);
+            for &d in &[D(1, 0), D(0, 1)] {
+                let q = p + d;
+                if !problem.is_valid_p(q) {
+                    continue;
+                }
+                let l = sat.new_lit();
+                if problem[p] == UNUSED || problem[q] == UNUSED {
+                    sat.add_clause(&[!l]);
+                }
+                let (u, v) = (vertex(p, d), vertex(q, d));
+                line[LP::of_vertex(p) + d] = Some(l);
+                edges.push((u, v, l));
+                keys.push(l);
+                incident[u].push(l);
+                incident[v].push(l);
+            }
+        }
+    }
+
+    let max_clue = (0..n_cells).map(|i| problem[i].0).max().unwrap_or(0).max(1) as u64;
+    let n_bits = 64 - max_clue.leading_zeros() as usize;
+    let mut id = vec![vec![]; n_cells * 2];
+    for y in 0..height {
+        for x in 0..width {
+            let p = P(y, x);
+            let i = problem.index_p(p);
+            id[i] = sat.new_bits(n_bits);
+            match problem[p] {
+                UNUSED => (),
+                BRIDGE => {
+                    for &d in &FOUR_NEIGHBOURS {
+                        let e = LP::of_vertex(p) + d;
+                        if line.is_valid_lp(e) {
+                            sat.add_clause(&[line[e].unwrap()]);
+                        } else {
+                            sat.add_contradiction();
+                        }
+                    }
+                    id[n_cells + i] = sat.new_bits(n_bits);
+                }
+                NO_CLUE => {
+                    sat.at_most_k(&incident[i], 2);
+                    sat.not_exactly_one(&incident[i]);
+                    if every_cell_used {
+                        sat.add_clause(&incident[i]);
+                    }
+                }
+                Clue(c) => {
+                    sat.exactly_one(&incident[i]);
+                    sat.fix_bits(&id[i], c as u64);
+                }
+            }
+        }
+    }
+    for &(u, v, l) in &edges {
+        sat.equal_bits_if(l, &id[u], &id[v]);
+    }
+    sat.acyclic(n_cells * 2, &edges);
+
+    let mut ret = vec![];
+    while ret.len() < limit {
+        let model = match sat.solve() {
+            Some(model) => model,
+            None => break,
+        };
+        let mut answer = LinePlacement::new(height, width);
+        for y in 0..height {
+            for x in 0..width {
+                let p = P(y, x);
+                if y < height - 1 {
+                    answer.set_down(p, model.value(line[LP::of_vertex(p) + D(1, 0)].unwrap()));
+                }
+                if x < width - 1 {
+                    answer.set_right(p, model.value(line[LP::of_vertex(p) + D(0, 1)].unwrap()));
+                }
+            }
+        }
+        ret.push(answer);
+        sat.block(&model, &keys);
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_problem<T: AsRef<[i32]>>(problem: &[T]) -> Grid<Clue> {
+        let height = problem.len() as i32;
+        let width = problem[0].as_ref().len() as i32;
+        let mut ret = Grid::new(height, width, NO_CLUE);
+        for y in 0..height {
+            for x in 0..width {
+                ret[P(y, x)] = Clue(problem[y as usize].as_ref()[x as usize]);
+            }
+        }
+        ret
+    }
+
+    #[test]
+    fn test_solve_sat() {
+        let problem = make_problem(&[[1, 0, 0], [0, 0, 1]]);
+        assert_eq!(solve_sat(&problem, true, 10).len(), 1);
+        // Lines of any shape are allowed
+        assert_eq!(solve_sat(&problem, false, 10).len(), 4);
+
+        let problem = make_problem(&[[1, 0, 1], [0, 0, 0]]);
+        assert_eq!(solve_sat(&problem, true, 10).len(), 0);
+
+        let problem = make_problem(&[[1, 0, 2], [0, -2, 0], [2, 0, 1]]);
+        let answers = solve_sat(&problem, true, 10);
+        assert_eq!(answers.len(), 2);
+        for answer in &answers {
+            assert!(answer.is_crossing(P(1, 1)));
+        }
+
+        let problem = make_problem(&[[1, -2, 1], [0, 0, 0]]);
+        assert_eq!(solve_sat(&problem, false, 10).len(), 0);
+    }
+}
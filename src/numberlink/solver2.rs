@@ -1,4 +1,4 @@
-use super::super::{Grid, D, LP, P};
+use super::super::{minimize_conflict, Grid, D, LP, P};
 use super::*;
 use std::fmt;
 
@@ -22,12 +22,15 @@ struct SolverField {
     another_end: Grid<i32>,        // height * width
     has_clue: Grid<bool>,          // height * width
     unused: Grid<bool>,            // height * width
+    bridge: Grid<bool>,            // height * width
     down_left: Grid<bool>,         // height * width
     down_right: Grid<bool>,        // height * width
     left_clue_distance: Grid<i32>, // height * width
     edge: Grid<Edge>,              // (2 * height - 1) * (2 * width - 1)
     inconsistent: bool,
+    has_bridge: bool,
     disallow_unused_cell: bool,
+    canonical_only: bool,
     history: Vec<History>,
 
     // for cut-based pruning
@@ -46,6 +49,7 @@ impl SolverField {
         let mut edge = Grid::new(height * 2 - 1, width * 2 - 1, Edge::Undecided);
         let mut has_clue = Grid::new(height, width, false);
         let mut unused = Grid::new(height, width, false);
+        let mut bridge = Grid::new(height, width, false);
         let mut max_clue = 0;
         for y in 0..height {
             for x in 0..width {
@@ -67,6 +71,18 @@ impl SolverField {
                     if x < width - 1 {
                         edge[LP::of_vertex(pos) + D(0, 1)] = Edge::Blank;
                     }
+                } else if c == BRIDGE {
+                    has_clue[pos] = true;
+                    unused[pos] = true;
+                    bridge[pos] = true;
+                    another_end[pos] = CLOSED_END;
+
+                    for &d in &FOUR_NEIGHBOURS {
+                        let e = LP::of_vertex(pos) + d;
+                        if edge.is_valid_lp(e) {
+                            edge[e] = Edge::Line;
+                        }
+                    }
                 } else if c == NO_CLUE {
                     let id = another_end.index_p(pos) as i32;
                     another_end[pos] = id;
@@ -125,18 +141,22 @@ impl SolverField {
             another_end,
             has_clue,
             unused,
+            bridge,
             down_left,
             down_right,
             left_clue_distance,
             edge,
             inconsistent: false,
+            has_bridge: false,
             disallow_unused_cell,
+            canonical_only: !disallow_unused_cell,
             history: Vec::new(),
             undecided_count,
             open_end_count,
             number_end,
         };
-        if disallow_unused_cell {
+        ret.connect_bridges();
+        if disallow_unused_cell || ret.has_bridge {
             for y in 0..height {
                 for x in 0..width {
                     ret.inspect(P(y, x));
@@ -145,6 +165,55 @@ impl SolverField {
         }
         ret
     }
+    /// Connects the chains on both sides of each maximal run of bridges.
+    fn connect_bridges(&mut self) {
+        let height = self.height();
+        let width = self.width();
+        for y in 0..height {
+            for x in 0..width {
+                let pos = P(y, x);
+                if !self.bridge[pos] {
+                    continue;
+                }
+                self.has_bridge = true;
+                for &d in &[D(0, 1), D(1, 0)] {
+                    if self.bridge.is_valid_p(pos - d) && self.bridge[pos - d] {
+                        continue;
+                    }
+                    let mut end2 = pos + d;
+                    while self.bridge.is_valid_p(end2) && self.bridge[end2] {
+                        end2 = end2 + d;
+                    }
+                    let end1 = pos - d;
+                    if !self.bridge.is_valid_p(end1)
+                        || !self.bridge.is_valid_p(end2)
+                        || (self.unused[end1] && !self.bridge[end1])
+                        || (self.unused[end2] && !self.bridge[end2])
+                    {
+                        self.set_inconsistent();
+                        return;
+                    }
+                    if self.connect(end1, end2) {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+    /// Whether `pos` is a line which is not forced by a bridge.
+    /// Canonical-form pruning must not rely on lines which cannot be rerouted.
+    fn is_free_line(&self, pos: LP) -> bool {
+        if self.get_edge(pos) != Edge::Line {
+            return false;
+        }
+        let LP(y, x) = pos;
+        let (end1, end2) = if y % 2 == 0 {
+            (P(y / 2, x / 2), P(y / 2, x / 2 + 1))
+        } else {
+            (P(y / 2, x / 2), P(y / 2 + 1, x / 2))
+        };
+        !(self.bridge[end1] || self.bridge[end2])
+    }
     fn get_edge(&self, pos: LP) -> Edge {
         if self.edge.is_valid_lp(pos) {
             self.edge[pos]
@@ -237,6 +306,70 @@ impl SolverField {
             }
         }
     }
+    /// Joins the chains at `end1` and `end2`, which are connected by a line.
+    /// Returns `true` if this causes an inconsistency.
+    fn connect(&mut self, end1: P, end2: P) -> bool {
+        let end1_id = self.another_end.index_p(end1) as i32;
+        let end2_id = self.another_end.index_p(end2) as i32;
+
+        let another_end1_id = self.another_end[end1];
+        let another_end2_id = self.another_end[end2];
+
+        // connecting closed ends / closing single chain
+        if another_end1_id == CLOSED_END
+            || another_end2_id == CLOSED_END
+            || another_end1_id == end2_id
+        {
+            return self.set_inconsistent();
+        }
+        match (another_end1_id < 0, another_end2_id < 0) {
+            (true, true) => {
+                if another_end1_id == another_end2_id {
+                    self.close_number_end(-another_end1_id - 1);
+                    self.update_another_end(end1_id, CLOSED_END);
+                    self.update_another_end(end2_id, CLOSED_END);
+                } else {
+                    return self.set_inconsistent();
+                }
+            }
+            (false, true) => {
+                let ae1_x = self.another_end.p(another_end1_id as usize).x();
+                self.update_open_end_count(ae1_x, end1.1, -1);
+                self.update_number_end(-another_end2_id - 1, end2.1, ae1_x);
+                if end1_id != another_end1_id {
+                    self.update_another_end(end1_id, CLOSED_END);
+                }
+                self.update_another_end(another_end1_id, another_end2_id);
+                self.update_another_end(end2_id, CLOSED_END);
+            }
+            (true, false) => {
+                let ae2_x = self.another_end.p(another_end2_id as usize).x();
+                self.update_open_end_count(ae2_x, end2.1, -1);
+                self.update_number_end(-another_end1_id - 1, end1.1, ae2_x);
+                if end2_id != another_end2_id {
+                    self.update_another_end(end2_id, CLOSED_END);
+                }
+                self.update_another_end(another_end2_id, another_end1_id);
+                self.update_another_end(end1_id, CLOSED_END);
+            }
+            (false, false) => {
+                let ae1_x = self.another_end.p(another_end1_id as usize).x();
+                let ae2_x = self.another_end.p(another_end2_id as usize).x();
+                self.update_open_end_count(ae1_x, end1.1, -1);
+                self.update_open_end_count(ae2_x, end2.1, -1);
+                self.update_open_end_count(ae1_x, ae2_x, 1);
+                if end1_id != another_end1_id {
+                    self.update_another_end(end1_id, CLOSED_END);
+                }
+                self.update_another_end(another_end1_id, another_end2_id);
+                if end2_id != another_end2_id {
+                    self.update_another_end(end2_id, CLOSED_END);
+                }
+                self.update_another_end(another_end2_id, another_end1_id);
+            }
+        }
+        false
+    }
     /// Decide edge `cd`.
     /// `cd` must be in universal-coordination.
     fn decide_edge(&mut self, pos: LP, state: Edge) -> bool {
@@ -260,66 +393,8 @@ impl SolverField {
             end1 = P(y / 2, x / 2);
             end2 = P(y / 2 + 1, x / 2);
         }
-        let end1_id = self.another_end.index_p(end1) as i32;
-        let end2_id = self.another_end.index_p(end2) as i32;
-
-        if state == Edge::Line {
-            let another_end1_id = self.another_end[end1];
-            let another_end2_id = self.another_end[end2];
-
-            // connecting closed ends / closing single chain
-            if another_end1_id == CLOSED_END
-                || another_end2_id == CLOSED_END
-                || another_end1_id == end2_id
-            {
-                return self.set_inconsistent();
-            }
-            match (another_end1_id < 0, another_end2_id < 0) {
-                (true, true) => {
-                    if another_end1_id == another_end2_id {
-                        self.close_number_end(-another_end1_id - 1);
-                        self.update_another_end(end1_id, CLOSED_END);
-                        self.update_another_end(end2_id, CLOSED_END);
-                    } else {
-                        return self.set_inconsistent();
-                    }
-                }
-                (false, true) => {
-                    let ae1_x = self.another_end.p(another_end1_id as usize).x();
-                    self.update_open_end_count(ae1_x, end1.1, -1);
-                    self.update_number_end(-another_end2_id - 1, end2.1, ae1_x);
-                    if end1_id != another_end1_id {
-                        self.update_another_end(end1_id, CLOSED_END);
-                    }
-                    self.update_another_end(another_end1_id, another_end2_id);
-                    self.update_another_end(end2_id, CLOSED_END);
-                }
-                (true, false) => {
-                    let ae2_x = self.another_end.p(another_end2_id as usize).x();
-                    self.update_open_end_count(ae2_x, end2.1, -1);
-                    self.update_number_end(-another_end1_id - 1, end1.1, ae2_x);
-                    if end2_id != another_end2_id {
-                        self.update_another_end(end2_id, CLOSED_END);
-                    }
-                    self.update_another_end(another_end2_id, another_end1_id);
-                    self.update_another_end(end1_id, CLOSED_END);
-                }
-                (false, false) => {
-                    let ae1_x = self.another_end.p(another_end1_id as usize).x();
-                    let ae2_x = self.another_end.p(another_end2_id as usize).x();
-                    self.update_open_end_count(ae1_x, end1.1, -1);
-                    self.update_open_end_count(ae2_x, end2.1, -1);
-                    self.update_open_end_count(ae1_x, ae2_x, 1);
-                    if end1_id != another_end1_id {
-                        self.update_another_end(end1_id, CLOSED_END);
-                    }
-                    self.update_another_end(another_end1_id, another_end2_id);
-                    if end2_id != another_end2_id {
-                        self.update_another_end(end2_id, CLOSED_END);
-                    }
-                    self.update_another_end(another_end2_id, another_end1_id);
-                }
-            }
+        if state == Edge::Line && self.connect(end1, end2) {
+            return true;
         }
 
         // update edge state
@@ -330,33 +405,30 @@ impl SolverField {
         }
 
         // ensure canonical form
-        if state == Edge::Line {
+        if state == Edge::Line && self.canonical_only {
             if y % 2 == 0 {
-                if !self.down_right[P(y / 2, x / 2)] && self.get_edge(pos + D(1, -1)) == Edge::Line
-                {
+                if !self.down_right[P(y / 2, x / 2)] && self.is_free_line(pos + D(1, -1)) {
                     return self.set_inconsistent();
                 }
-                if !self.down_left[P(y / 2, x / 2 + 1)]
-                    && self.get_edge(pos + D(1, 1)) == Edge::Line
-                {
+                if !self.down_left[P(y / 2, x / 2 + 1)] && self.is_free_line(pos + D(1, 1)) {
                     return self.set_inconsistent();
                 }
 
-                if self.get_edge(pos + D(-2, 0)) == Edge::Line {
+                if self.is_free_line(pos + D(-2, 0)) {
                     if self.decide_edge(pos + D(-1, -1), Edge::Blank) {
                         return true;
                     }
                     if self.decide_edge(pos + D(-1, 1), Edge::Blank) {
                         return true;
                     }
-                } else if self.get_edge(pos + D(-1, -1)) == Edge::Line {
+                } else if self.is_free_line(pos + D(-1, -1)) {
                     if self.decide_edge(pos + D(-2, 0), Edge::Blank) {
                         return true;
                     }
                     if self.decide_edge(pos + D(-1, 1), Edge::Blank) {
                         return true;
                     }
-                } else if self.get_edge(pos + D(-1, 1)) == Edge::Line {
+                } else if self.is_free_line(pos + D(-1, 1)) {
                     if self.decide_edge(pos + D(-2, 0), Edge::Blank) {
                         return true;
                     }
@@ -365,14 +437,14 @@ impl SolverField {
                     }
                 }
 
-                if self.get_edge(pos + D(2, 0)) == Edge::Line {
+                if self.is_free_line(pos + D(2, 0)) {
                     if self.decide_edge(pos + D(1, -1), Edge::Blank) {
                         return true;
                     }
                     if self.decide_edge(pos + D(1, 1), Edge::Blank) {
                         return true;
                     }
-                } else if self.get_edge(pos + D(1, -1)) == Edge::Line {
+                } else if self.is_free_line(pos + D(1, -1)) {
                     if self.decide_edge(pos + D(2, 0), Edge::Blank) {
                         return true;
                     }
@@ -389,7 +461,7 @@ impl SolverField {
                             return true;
                         }
                     }
-                } else if self.get_edge(pos + D(1, 1)) == Edge::Line {
+                } else if self.is_free_line(pos + D(1, 1)) {
                     if self.decide_edge(pos + D(2, 0), Edge::Blank) {
                         return true;
                     }
@@ -408,23 +480,21 @@ impl SolverField {
                     }
                 }
             } else {
-                if !self.down_left[P(y / 2, x / 2)] && self.get_edge(pos + D(-1, -1)) == Edge::Line
-                {
+                if !self.down_left[P(y / 2, x / 2)] && self.is_free_line(pos + D(-1, -1)) {
                     return self.set_inconsistent();
                 }
-                if !self.down_right[P(y / 2, x / 2)] && self.get_edge(pos + D(-1, 1)) == Edge::Line
-                {
+                if !self.down_right[P(y / 2, x / 2)] && self.is_free_line(pos + D(-1, 1)) {
                     return self.set_inconsistent();
                 }
 
-                if self.get_edge(pos + D(0, -2)) == Edge::Line {
+                if self.is_free_line(pos + D(0, -2)) {
                     if self.decide_edge(pos + D(-1, -1), Edge::Blank) {
                         return true;
                     }
                     if self.decide_edge(pos + D(1, -1), Edge::Blank) {
                         return true;
                     }
-                } else if self.get_edge(pos + D(-1, -1)) == Edge::Line {
+                } else if self.is_free_line(pos + D(-1, -1)) {
                     if self.decide_edge(pos + D(0, -2), Edge::Blank) {
                         return true;
                     }
@@ -441,7 +511,7 @@ impl SolverField {
                             return true;
                         }
                     }
-                } else if self.get_edge(pos + D(1, -1)) == Edge::Line {
+                } else if self.is_free_line(pos + D(1, -1)) {
                     if self.decide_edge(pos + D(0, -2), Edge::Blank) {
                         return true;
                     }
@@ -450,14 +520,14 @@ impl SolverField {
                     }
                 }
 
-                if self.get_edge(pos + D(0, 2)) == Edge::Line {
+                if self.is_free_line(pos + D(0, 2)) {
                     if self.decide_edge(pos + D(-1, 1), Edge::Blank) {
                         return true;
                     }
                     if self.decide_edge(pos + D(1, 1), Edge::Blank) {
                         return true;
                     }
-                } else if self.get_edge(pos + D(-1, 1)) == Edge::Line {
+                } else if self.is_free_line(pos + D(-1, 1)) {
                     if self.decide_edge(pos + D(0, 2), Edge::Blank) {
                         return true;
                     }
@@ -474,7 +544,7 @@ impl SolverField {
                             return true;
                         }
                     }
-                } else if self.get_edge(pos + D(1, 1)) == Edge::Line {
+                } else if self.is_free_line(pos + D(1, 1)) {
                     if self.decide_edge(pos + D(0, 2), Edge::Blank) {
                         return true;
                     }
@@ -522,14 +592,16 @@ impl SolverField {
                 if self.another_end.is_valid_p(pos2) {
                     let another_end2 = self.another_end[pos2];
                     if another_end2 < -1 {
-                        if self.decide_edge(
-                            LP::of_vertex(pos) + d,
-                            if another_end == another_end2 {
-                                Edge::Line
-                            } else {
-                                Edge::Blank
-                            },
-                        ) {
+                        // Adjacent ends of the same number may be joined elsewhere unless
+                        // only canonical answers are searched for
+                        let state = if another_end != another_end2 {
+                            Edge::Blank
+                        } else if self.canonical_only && !self.has_bridge {
+                            Edge::Line
+                        } else {
+                            continue;
+                        };
+                        if self.decide_edge(LP::of_vertex(pos) + d, state) {
                             return true;
                         }
                     }
@@ -646,24 +718,85 @@ struct AnswerInfo {
     found_not_fully_filled: bool,
 }
 
-pub fn solve2(
+/// Options for `solve2`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SolverOption {
+    /// The maximum number of answers to enumerate; all answers are enumerated if `None`.
+    pub limit: Option<usize>,
+    /// Requires every cell except walls (`UNUSED`) to be passed by a line.
+    /// Otherwise, answers leaving some cells unused are also accepted.
+    pub every_cell_used: bool,
+    /// Stops the search as soon as an answer leaving some cell unused is found.
+    /// `AnswerDetail::found_not_fully_filled` tells whether it happened.
+    pub terminate_on_not_fully_filled: bool,
+}
+
+/// Enumerates the answers of `problem`.
+///
+/// Unless `opt.every_cell_used` is set, only answers in a canonical form are enumerated:
+/// an answer is pruned if its lines can be shortened without changing the connections of
+/// the clues. Lines crossing on bridges (`BRIDGE`) are never rerouted.
+pub fn solve2(problem: &Grid<Clue>, opt: &SolverOption) -> AnswerDetail {
+    solve2_with_segments(problem, &[], opt)
+}
+
+/// Same as `solve2`, but searches only for the answers containing the line segments in `lines`.
+///
+/// If no answer contains all of them, returns a minimal set of the segments which cannot be
+/// drawn together instead, in the coordinates of `LinePlacement::get`.
+/// The set is empty if the problem has no answer at all.
+pub fn solve2_from(
     problem: &Grid<Clue>,
-    limit: Option<usize>,
-    disallow_unused_cell: bool,
-    terminate_on_not_fully_filled: bool,
-) -> AnswerDetail {
-    let mut solver_field = SolverField::new(problem, disallow_unused_cell);
+    lines: &LinePlacement,
+    opt: &SolverOption,
+) -> Result<AnswerDetail, Vec<LP>> {
+    let mut segments = vec![];
+    for y in 0..(problem.height() * 2 - 1) {
+        for x in 0..(problem.width() * 2 - 1) {
+            if y % 2 != x % 2 && lines.get(LP(y, x)) {
+                segments.push(LP(y, x));
+            }
+        }
+    }
+
+    let ans = solve2_with_segments(problem, &segments, opt);
+    if !ans.answers.is_empty() || ans.found_not_fully_filled {
+        return Ok(ans);
+    }
+    let check_opt = SolverOption {
+        limit: Some(1),
+        terminate_on_not_fully_filled: false,
+        ..*opt
+    };
+    Err(minimize_conflict(&segments, |segments| {
+        solve2_with_segments(problem, segments, &check_opt)
+            .answers
+            .is_empty()
+    }))
+}
+
+fn solve2_with_segments(problem: &Grid<Clue>, segments: &[LP], opt: &SolverOption) -> AnswerDetail {
+    let mut solver_field = SolverField::new(problem, opt.every_cell_used);
+    if !segments.is_empty() {
+        // The given segments may be a part of a non-canonical answer
+        solver_field.canonical_only = false;
+        for &pos in segments {
+            solver_field.decide_edge(pos, Edge::Line);
+        }
+    }
     let mut answer_info = AnswerInfo {
         answers: Vec::new(),
-        limit,
-        terminate_on_not_fully_filled,
+        limit: opt.limit,
+        terminate_on_not_fully_filled: opt.terminate_on_not_fully_filled,
         found_not_fully_filled: false,
     };
     let mut n_steps = 0u64;
 
-    search(0, 0, &mut solver_field, &mut answer_info, &mut n_steps, 0);
+    if !solver_field.inconsistent {
+        search(0, 0, &mut solver_field, &mut answer_info, &mut n_steps, 0);
+    }
 
-    let fully_checked = if let Some(limit) = limit {
+    let fully_checked = if let Some(limit) = opt.limit {
         limit == answer_info.answers.len()
     } else {
         true
@@ -742,12 +875,14 @@ fn search(
                     line_chain = 0;
                 }
             }
-            if line_chain > 0 && field.get_edge(LP(y * 2 - 1, x * 2)) == Edge::Line {
-                if field.get_edge(LP(y * 2 - 1, (x - line_chain) * 2)) == Edge::Line
-                    && field.left_clue_distance[P(y - 1, x)] >= line_chain
-                {
-                    return false;
-                }
+            if field.canonical_only
+                && !field.has_bridge
+                && line_chain > 0
+                && field.get_edge(LP(y * 2 - 1, x * 2)) == Edge::Line
+                && field.get_edge(LP(y * 2 - 1, (x - line_chain) * 2)) == Edge::Line
+                && field.left_clue_distance[P(y - 1, x)] >= line_chain
+            {
+                return false;
             }
         }
     }
@@ -824,19 +959,24 @@ fn search(
 
         let right_effective = right || (field.get_edge(LP(y * 2, x * 2 + 1)) == Edge::Line);
         let down_effective = down || (field.get_edge(LP(y * 2 + 1, x * 2)) == Edge::Line);
-        if right_effective && down_effective {
-            if !field.down_right[P(y, x)] {
-                continue;
-            }
+        if right_effective
+            && down_effective
+            && field.canonical_only
+            && !field.has_bridge
+            && !field.down_right[P(y, x)]
+        {
+            continue;
         }
-        if right_effective && field.get_edge(LP(y * 2 - 1, x * 2 + 2)) == Edge::Line {
-            if line_chain > 0
-                && field.get_edge(LP(y * 2 - 2, x * 2 + 1)) == Edge::Blank
-                && field.get_edge(LP(y * 2 - 1, (x - line_chain) * 2)) == Edge::Line
-                && field.left_clue_distance[P(y - 1, x + 1)] >= line_chain + 1
-            {
-                continue;
-            }
+        if right_effective
+            && field.get_edge(LP(y * 2 - 1, x * 2 + 2)) == Edge::Line
+            && field.canonical_only
+            && !field.has_bridge
+            && line_chain > 0
+            && field.get_edge(LP(y * 2 - 2, x * 2 + 1)) == Edge::Blank
+            && field.get_edge(LP(y * 2 - 1, (x - line_chain) * 2)) == Edge::Line
+            && field.left_clue_distance[P(y - 1, x + 1)] > line_chain
+        {
+            continue;
         }
         field.add_checkpoint();
         let mut inconsistent = false;
@@ -876,26 +1016,130 @@ fn search(
 #[cfg(test)]
 mod tests {
     use super::*;
-    #[test]
-    fn test_solver_unused_cells() {
-        let problem_base = [
-            [0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0],
-            [1, 2, -1, 2, 1],
-            [-1, -1, -1, -1, -1],
-        ];
+
+    fn make_problem<T: AsRef<[i32]>>(problem_base: &[T]) -> Grid<Clue> {
         let mut problem = Grid::new(
             problem_base.len() as i32,
-            problem_base[0].len() as i32,
+            problem_base[0].as_ref().len() as i32,
             NO_CLUE,
         );
         for y in 0..problem_base.len() {
-            for x in 0..problem_base[0].len() {
-                problem[P(y as i32, x as i32)] = Clue(problem_base[y][x]);
+            for x in 0..problem_base[0].as_ref().len() {
+                problem[P(y as i32, x as i32)] = Clue(problem_base[y].as_ref()[x]);
             }
         }
+        problem
+    }
+
+    fn is_fully_filled(problem: &Grid<Clue>, answer: &LinePlacement) -> bool {
+        (0..problem.height()).all(|y| {
+            (0..problem.width()).all(|x| problem[P(y, x)] == UNUSED || !answer.isolated(P(y, x)))
+        })
+    }
 
-        let ans = solve2(&problem, None, false, false);
+    #[test]
+    fn test_solver_unused_cells() {
+        let problem = make_problem(&[
+            [0, 0, 0, 0, 0],
+            [0, 0, 0, 0, 0],
+            [1, 2, -1, 2, 1],
+            [-1, -1, -1, -1, -1],
+        ]);
+
+        let ans = solve2(&problem, &SolverOption::default());
+        assert_eq!(ans.len(), 1);
+    }
+
+    #[test]
+    fn test_solver_every_cell_used() {
+        let problem = make_problem(&[[1, 0, 0], [0, 0, 1]]);
+
+        let ans = solve2(&problem, &SolverOption::default());
+        assert_eq!(ans.len(), 1);
+        assert!(!is_fully_filled(&problem, &ans[0]));
+
+        let opt = SolverOption {
+            every_cell_used: true,
+            ..Default::default()
+        };
+        let ans = solve2(&problem, &opt);
         assert_eq!(ans.len(), 1);
+        assert!(is_fully_filled(&problem, &ans[0]));
+
+        let problem = make_problem(&[[1, 0, 1], [0, 0, 0]]);
+        assert_eq!(solve2(&problem, &opt).len(), 0);
+
+        // Adjacent numbers are joined by a detour
+        let problem = make_problem(&[[1, 1], [0, 0]]);
+        assert_eq!(solve2(&problem, &opt).len(), 1);
+        assert_eq!(solve2(&problem, &SolverOption::default()).len(), 1);
+    }
+
+    #[test]
+    fn test_solver_bridges() {
+        let problem = make_problem(&[[0, 1, 0], [2, -2, 2], [0, 1, 0]]);
+
+        let ans = solve2(&problem, &SolverOption::default());
+        assert_eq!(ans.len(), 1);
+        assert!(ans[0].is_crossing(P(1, 1)));
+
+        let opt = SolverOption {
+            every_cell_used: true,
+            ..Default::default()
+        };
+        assert_eq!(solve2(&problem, &opt).len(), 0);
+
+        let problem = make_problem(&[[1, 0, 2], [0, -2, 0], [2, 0, 1]]);
+        let ans = solve2(&problem, &opt);
+        assert_eq!(ans.len(), 2);
+        for answer in &ans.answers {
+            let groups = answer.extract_chain_groups().unwrap();
+            assert_eq!(groups[P(1, 0)], groups[P(1, 1)]);
+            assert_eq!(groups[P(1, 0)], groups[P(1, 2)]);
+            assert_eq!(groups[P(0, 1)], groups[P(2, 1)]);
+            assert_ne!(groups[P(0, 0)], groups[P(0, 2)]);
+        }
+
+        // Lines must detour to reach the bridge
+        let problem = make_problem(&[
+            [1, 0, 0, 0],
+            [0, 0, -2, 2],
+            [0, 0, 0, 0],
+            [2, 0, 1, 0],
+        ]);
+        let ans = solve2(&problem, &SolverOption::default());
+        assert_eq!(ans.len(), 1);
+        assert!(ans[0].is_crossing(P(1, 2)));
+
+        // A bridge on the border or next to a wall
+        for problem_base in &[[[1, -2, 1], [0, 0, 0]], [[-1, -2, 0], [0, 0, 0]]] {
+            let problem = make_problem(problem_base);
+            assert_eq!(solve2(&problem, &SolverOption::default()).len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_solver_from_lines() {
+        let problem = make_problem(&[[1, 0, 1], [0, 0, 0]]);
+        let mut lines = LinePlacement::new(2, 3);
+        lines.set_down(P(0, 0), true);
+        let ans = solve2_from(&problem, &lines, &SolverOption::default()).unwrap();
+        assert_eq!(ans.len(), 2);
+        for answer in &ans.answers {
+            assert!(answer.down(P(0, 0)));
+            assert!(!answer.right(P(0, 0)));
+        }
+
+        let problem = make_problem(&[[1, 0, 1], [2, 0, 2]]);
+        let mut lines = LinePlacement::new(2, 3);
+        lines.set_right(P(0, 0), true);
+        lines.set_down(P(0, 1), true);
+        let conflict = solve2_from(&problem, &lines, &SolverOption::default()).err();
+        assert_eq!(conflict, Some(vec![LP(1, 2)]));
+
+        let problem = make_problem(&[[1, 2], [2, 1]]);
+        let lines = LinePlacement::new(2, 2);
+        let conflict = solve2_from(&problem, &lines, &SolverOption::default()).err();
+        assert_eq!(conflict, Some(vec![]));
     }
 }
\ No newline at end of file
@@ -0,0 +1,473 @@
+//! Generation of puzzle packs: sets of problems which are distinct up to symmetry,
+//! along with their answers and difficulty.
+
+use std::collections::HashSet;
+
+use rand::Rng;
+use serde::Serialize;
+
+use crate::common::{Grid, Symmetry, LP, P};
+use crate::grid_loop::Edge;
+use crate::io::write_grid;
+use crate::{endview, numberlink, slitherlink};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Genre {
+    Numberlink,
+    Slitherlink,
+    Endview,
+}
+
+impl Genre {
+    /// Returns the genre named `name`, which is one of `numlin`, `slither` and `endview`.
+    pub fn from_name(name: &str) -> Option<Genre> {
+        match name {
+            "numlin" => Some(Genre::Numberlink),
+            "slither" => Some(Genre::Slitherlink),
+            "endview" => Some(Genre::Endview),
+            _ => None,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Genre::Numberlink => "numlin",
+            Genre::Slitherlink => "slither",
+            Genre::Endview => "endview",
+        }
+    }
+    /// Returns the abbreviation of the genre in `Seed`.
+    pub fn seed_genre(self) -> &'static str {
+        match self {
+            Genre::Numberlink => "nl",
+            Genre::Slitherlink => "sl",
+            Genre::Endview => "ev",
+        }
+    }
+}
+
+pub struct PackOption {
+    pub genre: Genre,
+    pub height: i32,
+    pub width: i32,
+    /// The number of problems in the pack.
+    pub count: usize,
+    /// The number of letters in Endview problems.
+    pub n_alpha: i32,
+    /// The symmetry of the clues in Numberlink and Slitherlink problems.
+    pub symmetry: Symmetry,
+    /// The minimum length of lines in Numberlink problems.
+    pub chain_threshold: i32,
+    /// The number of clues in Slitherlink problems.
+    pub num_clues: i32,
+    /// The number of clue placements tried for each Slitherlink problem before the call of
+    /// the generator is regarded as a failure.
+    pub placement_trials: usize,
+    /// The maximum number of calls of the generator, including the ones which fail
+    /// or produce a duplicate.
+    pub max_trials: usize,
+}
+
+/// A problem in a pack. `problem` and `answer` are in the format of `io::write_grid`:
+///
+/// - Numberlink: the problem is the same as `numberlink::write_penciloid_problem`, and the answer
+///   has the number of the line passing through each cell.
+/// - Slitherlink: the problem is the same as `slitherlink::write_penciloid_problem`, and
+///   the answer is the `(2 * height + 1) x (2 * width + 1)` lattice of the vertices (`+`),
+///   the edges (`-` or `|` for a line and `x` for a blank) and the clues.
+/// - Endview: the problem is the `(size + 2) x (size + 2)` grid with the clues on the border,
+///   and the answer has the letter in each cell (`.` for an empty cell).
+#[derive(Serialize, Debug, Clone)]
+pub struct PackEntry {
+    pub problem: String,
+    pub answer: String,
+    /// The URL of puzz.link, if supported for the genre.
+    pub url: Option<String>,
+    /// The number of search steps of `numberlink::solve2` for Numberlink, and the number of
+    /// cells decided by trials in `endview::Field::trial_and_error` for Endview.
+    /// Slitherlink problems are generated to be solved without trial and error, and this is
+    /// the number of edges which are not decided by any clue alone instead.
+    pub difficulty: u64,
+}
+
+/// Generates up to `opt.count` problems which are distinct up to the rotations and
+/// reflections of the grid. Fewer problems are returned if `opt.max_trials` is exhausted.
+pub fn generate_pack<R: Rng>(opt: &PackOption, rng: &mut R) -> Vec<PackEntry> {
+    let (height, width) = (opt.height, opt.width);
+    match opt.genre {
+        Genre::Numberlink => {
+            let mut generator = numberlink::PlacementGenerator::new(height, width);
+            let generator_opt = numberlink::GeneratorOption {
+                chain_threshold: opt.chain_threshold,
+                endpoint_constraint: None,
+                forbid_adjacent_clue: false,
+                symmetry: opt.symmetry,
+                clue_limit: None,
+                prioritized_extension: false,
+            };
+            collect_distinct(opt, || {
+                let (problem, n_steps) = generator.generate_and_measure(&generator_opt, rng)?;
+                numberlink_entry(&problem, n_steps)
+            })
+        }
+        Genre::Slitherlink => {
+            let dic = slitherlink::Dictionary::shared();
+            collect_distinct(opt, || {
+                let problem = slitherlink::generate_problem(
+                    height,
+                    width,
+                    opt.num_clues,
+                    opt.symmetry,
+                    dic,
+                    opt.placement_trials,
+                    rng,
+                )?;
+                slitherlink_entry(&problem, dic)
+            })
+        }
+        Genre::Endview => collect_distinct(opt, || {
+            let problem = endview::generate(height, opt.n_alpha, rng)?;
+            endview_entry(&problem)
+        }),
+    }
+}
+
+fn collect_distinct<F>(opt: &PackOption, mut generate: F) -> Vec<PackEntry>
+where
+    F: FnMut() -> Option<(Vec<i32>, PackEntry)>,
+{
+    let mut keys = HashSet::new();
+    let mut ret = vec![];
+    for _ in 0..opt.max_trials {
+        if ret.len() >= opt.count {
+            break;
+        }
+        if let Some((key, entry)) = generate() {
+            if keys.insert(key) {
+                ret.push(entry);
+            }
+        }
+    }
+    ret
+}
+
+/// Returns the smallest image of `grid` under the rotations and reflections keeping its shape,
+/// which identifies the problem up to symmetry. If `relabel` is `true`, positive values are
+/// renumbered in the order of appearance as they are only labels (e.g. Numberlink numbers).
+fn canonical_key(grid: &Grid<i32>, relabel: bool) -> Vec<i32> {
+    let (height, width) = (grid.height(), grid.width());
+    let mut ret: Option<Vec<i32>> = None;
+    for t in 0..8 {
+        let transpose = (t & 4) != 0;
+        if transpose && height != width {
+            continue;
+        }
+        let mut image = vec![];
+        let mut labels = vec![];
+        for y in 0..height {
+            for x in 0..width {
+                let y2 = if (t & 1) != 0 { height - 1 - y } else { y };
+                let x2 = if (t & 2) != 0 { width - 1 - x } else { x };
+                let v = grid[if transpose { P(x2, y2) } else { P(y2, x2) }];
+                if relabel && v > 0 {
+                    let label = match labels.iter().position(|&l| l == v) {
+                        Some(i) => i,
+                        None => {
+                            labels.push(v);
+                            labels.len() - 1
+                        }
+                    };
+                    image.push(label as i32 + 1);
+                } else {
+                    image.push(v);
+                }
+            }
+        }
+        match ret {
+            Some(ref r) if *r <= image => (),
+            _ => ret = Some(image),
+        }
+    }
+    ret.unwrap()
+}
+
+fn grid_to_string<T: Clone, F: Fn(&T) -> String>(grid: &Grid<T>, converter: F) -> String {
+    let mut buf = vec![];
+    write_grid(&mut buf, grid, converter).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+fn numberlink_entry(
+    problem: &Grid<numberlink::Clue>,
+    n_steps: u64,
+) -> Option<(Vec<i32>, PackEntry)> {
+    let (height, width) = (problem.height(), problem.width());
+    let opt = numberlink::SolverOption {
+        limit: Some(2),
+        ..Default::default()
+    };
+    let answer = numberlink::solve2(problem, &opt);
+    if answer.len() != 1 {
+        return None;
+    }
+    let ids = answer.answers[0].extract_chain_groups()?;
+    let mut numbers = vec![0; (height * width) as usize];
+    for i in 0..(height * width) as usize {
+        if problem[i].0 > 0 {
+            numbers[ids[i] as usize] = problem[i].0;
+        }
+    }
+    let mut lines = Grid::new(height, width, 0);
+    for i in 0..(height * width) as usize {
+        lines[i] = if ids[i] >= 0 {
+            numbers[ids[i] as usize]
+        } else {
+            0
+        };
+    }
+
+    let mut clues = Grid::new(height, width, 0);
+    for i in 0..(height * width) as usize {
+        clues[i] = problem[i].0;
+    }
+    let mut buf = vec![];
+    numberlink::write_penciloid_problem(&mut buf, problem).ok()?;
+    let entry = PackEntry {
+        problem: String::from_utf8(buf).ok()?,
+        answer: grid_to_string(&lines, |&n| {
+            if n > 0 {
+                n.to_string()
+            } else {
+                String::from(".")
+            }
+        }),
+        url: Some(crate::encode_url_numlin(problem)),
+        difficulty: n_steps,
+    };
+    Some((canonical_key(&clues, true), entry))
+}
+
+fn slitherlink_entry(
+    problem: &Grid<slitherlink::Clue>,
+    dic: &slitherlink::Dictionary,
+) -> Option<(Vec<i32>, PackEntry)> {
+    let (height, width) = (problem.height(), problem.width());
+    let mut field = slitherlink::Field::new(problem, dic);
+    field.check_all_cell();
+    if field.inconsistent() || !field.fully_solved() {
+        return None;
+    }
+    let mut lattice = Grid::new(height * 2 + 1, width * 2 + 1, String::new());
+    for y in 0..(height * 2 + 1) {
+        for x in 0..(width * 2 + 1) {
+            let pos = LP(y, x);
+            lattice[pos] = match (y % 2, x % 2) {
+                (0, 0) => String::from("+"),
+                (1, 1) => match problem[P(y / 2, x / 2)] {
+                    slitherlink::Clue(n) if n >= 0 => n.to_string(),
+                    _ => String::from("."),
+                },
+                _ => String::from(match field.get_edge(pos) {
+                    Edge::Line if y % 2 == 0 => "-",
+                    Edge::Line => "|",
+                    _ => "x",
+                }),
+            };
+        }
+    }
+
+    let mut clues = Grid::new(height, width, 0);
+    for i in 0..(height * width) as usize {
+        clues[i] = problem[i].0;
+    }
+    let mut buf = vec![];
+    slitherlink::write_penciloid_problem(&mut buf, problem).ok()?;
+    let entry = PackEntry {
+        problem: String::from_utf8(buf).ok()?,
+        answer: grid_to_string(&lattice, |s| s.clone()),
+        url: Some(slitherlink::encode_url(problem)),
+        difficulty: slitherlink_difficulty(problem, dic),
+    };
+    Some((canonical_key(&clues, false), entry))
+}
+
+/// Counts the edges which are not decided by any clue alone, as those require combining clues.
+fn slitherlink_difficulty(problem: &Grid<slitherlink::Clue>, dic: &slitherlink::Dictionary) -> u64 {
+    let (height, width) = (problem.height(), problem.width());
+    let mut decided = Grid::new(height * 2 + 1, width * 2 + 1, false);
+    for i in 0..(height * width) as usize {
+        if problem[i].0 < 0 {
+            continue;
+        }
+        let mut single = Grid::new(height, width, slitherlink::Clue(-1));
+        single[i] = problem[i];
+        let mut field = slitherlink::Field::new(&single, dic);
+        field.check_all_cell();
+        for y in 0..(height * 2 + 1) {
+            for x in 0..(width * 2 + 1) {
+                if field.get_edge(LP(y, x)) != Edge::Undecided {
+                    decided[LP(y, x)] = true;
+                }
+            }
+        }
+    }
+    let mut ret = 0;
+    for y in 0..(height * 2 + 1) {
+        for x in 0..(width * 2 + 1) {
+            if y % 2 != x % 2 && !decided[LP(y, x)] {
+                ret += 1;
+            }
+        }
+    }
+    ret
+}
+
+fn endview_entry(problem: &endview::Problem) -> Option<(Vec<i32>, PackEntry)> {
+    let size = problem.size();
+    let mut field = endview::Field::from_problem(problem);
+    let n_trials = field.trial_and_error();
+    if field.inconsistent() || !field.is_solved() {
+        return None;
+    }
+    let letter = |n: i32| {
+        if n >= 0 {
+            ((b'A' + n as u8) as char).to_string()
+        } else {
+            String::from(".")
+        }
+    };
+    let mut cells = Grid::new(size, size, 0);
+    for y in 0..size {
+        for x in 0..size {
+            cells[P(y, x)] = field.get_value(P(y, x)).0;
+        }
+    }
+
+    // The clues are placed around the grid so that the transformations of the grid
+    // move them to the right places
+    let mut clues = Grid::new(size + 2, size + 2, endview::NO_CLUE.0);
+    for i in 0..size {
+        clues[P(i + 1, 0)] = problem.get_clue(endview::ClueLoc::Left, i).0;
+        clues[P(i + 1, size + 1)] = problem.get_clue(endview::ClueLoc::Right, i).0;
+        clues[P(0, i + 1)] = problem.get_clue(endview::ClueLoc::Top, i).0;
+        clues[P(size + 1, i + 1)] = problem.get_clue(endview::ClueLoc::Bottom, i).0;
+    }
+    let entry = PackEntry {
+        problem: grid_to_string(&clues, |&n| letter(n)),
+        answer: grid_to_string(&cells, |&n| letter(n)),
+        url: None,
+        difficulty: n_trials as u64,
+    };
+    Some((canonical_key(&clues, false), entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_key() {
+        let mut grid = Grid::new(2, 3, 0);
+        grid[P(0, 0)] = 2;
+        grid[P(0, 2)] = 1;
+        grid[P(1, 1)] = 2;
+        grid[P(1, 2)] = 1;
+
+        let mut rotated = Grid::new(2, 3, 0);
+        for y in 0..2 {
+            for x in 0..3 {
+                rotated[P(1 - y, 2 - x)] = grid[P(y, x)];
+            }
+        }
+        assert_eq!(canonical_key(&grid, false), canonical_key(&rotated, false));
+
+        let mut swapped = grid.clone();
+        for i in 0..6 {
+            swapped[i] = [0, 2, 1][grid[i] as usize];
+        }
+        assert_ne!(canonical_key(&grid, false), canonical_key(&swapped, false));
+        assert_eq!(canonical_key(&grid, true), canonical_key(&swapped, true));
+
+        grid[P(1, 0)] = 3;
+        assert_ne!(canonical_key(&grid, true), canonical_key(&rotated, true));
+    }
+
+    #[test]
+    fn test_generate_pack() {
+        for &(genre, size) in &[
+            (Genre::Numberlink, 6),
+            (Genre::Slitherlink, 5),
+            (Genre::Endview, 4),
+        ] {
+            let mut rng = crate::SeedRng::new(0);
+            let opt = PackOption {
+                genre,
+                height: size,
+                width: size,
+                count: 2,
+                n_alpha: 3,
+                symmetry: Symmetry::none(),
+                chain_threshold: 3,
+                num_clues: size * size * 2 / 5,
+                placement_trials: 10,
+                max_trials: 100,
+            };
+            let pack = generate_pack(&opt, &mut rng);
+            assert_eq!(pack.len(), 2, "{:?}", genre);
+            // Endview clues are placed around the grid
+            let n = if genre == Genre::Endview {
+                size + 2
+            } else {
+                size
+            };
+            for entry in &pack {
+                assert!(entry.problem.starts_with(&format!("{} {}\n", n, n)));
+                assert!(!entry.answer.is_empty());
+                assert_eq!(entry.url.is_some(), genre != Genre::Endview);
+            }
+            assert_ne!(pack[0].problem, pack[1].problem);
+        }
+    }
+
+    #[test]
+    fn test_generate_symmetric_pack() {
+        let symmetry = Symmetry::from_names("dyad").unwrap();
+        for &genre in &[Genre::Numberlink, Genre::Slitherlink] {
+            let mut rng = crate::SeedRng::new(0);
+            let opt = PackOption {
+                genre,
+                height: 6,
+                width: 6,
+                count: 2,
+                n_alpha: 3,
+                symmetry,
+                chain_threshold: 3,
+                num_clues: 14,
+                placement_trials: 10,
+                max_trials: 100,
+            };
+            let pack = generate_pack(&opt, &mut rng);
+            assert_eq!(pack.len(), 2, "{:?}", genre);
+            for entry in &pack {
+                // Numbers of Numberlink are separated by spaces
+                let cells = entry
+                    .problem
+                    .lines()
+                    .skip(1)
+                    .map(|row| {
+                        if row.contains(' ') {
+                            row.split(' ').map(String::from).collect()
+                        } else {
+                            row.chars().map(|c| c.to_string()).collect()
+                        }
+                    })
+                    .collect::<Vec<Vec<_>>>();
+                let is_clue = |y: usize, x: usize| cells[y][x] != ".";
+                for y in 0..6 {
+                    for x in 0..6 {
+                        assert_eq!(is_clue(y, x), is_clue(5 - y, 5 - x), "{}", entry.problem);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Drawing of `Picture`s on an HTML canvas.
+
+use std::f64::consts::PI;
+
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use super::{Picture, Shape};
+
+impl Picture {
+    /// Clears the area of `width` x `height` of `ctx` and draws the picture in it,
+    /// scaled to fit while keeping the aspect ratio.
+    pub fn paint(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        width: f64,
+        height: f64,
+    ) -> Result<(), JsValue> {
+        let scale = (width / self.width as f64).min(height / self.height as f64);
+
+        ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)?;
+        ctx.clear_rect(0.0, 0.0, width, height);
+        ctx.set_transform(scale, 0.0, 0.0, scale, 0.0, 0.0)?;
+        ctx.set_fill_style_str("white");
+        ctx.fill_rect(0.0, 0.0, self.width as f64, self.height as f64);
+        ctx.set_line_cap("round");
+        ctx.set_text_align("center");
+        ctx.set_text_baseline("middle");
+
+        for shape in &self.shapes {
+            match *shape {
+                Shape::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    fill,
+                } => {
+                    ctx.set_fill_style_str(&fill.to_hex());
+                    ctx.fill_rect(x as f64, y as f64, width as f64, height as f64);
+                }
+                Shape::Line {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    stroke,
+                    width,
+                } => {
+                    ctx.set_stroke_style_str(&stroke.to_hex());
+                    ctx.set_line_width(width as f64);
+                    ctx.begin_path();
+                    ctx.move_to(x1 as f64, y1 as f64);
+                    ctx.line_to(x2 as f64, y2 as f64);
+                    ctx.stroke();
+                }
+                Shape::Circle {
+                    cx,
+                    cy,
+                    r,
+                    fill,
+                    stroke,
+                } => {
+                    ctx.begin_path();
+                    ctx.arc(cx as f64, cy as f64, r as f64, 0.0, PI * 2.0)?;
+                    ctx.set_fill_style_str(&fill.to_hex());
+                    ctx.fill();
+                    if let Some((color, width)) = stroke {
+                        ctx.set_stroke_style_str(&color.to_hex());
+                        ctx.set_line_width(width as f64);
+                        ctx.stroke();
+                    }
+                }
+                Shape::Text {
+                    x,
+                    y,
+                    size,
+                    ref text,
+                    fill,
+                } => {
+                    ctx.set_font(&format!("{}px sans-serif", size));
+                    ctx.set_fill_style_str(&fill.to_hex());
+                    ctx.fill_text(text, x as f64, y as f64)?;
+                }
+            }
+        }
+
+        ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+    }
+}
@@ -0,0 +1,632 @@
+//! Rendering of problems and answers as images.
+//!
+//! Each genre is drawn into a `Picture`, a list of simple shapes which can be written as SVG,
+//! painted on an HTML canvas, or rasterized to PNG with the `png` feature.
+
+mod canvas;
+#[cfg(feature = "png")]
+mod png;
+
+use std::fmt::Write;
+
+use crate::common::{Grid, D, LP, P};
+use crate::doublechoco;
+use crate::grid_loop::Edge;
+use crate::numberlink;
+use crate::slitherlink;
+
+/// The size of a cell in pixels.
+pub const CELL_SIZE: i32 = 32;
+/// The blank space around the board in pixels.
+pub const MARGIN: i32 = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+pub const WHITE: Rgb = Rgb(255, 255, 255);
+pub const BLACK: Rgb = Rgb(0, 0, 0);
+const GRID_COLOR: Rgb = Rgb(153, 153, 153);
+const SHADE_COLOR: Rgb = Rgb(204, 204, 204);
+const WALL_COLOR: Rgb = Rgb(68, 68, 68);
+const ALERT_COLOR: Rgb = Rgb(160, 0, 0);
+
+/// The length of a dash, and of a gap between dashes, of dashed lines.
+const DASH: i32 = CELL_SIZE / 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Rect {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        fill: Rgb,
+    },
+    /// A line segment with round caps.
+    Line {
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        stroke: Rgb,
+        width: i32,
+    },
+    Circle {
+        cx: i32,
+        cy: i32,
+        r: i32,
+        fill: Rgb,
+        stroke: Option<(Rgb, i32)>,
+    },
+    /// A text centered at (`x`, `y`). Rasterization supports digits, `?`, `-` and spaces only,
+    /// and draws other characters as boxes.
+    Text {
+        x: i32,
+        y: i32,
+        size: i32,
+        text: String,
+        fill: Rgb,
+    },
+}
+
+/// An image on a white background, made of shapes drawn in order.
+#[derive(Clone, Debug)]
+pub struct Picture {
+    width: i32,
+    height: i32,
+    shapes: Vec<Shape>,
+}
+
+impl Picture {
+    pub fn new(width: i32, height: i32) -> Picture {
+        Picture {
+            width,
+            height,
+            shapes: vec![],
+        }
+    }
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+    pub fn push(&mut self, shape: Shape) {
+        self.shapes.push(shape);
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut ret = String::new();
+        writeln!(
+            ret,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#,
+            self.width, self.height
+        )
+        .unwrap();
+        writeln!(ret, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+
+        for shape in &self.shapes {
+            match *shape {
+                Shape::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    fill,
+                } => writeln!(
+                    ret,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    x,
+                    y,
+                    width,
+                    height,
+                    fill.to_hex()
+                ),
+                Shape::Line {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    stroke,
+                    width,
+                } => writeln!(
+                    ret,
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}" stroke-linecap="round"/>"#,
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    stroke.to_hex(),
+                    width
+                ),
+                Shape::Circle {
+                    cx,
+                    cy,
+                    r,
+                    fill,
+                    stroke,
+                } => {
+                    let stroke = match stroke {
+                        Some((color, width)) => {
+                            format!(r#" stroke="{}" stroke-width="{}""#, color.to_hex(), width)
+                        }
+                        None => String::new(),
+                    };
+                    writeln!(
+                        ret,
+                        r#"<circle cx="{}" cy="{}" r="{}" fill="{}"{}/>"#,
+                        cx,
+                        cy,
+                        r,
+                        fill.to_hex(),
+                        stroke
+                    )
+                }
+                Shape::Text {
+                    x,
+                    y,
+                    size,
+                    ref text,
+                    fill,
+                } => writeln!(
+                    ret,
+                    r#"<text x="{}" y="{}" font-family="sans-serif" font-size="{}" text-anchor="middle" dominant-baseline="central" fill="{}">{}</text>"#,
+                    x,
+                    y,
+                    size,
+                    fill.to_hex(),
+                    escape_xml(text)
+                ),
+            }
+            .unwrap();
+        }
+
+        ret.push_str("</svg>\n");
+        ret
+    }
+
+    /// Rasterizes the picture and encodes it as PNG.
+    /// Texts may contain digits, `?`, `-` and spaces only; other characters are drawn as boxes.
+    #[cfg(feature = "png")]
+    pub fn to_png(&self) -> Vec<u8> {
+        png::encode(self.width, self.height, &png::rasterize(self))
+    }
+}
+
+impl Rgb {
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+    /// Converts a color in HSL (`hue` in degrees, `saturation` and `lightness` in [0, 1]).
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Rgb {
+        let c = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let h = (hue.rem_euclid(360.0)) / 60.0;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as i32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = lightness - c / 2.0;
+        let to_u8 = |v: f64| ((v + m) * 255.0).round() as u8;
+        Rgb(to_u8(r), to_u8(g), to_u8(b))
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Picks a color for the `id`-th of `n` chains so that the hues are evenly spaced.
+pub fn chain_color(id: i32, n: i32) -> Rgb {
+    if n <= 0 {
+        return BLACK;
+    }
+    Rgb::from_hsl((id * 360 / n) as f64, 0.7, 0.45)
+}
+
+fn new_board(height: i32, width: i32) -> Picture {
+    Picture::new(width * CELL_SIZE + MARGIN * 2, height * CELL_SIZE + MARGIN * 2)
+}
+
+/// The top-left corner of `pos`, which may be outside the board by one.
+fn corner(pos: P) -> (i32, i32) {
+    let P(y, x) = pos;
+    (MARGIN + x * CELL_SIZE, MARGIN + y * CELL_SIZE)
+}
+
+fn center(pos: P) -> (i32, i32) {
+    let (x, y) = corner(pos);
+    (x + CELL_SIZE / 2, y + CELL_SIZE / 2)
+}
+
+fn fill_cell(picture: &mut Picture, pos: P, fill: Rgb) {
+    let (x, y) = corner(pos);
+    picture.push(Shape::Rect {
+        x,
+        y,
+        width: CELL_SIZE,
+        height: CELL_SIZE,
+        fill,
+    });
+}
+
+fn draw_number(picture: &mut Picture, pos: P, n: i32) {
+    let (x, y) = center(pos);
+    picture.push(Shape::Text {
+        x,
+        y,
+        size: CELL_SIZE * 5 / 8,
+        text: n.to_string(),
+        fill: BLACK,
+    });
+}
+
+fn draw_grid(picture: &mut Picture, height: i32, width: i32, stroke: Rgb) {
+    for y in 0..=height {
+        let (x1, y1) = corner(P(y, 0));
+        let (x2, y2) = corner(P(y, width));
+        picture.push(Shape::Line {
+            x1,
+            y1,
+            x2,
+            y2,
+            stroke,
+            width: 1,
+        });
+    }
+    for x in 0..=width {
+        let (x1, y1) = corner(P(0, x));
+        let (x2, y2) = corner(P(height, x));
+        picture.push(Shape::Line {
+            x1,
+            y1,
+            x2,
+            y2,
+            stroke,
+            width: 1,
+        });
+    }
+}
+
+/// Draws a dashed line from `(x1, y1)` to `(x2, y2)`, which is either horizontal or vertical
+/// and whose length is a multiple of `2 * DASH`. The dashes are placed symmetrically, so that
+/// lines of adjacent cells do not meet at their ends.
+fn draw_dashed_line(
+    picture: &mut Picture,
+    (x1, y1): (i32, i32),
+    (x2, y2): (i32, i32),
+    stroke: Rgb,
+    width: i32,
+) {
+    let (dx, dy) = ((x2 - x1).signum(), (y2 - y1).signum());
+    let len = (x2 - x1).abs() + (y2 - y1).abs();
+    for i in 0..(len / (2 * DASH)) {
+        let start = 2 * DASH * i + DASH / 2;
+        let end = start + DASH;
+        picture.push(Shape::Line {
+            x1: x1 + dx * start,
+            y1: y1 + dy * start,
+            x2: x1 + dx * end,
+            y2: y1 + dy * end,
+            stroke,
+            width,
+        });
+    }
+}
+
+/// The ends of the segment on the grid lines which corresponds to `pos` in the lattice of
+/// `(2 * height + 1) * (2 * width + 1)`.
+fn lattice_edge_ends(pos: LP) -> ((i32, i32), (i32, i32)) {
+    let LP(y, x) = pos;
+    let start = corner(P(y / 2, x / 2));
+    let end = if y % 2 == 0 {
+        corner(P(y / 2, x / 2 + 1))
+    } else {
+        corner(P(y / 2 + 1, x / 2))
+    };
+    (start, end)
+}
+
+/// Draws the segment on the grid lines which corresponds to `pos` in the lattice of
+/// `(2 * height + 1) * (2 * width + 1)`.
+fn draw_lattice_edge(picture: &mut Picture, pos: LP, stroke: Rgb, width: i32) {
+    let ((x1, y1), (x2, y2)) = lattice_edge_ends(pos);
+    picture.push(Shape::Line {
+        x1,
+        y1,
+        x2,
+        y2,
+        stroke,
+        width,
+    });
+}
+
+/// Draws a Double Choco problem, and the borders of `answer` if given.
+///
+/// The inner grid is dashed, since borders are only drawn on some of the grid lines.
+/// If `highlight_undecided` is `true`, the borders of `answer` which are not decided yet are
+/// drawn as red dashes, as in a partial answer of the solver.
+pub fn doublechoco(
+    color: &Grid<doublechoco::Color>,
+    clue: &Grid<doublechoco::Clue>,
+    answer: Option<&doublechoco::Field>,
+    highlight_undecided: bool,
+) -> Picture {
+    let height = color.height();
+    let width = color.width();
+    let mut picture = new_board(height, width);
+
+    for y in 0..height {
+        for x in 0..width {
+            if color[P(y, x)] == doublechoco::Color::Black {
+                fill_cell(&mut picture, P(y, x), SHADE_COLOR);
+            }
+        }
+    }
+    for y in 1..height {
+        draw_dashed_line(
+            &mut picture,
+            corner(P(y, 0)),
+            corner(P(y, width)),
+            GRID_COLOR,
+            1,
+        );
+    }
+    for x in 1..width {
+        draw_dashed_line(
+            &mut picture,
+            corner(P(0, x)),
+            corner(P(height, x)),
+            GRID_COLOR,
+            1,
+        );
+    }
+
+    if let Some(field) = answer {
+        for y in 1..(2 * height) {
+            for x in 1..(2 * width) {
+                if y % 2 == x % 2 {
+                    continue;
+                }
+                // `Field::border` is indexed without the outer frame
+                match field.border(LP(y - 1, x - 1)) {
+                    doublechoco::Border::Line => {
+                        draw_lattice_edge(&mut picture, LP(y, x), BLACK, 3)
+                    }
+                    doublechoco::Border::Undecided if highlight_undecided => {
+                        let (start, end) = lattice_edge_ends(LP(y, x));
+                        draw_dashed_line(&mut picture, start, end, ALERT_COLOR, 3);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    draw_frame(&mut picture, height, width);
+
+    for y in 0..height {
+        for x in 0..width {
+            if clue[P(y, x)] != doublechoco::NO_CLUE {
+                draw_number(&mut picture, P(y, x), clue[P(y, x)]);
+            }
+        }
+    }
+
+    picture
+}
+
+fn draw_frame(picture: &mut Picture, height: i32, width: i32) {
+    for y in 0..(2 * height + 1) {
+        for x in 0..(2 * width + 1) {
+            if y % 2 != x % 2 && (y == 0 || y == 2 * height || x == 0 || x == 2 * width) {
+                draw_lattice_edge(picture, LP(y, x), BLACK, 3);
+            }
+        }
+    }
+}
+
+/// Draws a Slitherlink problem, and the lines of `answer` if given.
+pub fn slitherlink(clue: &Grid<slitherlink::Clue>, answer: Option<&slitherlink::Field>) -> Picture {
+    let height = clue.height();
+    let width = clue.width();
+    let mut picture = new_board(height, width);
+
+    for y in 0..height {
+        for x in 0..width {
+            if clue[P(y, x)] == slitherlink::HOLE {
+                fill_cell(&mut picture, P(y, x), WALL_COLOR);
+            }
+        }
+    }
+
+    if let Some(field) = answer {
+        for y in 0..(2 * height + 1) {
+            for x in 0..(2 * width + 1) {
+                if y % 2 != x % 2 && field.get_edge(LP(y, x)) == Edge::Line {
+                    draw_lattice_edge(&mut picture, LP(y, x), BLACK, 4);
+                }
+            }
+        }
+    }
+
+    for y in 0..=height {
+        for x in 0..=width {
+            let (cx, cy) = corner(P(y, x));
+            picture.push(Shape::Circle {
+                cx,
+                cy,
+                r: 2,
+                fill: BLACK,
+                stroke: None,
+            });
+        }
+    }
+
+    for y in 0..height {
+        for x in 0..width {
+            let slitherlink::Clue(n) = clue[P(y, x)];
+            if n >= 0 {
+                draw_number(&mut picture, P(y, x), n);
+            }
+        }
+    }
+
+    picture
+}
+
+/// Draws a Numberlink problem, and the lines of `answer` if given.
+///
+/// Chains are colored by `LinePlacement::extract_chain_groups`, or drawn in black if the lines
+/// do not form valid chains (for example, if some cells are unused).
+pub fn numberlink(
+    clue: &Grid<numberlink::Clue>,
+    answer: Option<&numberlink::LinePlacement>,
+) -> Picture {
+    let height = clue.height();
+    let width = clue.width();
+    let mut picture = new_board(height, width);
+
+    for y in 0..height {
+        for x in 0..width {
+            if clue[P(y, x)] == numberlink::UNUSED {
+                fill_cell(&mut picture, P(y, x), WALL_COLOR);
+            }
+        }
+    }
+    draw_grid(&mut picture, height, width, SHADE_COLOR);
+
+    let groups = answer.and_then(|lines| lines.extract_chain_groups());
+    let n_groups = groups
+        .as_ref()
+        .map(|g| (0..(height * width) as usize).map(|i| g[i] + 1).max().unwrap_or(0))
+        .unwrap_or(0);
+    let color = |pos: P| match groups {
+        Some(ref g) => chain_color(g[pos], n_groups),
+        None => BLACK,
+    };
+
+    if let Some(lines) = answer {
+        for y in 0..height {
+            for x in 0..width {
+                let pos = P(y, x);
+                // A crossing cell has the id of the horizontal chain,
+                // so the vertical chain is looked up from a cell which is not crossing.
+                let mut vertical = pos;
+                while lines.is_crossing(vertical) {
+                    vertical = vertical + D(-1, 0);
+                }
+                for &(connected, pos2, color_pos) in &[
+                    (lines.right(pos), pos + D(0, 1), pos),
+                    (lines.down(pos), pos + D(1, 0), vertical),
+                ] {
+                    if connected {
+                        let (x1, y1) = center(pos);
+                        let (x2, y2) = center(pos2);
+                        picture.push(Shape::Line {
+                            x1,
+                            y1,
+                            x2,
+                            y2,
+                            stroke: color(color_pos),
+                            width: 6,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            let numberlink::Clue(n) = clue[pos];
+            if n <= 0 {
+                continue;
+            }
+            let (cx, cy) = center(pos);
+            picture.push(Shape::Circle {
+                cx,
+                cy,
+                r: CELL_SIZE * 2 / 5,
+                fill: WHITE,
+                stroke: Some((color(pos), 2)),
+            });
+            draw_number(&mut picture, pos, n);
+        }
+    }
+
+    picture
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_hsl() {
+        assert_eq!(Rgb::from_hsl(0.0, 1.0, 0.5), Rgb(255, 0, 0));
+        assert_eq!(Rgb::from_hsl(120.0, 1.0, 0.5), Rgb(0, 255, 0));
+        assert_eq!(Rgb::from_hsl(240.0, 1.0, 0.25), Rgb(0, 0, 128));
+        assert_eq!(Rgb::from_hsl(0.0, 0.0, 1.0), WHITE);
+        assert_eq!(Rgb(255, 16, 0).to_hex(), "#ff1000");
+    }
+
+    #[test]
+    fn test_slitherlink() {
+        let dic = slitherlink::Dictionary::complete();
+        let problem = slitherlink::parse_url("https://puzz.link/p?slither/3/3/dgcg0").unwrap();
+        let mut field = slitherlink::Field::new(&problem, &dic);
+        field.check_all_cell();
+
+        let picture = slitherlink(&problem, Some(&field));
+        assert_eq!(picture.width(), 3 * CELL_SIZE + 2 * MARGIN);
+        let n_lines = picture
+            .shapes()
+            .iter()
+            .filter(|s| matches!(s, Shape::Line { .. }))
+            .count();
+        let n_decided_lines = (0..7)
+            .flat_map(|y| (0..7).map(move |x| LP(y, x)))
+            .filter(|&pos| pos.0 % 2 != pos.1 % 2 && field.get_edge(pos) == Edge::Line)
+            .count();
+        assert!(n_decided_lines > 0);
+        assert_eq!(n_lines, n_decided_lines);
+        let svg = picture.to_svg();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<text").count(), 3);
+        assert_eq!(svg.matches("<circle").count(), 16);
+    }
+
+    #[test]
+    fn test_doublechoco() {
+        let mut color = Grid::new(2, 2, doublechoco::Color::White);
+        color[P(1, 1)] = doublechoco::Color::Black;
+        let mut clue = Grid::new(2, 2, doublechoco::NO_CLUE);
+        clue[P(0, 0)] = 2;
+        let picture = doublechoco(&color, &clue, None, true);
+        let svg = picture.to_svg();
+        // The background and a shaded cell
+        assert_eq!(svg.matches("<rect").count(), 2);
+        // Two dashes for each cell on the inner grid, and the frame
+        assert_eq!(svg.matches("<line").count(), 2 * 2 * 2 + 8);
+        assert_eq!(svg.matches("<text").count(), 1);
+
+        // No border is decided before solving
+        let field = doublechoco::Field::new(&color, &clue);
+        let svg = doublechoco(&color, &clue, Some(&field), true).to_svg();
+        let alert = format!(r#"stroke="{}""#, ALERT_COLOR.to_hex());
+        assert_eq!(svg.matches(&alert).count(), 4 * 2);
+        let svg = doublechoco(&color, &clue, Some(&field), false).to_svg();
+        assert_eq!(svg.matches(&alert).count(), 0);
+    }
+}
@@ -0,0 +1,290 @@
+//! Rasterization of `Picture`s and PNG encoding.
+//!
+//! Shapes are drawn without anti-aliasing, and texts are drawn with a built-in 5x7 font.
+//! The font has digits, `?`, `-` and a space only; any other character is drawn as a box.
+
+use super::{Picture, Rgb, Shape, WHITE};
+
+/// Offset of sampling points from the centers of pixels, so that a line of odd width
+/// lying on a pixel boundary covers pixels on one side only.
+const SAMPLE_OFFSET: f64 = 0.01;
+
+const FONT: [(char, [u8; 7]); 13] = [
+    ('0', [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e]),
+    ('1', [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e]),
+    ('2', [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f]),
+    ('3', [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e]),
+    ('4', [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02]),
+    ('5', [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e]),
+    ('6', [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e]),
+    ('7', [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e]),
+    ('9', [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c]),
+    ('?', [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04]),
+    ('-', [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00]),
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+];
+
+/// The glyph drawn for the characters missing in `FONT`
+const FALLBACK_GLYPH: [u8; 7] = [0x1f, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1f];
+
+fn glyph(c: char) -> &'static [u8; 7] {
+    FONT.iter()
+        .find(|&&(c2, _)| c == c2)
+        .map_or(&FALLBACK_GLYPH, |(_, g)| g)
+}
+
+struct Canvas {
+    width: i32,
+    height: i32,
+    pixels: Vec<Rgb>,
+}
+
+impl Canvas {
+    /// Sets `color` to each pixel in the given range whose sampling point satisfies `pred`.
+    fn paint<F: Fn(f64, f64) -> bool>(
+        &mut self,
+        (x1, y1, x2, y2): (f64, f64, f64, f64),
+        color: Rgb,
+        pred: F,
+    ) {
+        let x_lo = (x1.floor() as i32).max(0);
+        let y_lo = (y1.floor() as i32).max(0);
+        let x_hi = (x2.ceil() as i32).min(self.width);
+        let y_hi = (y2.ceil() as i32).min(self.height);
+        for y in y_lo..y_hi {
+            for x in x_lo..x_hi {
+                let sx = x as f64 + 0.5 + SAMPLE_OFFSET;
+                let sy = y as f64 + 0.5 + SAMPLE_OFFSET;
+                if pred(sx, sy) {
+                    self.pixels[(y * self.width + x) as usize] = color;
+                }
+            }
+        }
+    }
+}
+
+fn distance_to_segment(px: f64, py: f64, (x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> f64 {
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 == 0.0 {
+        0.0
+    } else {
+        (((px - x1) * dx + (py - y1) * dy) / len2).clamp(0.0, 1.0)
+    };
+    let (qx, qy) = (x1 + t * dx, y1 + t * dy);
+    ((px - qx) * (px - qx) + (py - qy) * (py - qy)).sqrt()
+}
+
+/// Returns the pixels of `picture` in row-major order.
+pub fn rasterize(picture: &Picture) -> Vec<Rgb> {
+    let mut canvas = Canvas {
+        width: picture.width(),
+        height: picture.height(),
+        pixels: vec![WHITE; (picture.width() * picture.height()) as usize],
+    };
+
+    for shape in picture.shapes() {
+        match *shape {
+            Shape::Rect {
+                x,
+                y,
+                width,
+                height,
+                fill,
+            } => {
+                let (x, y) = (x as f64, y as f64);
+                let bound = (x, y, x + width as f64, y + height as f64);
+                canvas.paint(bound, fill, |_, _| true);
+            }
+            Shape::Line {
+                x1,
+                y1,
+                x2,
+                y2,
+                stroke,
+                width,
+            } => {
+                let p1 = (x1 as f64, y1 as f64);
+                let p2 = (x2 as f64, y2 as f64);
+                let r = width as f64 / 2.0;
+                let bound = (
+                    p1.0.min(p2.0) - r,
+                    p1.1.min(p2.1) - r,
+                    p1.0.max(p2.0) + r,
+                    p1.1.max(p2.1) + r,
+                );
+                canvas.paint(bound, stroke, |x, y| distance_to_segment(x, y, p1, p2) <= r);
+            }
+            Shape::Circle {
+                cx,
+                cy,
+                r,
+                fill,
+                stroke,
+            } => {
+                let (cx, cy, r) = (cx as f64, cy as f64, r as f64);
+                let dist = |x: f64, y: f64| ((x - cx) * (x - cx) + (y - cy) * (y - cy)).sqrt();
+                let bound = (cx - r - 1.0, cy - r - 1.0, cx + r + 1.0, cy + r + 1.0);
+                canvas.paint(bound, fill, |x, y| dist(x, y) <= r);
+                if let Some((color, width)) = stroke {
+                    let hw = width as f64 / 2.0;
+                    let bound = (cx - r - hw, cy - r - hw, cx + r + hw, cy + r + hw);
+                    canvas.paint(bound, color, |x, y| (dist(x, y) - r).abs() <= hw);
+                }
+            }
+            Shape::Text {
+                x,
+                y,
+                size,
+                ref text,
+                fill,
+            } => {
+                let unit = size as f64 / 8.0;
+                let n_chars = text.chars().count() as f64;
+                let left = x as f64 - (6.0 * n_chars - 1.0) * unit / 2.0;
+                let top = y as f64 - 3.5 * unit;
+                for (i, c) in text.chars().enumerate() {
+                    let g = glyph(c);
+                    let gx = left + (6 * i) as f64 * unit;
+                    let bound = (gx, top, gx + 5.0 * unit, top + 7.0 * unit);
+                    canvas.paint(bound, fill, |sx, sy| {
+                        let u = ((sx - gx) / unit).floor() as i32;
+                        let v = ((sy - top) / unit).floor() as i32;
+                        (0..5).contains(&u)
+                            && (0..7).contains(&v)
+                            && (g[v as usize] >> (4 - u)) & 1 == 1
+                    });
+                }
+            }
+        }
+    }
+
+    canvas.pixels
+}
+
+/// Encodes `pixels` (in row-major order) as an 8-bit RGB PNG image.
+pub fn encode(width: i32, height: i32, pixels: &[Rgb]) -> Vec<u8> {
+    let data = pixels
+        .iter()
+        .flat_map(|&Rgb(r, g, b)| vec![r, g, b])
+        .collect::<Vec<_>>();
+    let mut ret = vec![];
+    let mut encoder = ::png::Encoder::new(&mut ret, width as u32, height as u32);
+    encoder.set_color(::png::ColorType::Rgb);
+    encoder.set_depth(::png::BitDepth::Eight);
+    encoder.set_compression(::png::Compression::Best);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .expect("writing to a Vec never fails");
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::BLACK;
+    use super::*;
+
+    #[test]
+    fn test_rasterize() {
+        let mut picture = Picture::new(8, 4);
+        picture.push(Shape::Line {
+            x1: 0,
+            y1: 2,
+            x2: 8,
+            y2: 2,
+            stroke: BLACK,
+            width: 1,
+        });
+        let pixels = rasterize(&picture);
+        for y in 0..4 {
+            for x in 0..8 {
+                let expected = if y == 1 { BLACK } else { WHITE };
+                assert_eq!(pixels[y * 8 + x], expected, "y={}, x={}", y, x);
+            }
+        }
+    }
+
+    /// Decodes a PNG image produced by `encode` into its size and pixels.
+    fn decode(png: &[u8]) -> (i32, i32, Vec<Rgb>) {
+        let mut reader = ::png::Decoder::new(png).read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        assert_eq!(
+            (info.color_type, info.bit_depth),
+            (::png::ColorType::Rgb, ::png::BitDepth::Eight)
+        );
+        let pixels = data[..info.buffer_size()]
+            .chunks(3)
+            .map(|c| Rgb(c[0], c[1], c[2]))
+            .collect();
+        (info.width as i32, info.height as i32, pixels)
+    }
+
+    #[test]
+    fn test_unsupported_character() {
+        let text_pixels = |text: &str| {
+            let mut picture = Picture::new(16, 16);
+            picture.push(Shape::Text {
+                x: 8,
+                y: 8,
+                size: 8,
+                text: text.to_string(),
+                fill: BLACK,
+            });
+            rasterize(&picture)
+        };
+        let boxed = text_pixels("A");
+        assert_eq!(boxed.iter().filter(|&&c| c == BLACK).count(), 20);
+        assert_eq!(text_pixels("\u{3042}"), boxed);
+        assert!(text_pixels(" ").iter().all(|&c| c == WHITE));
+    }
+
+    #[test]
+    fn test_encode() {
+        let picture = crate::render::numberlink(
+            &crate::common::Grid::new(3, 3, crate::numberlink::Clue(1)),
+            None,
+        );
+        let png = picture.to_png();
+        assert_eq!(&png[0..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..20], &(picture.width() as u32).to_be_bytes());
+        assert_eq!(
+            &png[png.len() - 12..],
+            b"\x00\x00\x00\x00IEND\xae\x42\x60\x82"
+        );
+        // Mostly white images are compressed well
+        assert!(png.len() < (picture.width() * picture.height()) as usize / 4);
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let picture = crate::render::numberlink(
+            &crate::common::Grid::new(3, 3, crate::numberlink::Clue(12)),
+            None,
+        );
+        let pixels = rasterize(&picture);
+        assert_eq!(
+            decode(&encode(picture.width(), picture.height(), &pixels)),
+            (picture.width(), picture.height(), pixels)
+        );
+
+        // Pixels with few repetitions
+        let (width, height) = (150, 80);
+        let mut pixels = vec![];
+        let mut state = 12345u32;
+        for i in 0..(width * height) {
+            if i % 7 < 3 {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            }
+            let v = (state >> 8) as u8;
+            pixels.push(Rgb(v, v.wrapping_add(i as u8), 255 - v));
+        }
+        assert_eq!(
+            decode(&encode(width, height, &pixels)),
+            (width, height, pixels)
+        );
+    }
+}
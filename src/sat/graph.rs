@@ -0,0 +1,300 @@
+//! Lazy constraints on graphs.
+//!
+//! Vertices are numbered from 0, and an edge is given as a pair of its end vertices.
+
+use varisat::Lit;
+
+use super::{LazyConstraint, Model, Sat};
+
+struct UnionFind {
+    parent: Vec<usize>,
+}
+impl UnionFind {
+    fn new(n: usize) -> UnionFind {
+        UnionFind {
+            parent: (0..n).collect(),
+        }
+    }
+    fn root(&mut self, v: usize) -> usize {
+        if self.parent[v] != v {
+            let r = self.root(self.parent[v]);
+            self.parent[v] = r;
+        }
+        self.parent[v]
+    }
+    fn join(&mut self, u: usize, v: usize) -> bool {
+        let u = self.root(u);
+        let v = self.root(v);
+        self.parent[u] = v;
+        u != v
+    }
+}
+
+/// Requires that the vertices whose literals are true are connected through `edges`.
+pub struct Connected {
+    vertices: Vec<Lit>,
+    adj: Vec<Vec<usize>>,
+}
+impl Connected {
+    pub fn new(vertices: &[Lit], edges: &[(usize, usize)]) -> Connected {
+        let mut adj = vec![vec![]; vertices.len()];
+        for &(u, v) in edges {
+            adj[u].push(v);
+            adj[v].push(u);
+        }
+        Connected {
+            vertices: vertices.to_vec(),
+            adj,
+        }
+    }
+}
+impl LazyConstraint for Connected {
+    fn check(&self, model: &Model) -> Vec<Vec<Lit>> {
+        let n = self.vertices.len();
+        let active = self
+            .vertices
+            .iter()
+            .map(|&l| model.value(l))
+            .collect::<Vec<_>>();
+        let mut component = vec![!0; n];
+        let mut components = vec![];
+        for s in 0..n {
+            if !active[s] || component[s] != !0 {
+                continue;
+            }
+            let id = components.len();
+            let mut cells = vec![s];
+            component[s] = id;
+            let mut i = 0;
+            while i < cells.len() {
+                let u = cells[i];
+                i += 1;
+                for &v in &self.adj[u] {
+                    if active[v] && component[v] == !0 {
+                        component[v] = id;
+                        cells.push(v);
+                    }
+                }
+            }
+            components.push(cells);
+        }
+        if components.len() <= 1 {
+            return vec![];
+        }
+
+        // A path from a vertex of the component to a vertex outside of it must pass through
+        // one of the (currently inactive) vertices adjacent to the component.
+        let mut ret = vec![];
+        for (id, cells) in components.iter().enumerate() {
+            let other = components[if id == 0 { 1 } else { 0 }][0];
+            let mut clause = vec![!self.vertices[cells[0]], !self.vertices[other]];
+            let mut added = vec![false; n];
+            for &u in cells {
+                for &v in &self.adj[u] {
+                    if component[v] != id && !added[v] {
+                        added[v] = true;
+                        clause.push(self.vertices[v]);
+                    }
+                }
+            }
+            ret.push(clause);
+        }
+        ret
+    }
+}
+
+/// Requires that the edges whose literals are true form a single loop.
+/// The degree of each vertex is encoded eagerly by `Sat::single_loop`.
+pub struct SingleLoop {
+    n_vertices: usize,
+    edges: Vec<(usize, usize, Lit)>,
+}
+impl LazyConstraint for SingleLoop {
+    fn check(&self, model: &Model) -> Vec<Vec<Lit>> {
+        let mut uf = UnionFind::new(self.n_vertices);
+        let selected = self
+            .edges
+            .iter()
+            .filter(|e| model.value(e.2))
+            .cloned()
+            .collect::<Vec<_>>();
+        for &(u, v, _) in &selected {
+            uf.join(u, v);
+        }
+        let mut roots = vec![];
+        for &(u, _, _) in &selected {
+            let r = uf.root(u);
+            if !roots.contains(&r) {
+                roots.push(r);
+            }
+        }
+        if roots.len() <= 1 {
+            return vec![];
+        }
+
+        // Joining a loop with another edge requires an edge leaving the vertices of the loop
+        let mut ret = vec![];
+        for &r in &roots {
+            let in_loop = (0..self.n_vertices)
+                .map(|v| uf.root(v) == r)
+                .collect::<Vec<_>>();
+            let first = selected.iter().find(|e| in_loop[e.0]).unwrap();
+            let other = selected.iter().find(|e| !in_loop[e.0]).unwrap();
+            let mut clause = vec![!first.2, !other.2];
+            for &(u, v, lit) in &self.edges {
+                if (in_loop[u] || in_loop[v]) && !model.value(lit) {
+                    clause.push(lit);
+                }
+            }
+            ret.push(clause);
+        }
+        ret
+    }
+}
+
+/// Requires that the edges whose literals are true form a forest.
+pub struct Acyclic {
+    n_vertices: usize,
+    edges: Vec<(usize, usize, Lit)>,
+}
+impl LazyConstraint for Acyclic {
+    fn check(&self, model: &Model) -> Vec<Vec<Lit>> {
+        let mut uf = UnionFind::new(self.n_vertices);
+        let mut forest: Vec<Vec<(usize, Lit)>> = vec![vec![]; self.n_vertices];
+        let mut ret = vec![];
+        for &(u, v, lit) in &self.edges {
+            if !model.value(lit) {
+                continue;
+            }
+            if uf.join(u, v) {
+                forest[u].push((v, lit));
+                forest[v].push((u, lit));
+                continue;
+            }
+            // Find the path from `u` to `v` in the forest, which forms a cycle with this edge
+            let mut prev = vec![None; self.n_vertices];
+            let mut visited = vec![false; self.n_vertices];
+            let mut queue = vec![u];
+            visited[u] = true;
+            let mut i = 0;
+            while i < queue.len() && !visited[v] {
+                let w = queue[i];
+                i += 1;
+                for &(x, l) in &forest[w] {
+                    if !visited[x] {
+                        visited[x] = true;
+                        prev[x] = Some((w, l));
+                        queue.push(x);
+                    }
+                }
+            }
+            let mut clause = vec![!lit];
+            let mut w = v;
+            while let Some((p, l)) = prev[w] {
+                clause.push(!l);
+                w = p;
+            }
+            ret.push(clause);
+        }
+        ret
+    }
+}
+
+impl Sat {
+    /// Requires that the vertices whose literals are true are connected through `edges`.
+    pub fn connected(&mut self, vertices: &[Lit], edges: &[(usize, usize)]) {
+        self.add_lazy(Box::new(Connected::new(vertices, edges)));
+    }
+    /// Requires that the edges whose literals are true form a forest on `n_vertices` vertices.
+    pub fn acyclic(&mut self, n_vertices: usize, edges: &[(usize, usize, Lit)]) {
+        self.add_lazy(Box::new(Acyclic {
+            n_vertices,
+            edges: edges.to_vec(),
+        }));
+    }
+    /// Requires that the edges whose literals are true form exactly one loop
+    /// on `n_vertices` vertices.
+    pub fn single_loop(&mut self, n_vertices: usize, edges: &[(usize, usize, Lit)]) {
+        let mut incident = vec![vec![]; n_vertices];
+        for &(u, v, lit) in edges {
+            incident[u].push(lit);
+            incident[v].push(lit);
+        }
+        for lits in &incident {
+            self.at_most_k(lits, 2);
+            self.not_exactly_one(lits);
+        }
+        let all = edges.iter().map(|e| e.2).collect::<Vec<_>>();
+        self.add_clause(&all);
+        self.add_lazy(Box::new(SingleLoop {
+            n_vertices,
+            edges: edges.to_vec(),
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(sat: &mut Sat, keys: &[Lit]) -> usize {
+        let mut ret = 0;
+        while let Some(model) = sat.solve() {
+            ret += 1;
+            sat.block(&model, keys);
+        }
+        ret
+    }
+    /// Returns the edges of a grid graph of `height` x `width` vertices.
+    fn grid_edges(height: usize, width: usize) -> Vec<(usize, usize)> {
+        let mut ret = vec![];
+        for y in 0..height {
+            for x in 0..width {
+                if y + 1 < height {
+                    ret.push((y * width + x, (y + 1) * width + x));
+                }
+                if x + 1 < width {
+                    ret.push((y * width + x, y * width + x + 1));
+                }
+            }
+        }
+        ret
+    }
+
+    #[test]
+    fn test_connected() {
+        // Connected subsets of a path of 4 vertices: 4 + 3 + 2 + 1 nonempty ones and the empty one
+        let mut sat = Sat::new();
+        let vertices = sat.new_lits(4);
+        sat.connected(&vertices, &[(0, 1), (1, 2), (2, 3)]);
+        assert_eq!(count(&mut sat, &vertices), 11);
+    }
+
+    #[test]
+    fn test_acyclic() {
+        // Spanning forests of a 4-cycle: all subsets but the whole cycle
+        let mut sat = Sat::new();
+        let lits = sat.new_lits(4);
+        let edges = (0..4)
+            .map(|i| (i, (i + 1) % 4, lits[i]))
+            .collect::<Vec<_>>();
+        sat.acyclic(4, &edges);
+        assert_eq!(count(&mut sat, &lits), 15);
+    }
+
+    #[test]
+    fn test_single_loop() {
+        // Loops in a grid graph of 3x3 vertices: 4 unit squares, 4 rectangles of 2 squares
+        // and the outer boundary, plus 4 L-shapes of 3 squares
+        let mut sat = Sat::new();
+        let graph = grid_edges(3, 3);
+        let lits = sat.new_lits(graph.len());
+        let edges = graph
+            .iter()
+            .zip(&lits)
+            .map(|(&(u, v), &l)| (u, v, l))
+            .collect::<Vec<_>>();
+        sat.single_loop(9, &edges);
+        assert_eq!(count(&mut sat, &lits), 13);
+    }
+}
@@ -0,0 +1,274 @@
+//! A thin layer over `varisat` with the encodings shared by the SAT backends of the genres.
+//!
+//! Local constraints (cardinality, bit-vectors) are encoded into clauses eagerly. Global
+//! constraints on graphs (connectivity, acyclicity, single loop) are checked lazily: `Sat::solve`
+//! runs the solver, asks every `LazyConstraint` whether the model violates it, adds the clauses
+//! returned and repeats until a model satisfies all of them.
+
+use varisat::solver::Solver;
+use varisat::{ExtendFormula, Lit};
+
+mod graph;
+
+pub use self::graph::*;
+
+/// An assignment to the variables found by `Sat::solve`.
+pub struct Model {
+    values: Vec<bool>,
+}
+impl Model {
+    /// Returns whether `lit` is true in the model.
+    pub fn value(&self, lit: Lit) -> bool {
+        self.values[lit.index()] == lit.is_positive()
+    }
+}
+
+/// A constraint which is too large to be encoded in advance.
+pub trait LazyConstraint {
+    /// Returns clauses which `model` violates and which rule it out,
+    /// or an empty `Vec` if `model` satisfies the constraint.
+    fn check(&self, model: &Model) -> Vec<Vec<Lit>>;
+}
+
+pub struct Sat {
+    solver: Solver<'static>,
+    n_vars: usize,
+    lazy: Vec<Box<dyn LazyConstraint>>,
+}
+
+impl Default for Sat {
+    fn default() -> Sat {
+        Sat::new()
+    }
+}
+
+impl Sat {
+    pub fn new() -> Sat {
+        Sat {
+            solver: Solver::new(),
+            n_vars: 0,
+            lazy: vec![],
+        }
+    }
+
+    /// Returns the positive literal of a new variable.
+    pub fn new_lit(&mut self) -> Lit {
+        self.n_vars += 1;
+        self.solver.new_lit()
+    }
+    pub fn new_lits(&mut self, n: usize) -> Vec<Lit> {
+        (0..n).map(|_| self.new_lit()).collect()
+    }
+    pub fn add_clause(&mut self, clause: &[Lit]) {
+        self.solver.add_clause(clause);
+    }
+    pub fn add_lazy(&mut self, constraint: Box<dyn LazyConstraint>) {
+        self.lazy.push(constraint);
+    }
+    /// Makes the problem unsatisfiable.
+    pub fn add_contradiction(&mut self) {
+        let lit = self.new_lit();
+        self.add_clause(&[lit]);
+        self.add_clause(&[!lit]);
+    }
+
+    /// Requires that at most `k` of `lits` are true, with the sequential counter encoding.
+    pub fn at_most_k(&mut self, lits: &[Lit], k: usize) {
+        let n = lits.len();
+        if k >= n {
+            return;
+        }
+        if k == 0 {
+            for &l in lits {
+                self.add_clause(&[!l]);
+            }
+            return;
+        }
+        if k == 1 && n <= 6 {
+            for i in 0..n {
+                for j in (i + 1)..n {
+                    self.add_clause(&[!lits[i], !lits[j]]);
+                }
+            }
+            return;
+        }
+        // s[i][j]: at least j + 1 of lits[0..=i] are true
+        let s = (0..(n - 1)).map(|_| self.new_lits(k)).collect::<Vec<_>>();
+        self.add_clause(&[!lits[0], s[0][0]]);
+        for &l in &s[0][1..] {
+            self.add_clause(&[!l]);
+        }
+        for i in 1..(n - 1) {
+            self.add_clause(&[!lits[i], s[i][0]]);
+            self.add_clause(&[!s[i - 1][0], s[i][0]]);
+            for j in 1..k {
+                self.add_clause(&[!lits[i], !s[i - 1][j - 1], s[i][j]]);
+                self.add_clause(&[!s[i - 1][j], s[i][j]]);
+            }
+            self.add_clause(&[!lits[i], !s[i - 1][k - 1]]);
+        }
+        self.add_clause(&[!lits[n - 1], !s[n - 2][k - 1]]);
+    }
+    /// Requires that at least `k` of `lits` are true.
+    pub fn at_least_k(&mut self, lits: &[Lit], k: usize) {
+        if k > lits.len() {
+            self.add_contradiction();
+            return;
+        }
+        if k == 1 {
+            self.add_clause(lits);
+            return;
+        }
+        let negated = lits.iter().map(|&l| !l).collect::<Vec<_>>();
+        self.at_most_k(&negated, lits.len() - k);
+    }
+    /// Requires that exactly `k` of `lits` are true.
+    pub fn exactly_k(&mut self, lits: &[Lit], k: usize) {
+        self.at_most_k(lits, k);
+        self.at_least_k(lits, k);
+    }
+    pub fn at_most_one(&mut self, lits: &[Lit]) {
+        self.at_most_k(lits, 1);
+    }
+    pub fn exactly_one(&mut self, lits: &[Lit]) {
+        self.exactly_k(lits, 1);
+    }
+    /// Requires that the number of true literals in `lits` is not exactly one.
+    pub fn not_exactly_one(&mut self, lits: &[Lit]) {
+        for (i, &l) in lits.iter().enumerate() {
+            let mut clause = vec![!l];
+            clause.extend_from_slice(&lits[..i]);
+            clause.extend_from_slice(&lits[(i + 1)..]);
+            self.add_clause(&clause);
+        }
+    }
+
+    /// Returns a new bit-vector of `width` bits, least significant bit first.
+    pub fn new_bits(&mut self, width: usize) -> Vec<Lit> {
+        self.new_lits(width)
+    }
+    /// Requires that `bits` represents `value`.
+    pub fn fix_bits(&mut self, bits: &[Lit], value: u64) {
+        for (i, &b) in bits.iter().enumerate() {
+            self.add_clause(&[if (value >> i) & 1 != 0 { b } else { !b }]);
+        }
+    }
+    /// Requires that `bits` does not represent `value`.
+    pub fn forbid_bits(&mut self, bits: &[Lit], value: u64) {
+        let clause = bits
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| if (value >> i) & 1 != 0 { !b } else { b })
+            .collect::<Vec<_>>();
+        self.add_clause(&clause);
+    }
+    /// Requires that `a` and `b` are equal if `cond` is true. `a` and `b` must have the same width.
+    pub fn equal_bits_if(&mut self, cond: Lit, a: &[Lit], b: &[Lit]) {
+        assert_eq!(a.len(), b.len());
+        for i in 0..a.len() {
+            self.add_clause(&[!cond, !a[i], b[i]]);
+            self.add_clause(&[!cond, a[i], !b[i]]);
+        }
+    }
+    /// Requires that `a` and `b` are equal. `a` and `b` must have the same width.
+    pub fn equal_bits(&mut self, a: &[Lit], b: &[Lit]) {
+        assert_eq!(a.len(), b.len());
+        for i in 0..a.len() {
+            self.add_clause(&[!a[i], b[i]]);
+            self.add_clause(&[a[i], !b[i]]);
+        }
+    }
+
+    /// Finds a model satisfying all the clauses and lazy constraints.
+    /// Returns `None` if there is no such model.
+    pub fn solve(&mut self) -> Option<Model> {
+        loop {
+            if !self.solver.solve().unwrap_or(false) {
+                return None;
+            }
+            let mut values = vec![false; self.n_vars];
+            for lit in self.solver.model()? {
+                if lit.index() < values.len() {
+                    values[lit.index()] = lit.is_positive();
+                }
+            }
+            let model = Model { values };
+
+            let mut clauses = vec![];
+            for constraint in &self.lazy {
+                clauses.extend(constraint.check(&model));
+            }
+            if clauses.is_empty() {
+                return Some(model);
+            }
+            for clause in clauses {
+                self.add_clause(&clause);
+            }
+        }
+    }
+    /// Rules out the models which agree with `model` on all of `keys`,
+    /// so that the next call of `solve` finds a different answer.
+    pub fn block(&mut self, model: &Model, keys: &[Lit]) {
+        let clause = keys
+            .iter()
+            .map(|&l| if model.value(l) { !l } else { l })
+            .collect::<Vec<_>>();
+        self.add_clause(&clause);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts the models projected on `keys`.
+    fn count(sat: &mut Sat, keys: &[Lit]) -> usize {
+        let mut ret = 0;
+        while let Some(model) = sat.solve() {
+            ret += 1;
+            sat.block(&model, keys);
+        }
+        ret
+    }
+
+    #[test]
+    fn test_cardinality() {
+        for n in 0..8 {
+            for k in 0..(n + 2) {
+                let binom = |n: usize, k: usize| -> usize {
+                    if k > n {
+                        return 0;
+                    }
+                    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+                };
+
+                let mut sat = Sat::new();
+                let lits = sat.new_lits(n);
+                sat.exactly_k(&lits, k);
+                assert_eq!(count(&mut sat, &lits), binom(n, k), "{} {}", n, k);
+
+                let mut sat = Sat::new();
+                let lits = sat.new_lits(n);
+                sat.at_most_k(&lits, k);
+                let expected = (0..=k.min(n)).map(|i| binom(n, i)).sum::<usize>();
+                assert_eq!(count(&mut sat, &lits), expected, "{} {}", n, k);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bits() {
+        let mut sat = Sat::new();
+        let a = sat.new_bits(3);
+        let b = sat.new_bits(3);
+        let cond = sat.new_lit();
+        sat.fix_bits(&a, 5);
+        sat.equal_bits_if(cond, &a, &b);
+        sat.forbid_bits(&b, 5);
+        let model = sat.solve().unwrap();
+        assert!(!model.value(cond));
+
+        sat.add_clause(&[cond]);
+        assert!(sat.solve().is_none());
+    }
+}
@@ -0,0 +1,243 @@
+//! Seeds which reproduce generated problems.
+//!
+//! A seed is written as `<genre>-<height>x<width>-<value>` such as `nl-15x15-8a3f09c2d1e4b576`,
+//! where `<value>` is a 64-bit number in hexadecimal. The genres are abbreviated as
+//! `nl` (Numberlink), `sl` (Slitherlink), `dc` (Double Choco) and `ev` (Endview).
+//!
+//! The random numbers are drawn from `SeedRng`, whose output is fixed by the algorithm
+//! described there, through the methods of `StableRng`. Neither depends on the version of
+//! `rand` or on the platform, so that a seed gives the same problem on native and wasm targets
+//! as long as the generator itself is unchanged.
+
+use std::fmt;
+use std::str::FromStr;
+
+use rand::{Error, RngCore};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Seed {
+    pub genre: String,
+    pub height: i32,
+    pub width: i32,
+    pub value: u64,
+}
+
+impl Seed {
+    pub fn new(genre: &str, height: i32, width: i32, value: u64) -> Seed {
+        Seed {
+            genre: String::from(genre),
+            height,
+            width,
+            value,
+        }
+    }
+    /// Returns the random number generator for this seed. The stream depends only on `value`.
+    pub fn rng(&self) -> SeedRng {
+        SeedRng::new(self.value)
+    }
+}
+
+impl fmt::Display for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}x{}-{:016x}",
+            self.genre, self.height, self.width, self.value
+        )
+    }
+}
+
+impl FromStr for Seed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Seed, String> {
+        let invalid = || format!("invalid seed: {}", s);
+        let tokens = s.split('-').collect::<Vec<_>>();
+        if tokens.len() != 3 {
+            return Err(invalid());
+        }
+        let genre = tokens[0];
+        if genre.is_empty() || !genre.chars().all(|c| c.is_ascii_lowercase()) {
+            return Err(invalid());
+        }
+        let size = tokens[1].split('x').collect::<Vec<_>>();
+        if size.len() != 2 {
+            return Err(invalid());
+        }
+        let height = size[0].parse::<i32>().map_err(|_| invalid())?;
+        let width = size[1].parse::<i32>().map_err(|_| invalid())?;
+        if height <= 0 || width <= 0 {
+            return Err(invalid());
+        }
+        if tokens[2].is_empty() || tokens[2].len() > 16 {
+            return Err(invalid());
+        }
+        let value = u64::from_str_radix(tokens[2], 16).map_err(|_| invalid())?;
+        Ok(Seed::new(genre, height, width, value))
+    }
+}
+
+/// xoshiro256** by Blackman and Vigna, whose state is initialized with four outputs of
+/// SplitMix64 starting from the seed value.
+///
+/// `next_u64` is the output of xoshiro256**, `next_u32` is its upper 32 bits, and `fill_bytes`
+/// writes the outputs of `next_u64` in little endian, truncating the last one.
+#[derive(Clone, Debug)]
+pub struct SeedRng {
+    state: [u64; 4],
+}
+
+impl SeedRng {
+    pub fn new(value: u64) -> SeedRng {
+        let mut x = value;
+        let mut splitmix64 = || {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        SeedRng {
+            state: [splitmix64(), splitmix64(), splitmix64(), splitmix64()],
+        }
+    }
+}
+
+impl RngCore for SeedRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let ret = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        ret
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Random values computed from `next_u64` by fixed integer arithmetic. Unlike `gen_range` and
+/// `shuffle` of `rand::Rng`, the results are the same on 32-bit and 64-bit targets.
+pub trait StableRng: RngCore {
+    /// Returns a uniformly random number in `[0, n)` by rejection sampling. Panics if `n == 0`.
+    fn stable_below(&mut self, n: u64) -> u64 {
+        assert!(n > 0);
+        // 2^64 mod n values at the top are rejected so that every remainder is equally likely
+        let rem = (u64::MAX % n + 1) % n;
+        loop {
+            let x = self.next_u64();
+            if rem == 0 || x < rem.wrapping_neg() {
+                return x % n;
+            }
+        }
+    }
+    /// Returns a uniformly random index of a slice of length `len`.
+    fn stable_index(&mut self, len: usize) -> usize {
+        self.stable_below(len as u64) as usize
+    }
+    /// Returns a uniformly random number in `[low, high)`.
+    fn stable_range(&mut self, low: i32, high: i32) -> i32 {
+        assert!(low < high);
+        (low as i64 + self.stable_below((high as i64 - low as i64) as u64) as i64) as i32
+    }
+    /// Returns a uniformly random multiple of 2^-53 in `[0, 1)`.
+    fn stable_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+    /// Shuffles `values` by the Fisher-Yates algorithm from the last element.
+    fn stable_shuffle<T>(&mut self, values: &mut [T]) {
+        for i in (1..values.len()).rev() {
+            let j = self.stable_index(i + 1);
+            values.swap(i, j);
+        }
+    }
+}
+
+impl<R: RngCore + ?Sized> StableRng for R {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_string() {
+        let seed = "nl-15x15-8a3f".parse::<Seed>().unwrap();
+        assert_eq!(seed, Seed::new("nl", 15, 15, 0x8a3f));
+        assert_eq!(seed.to_string(), "nl-15x15-0000000000008a3f");
+        assert_eq!(seed.to_string().parse::<Seed>(), Ok(seed));
+
+        for s in &[
+            "",
+            "nl",
+            "nl-15x15",
+            "-15x15-0",
+            "NL-15x15-0",
+            "nl-15-0",
+            "nl-0x15-0",
+            "nl-15x15-",
+            "nl-15x15-xyz",
+            "nl-15x15-10000000000000000",
+            "nl-15x15-0-0",
+        ] {
+            assert!(s.parse::<Seed>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_seed_rng_stream() {
+        // The stream must never change, or printed seeds no longer reproduce the problems
+        let mut rng = SeedRng::new(0);
+        assert_eq!(rng.next_u64(), 0x99ec5f36cb75f2b4);
+        assert_eq!(rng.next_u64(), 0xbf6e1f784956452a);
+        assert_eq!(rng.next_u32(), 0x1a5f849d);
+
+        let mut rng = Seed::new("nl", 15, 15, 0x8a3f).rng();
+        let mut bytes = [0u8; 10];
+        rng.fill_bytes(&mut bytes);
+        let mut rng2 = SeedRng::new(0x8a3f);
+        let head = rng2.next_u64().to_le_bytes();
+        let tail = rng2.next_u64().to_le_bytes();
+        assert_eq!(&bytes[..8], &head);
+        assert_eq!(&bytes[8..], &tail[..2]);
+    }
+
+    #[test]
+    fn test_stable_rng() {
+        let mut rng = SeedRng::new(42);
+        let mut counts = [0; 3];
+        for _ in 0..3000 {
+            counts[rng.stable_index(3)] += 1;
+        }
+        assert!(counts.iter().all(|&c| 900 < c && c < 1100), "{:?}", counts);
+
+        for _ in 0..100 {
+            let v = rng.stable_range(-2, 3);
+            assert!((-2..3).contains(&v));
+            let f = rng.stable_f64();
+            assert!((0.0..1.0).contains(&f));
+        }
+        assert_eq!(rng.stable_range(i32::MIN, i32::MIN + 1), i32::MIN);
+        assert!(rng.stable_below(u64::MAX) < u64::MAX);
+
+        let mut values = (0..10).collect::<Vec<_>>();
+        rng.stable_shuffle(&mut values);
+        let mut sorted = values.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+    }
+}
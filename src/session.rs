@@ -0,0 +1,750 @@
+//! Interactive play of a problem: the player's partial answer with undo and redo.
+//!
+//! Edges are addressed in the lattice of `(2 * height + 1) * (2 * width + 1)` where cells are on
+//! odd coordinates. For Double Choco an edge is a border between two cells, and for Numberlink
+//! an edge is the connection between the two cells on its sides.
+
+use wasm_bindgen::prelude::*;
+
+use crate::common::{Grid, D, FOUR_NEIGHBOURS, LP, P};
+use crate::grid_loop::Edge;
+use crate::{doublechoco, numberlink, render, slitherlink};
+
+/// The number of Numberlink answers at which `Session::propagate` gives up
+const NUMBERLINK_PROPAGATION_LIMIT: usize = 16;
+
+/// The result of `Session::check`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// Some marks violate the rules.
+    Inconsistent,
+    /// No violation is found, but the answer is not complete.
+    Incomplete,
+    Solved,
+}
+
+enum Problem {
+    DoubleChoco(Grid<doublechoco::Color>, Grid<doublechoco::Clue>),
+    /// Clues and the edges given in the problem
+    Slitherlink(Grid<slitherlink::Clue>, Grid<Edge>),
+    Numberlink(Grid<numberlink::Clue>),
+}
+
+#[derive(Clone, Copy)]
+enum Change {
+    Edge(LP, Edge, Edge),
+    Cell(P, i32, i32),
+}
+
+#[wasm_bindgen]
+pub struct Session {
+    problem: Problem,
+    edges: Grid<Edge>,
+    cells: Grid<i32>,
+    undo_stack: Vec<Vec<Change>>,
+    redo_stack: Vec<Vec<Change>>,
+}
+
+fn edge_to_i32(edge: Edge) -> i32 {
+    match edge {
+        Edge::Undecided => 0,
+        Edge::Line => 1,
+        Edge::Blank => 2,
+    }
+}
+
+fn border_to_edge(border: doublechoco::Border) -> Edge {
+    match border {
+        doublechoco::Border::Undecided => Edge::Undecided,
+        doublechoco::Border::Line => Edge::Line,
+        doublechoco::Border::Blank => Edge::Blank,
+    }
+}
+
+fn edge_to_border(edge: Edge) -> doublechoco::Border {
+    match edge {
+        Edge::Undecided => doublechoco::Border::Undecided,
+        Edge::Line => doublechoco::Border::Line,
+        Edge::Blank => doublechoco::Border::Blank,
+    }
+}
+
+#[wasm_bindgen]
+impl Session {
+    /// Starts a session of the problem at `url`.
+    /// `genre` is one of `dblchoco`, `slither` and `numlin`.
+    /// Returns `None` if the genre is unknown or the URL is invalid.
+    pub fn open(genre: &str, url: &str) -> Option<Session> {
+        let problem = match genre {
+            "dblchoco" => {
+                let (color, clue) = crate::parse_url_dblchoco_internal(url)?;
+                Problem::DoubleChoco(color, clue)
+            }
+            "slither" => {
+                let (clue, givens) = slitherlink::parse_url_with_givens(url)?;
+                Problem::Slitherlink(clue, givens)
+            }
+            "numlin" => Problem::Numberlink(crate::parse_url_numlin_internal(url.to_string())?),
+            _ => return None,
+        };
+        let (height, width) = match problem {
+            Problem::DoubleChoco(ref color, _) => (color.height(), color.width()),
+            Problem::Slitherlink(ref clue, _) => (clue.height(), clue.width()),
+            Problem::Numberlink(ref clue) => (clue.height(), clue.width()),
+        };
+        Some(Session {
+            problem,
+            edges: Grid::new(2 * height + 1, 2 * width + 1, Edge::Undecided),
+            cells: Grid::new(height, width, 0),
+            undo_stack: vec![],
+            redo_stack: vec![],
+        })
+    }
+
+    pub fn height(&self) -> i32 {
+        self.cells.height()
+    }
+    pub fn width(&self) -> i32 {
+        self.cells.width()
+    }
+
+    /// Returns the state of the edge at (`y`, `x`): 0 for undecided, 1 for a line and 2 for
+    /// a blank. Edges given in the problem are included.
+    pub fn edge(&self, y: i32, x: i32) -> i32 {
+        let pos = LP(y, x);
+        if !self.is_edge(pos) {
+            return 0;
+        }
+        match self.problem {
+            Problem::Slitherlink(_, ref givens) if givens[pos] != Edge::Undecided => {
+                edge_to_i32(givens[pos])
+            }
+            _ => edge_to_i32(self.edges[pos]),
+        }
+    }
+
+    /// Sets the state of the edge at (`y`, `x`) in the same encoding as `edge`.
+    /// Returns `false` if the edge cannot be changed.
+    pub fn set_edge(&mut self, y: i32, x: i32, state: i32) -> bool {
+        let pos = LP(y, x);
+        let edge = match state {
+            0 => Edge::Undecided,
+            1 => Edge::Line,
+            2 => Edge::Blank,
+            _ => return false,
+        };
+        if !self.is_editable_edge(pos) {
+            return false;
+        }
+        if self.edges[pos] != edge {
+            self.apply(vec![Change::Edge(pos, self.edges[pos], edge)]);
+        }
+        true
+    }
+
+    /// Returns the mark on the cell at (`y`, `x`).
+    pub fn cell(&self, y: i32, x: i32) -> i32 {
+        if self.cells.is_valid_p(P(y, x)) {
+            self.cells[P(y, x)]
+        } else {
+            0
+        }
+    }
+
+    /// Puts `mark` on the cell at (`y`, `x`), where 0 means no mark.
+    /// Cell marks are memos of the player, such as shading or dots, and are not checked.
+    /// Returns `false` if the position is out of the board.
+    pub fn set_cell(&mut self, y: i32, x: i32, mark: i32) -> bool {
+        let pos = P(y, x);
+        if !self.cells.is_valid_p(pos) {
+            return false;
+        }
+        if self.cells[pos] != mark {
+            self.apply(vec![Change::Cell(pos, self.cells[pos], mark)]);
+        }
+        true
+    }
+
+    /// Reverts the last change. Returns `false` if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let step = match self.undo_stack.pop() {
+            Some(step) => step,
+            None => return false,
+        };
+        for &change in step.iter().rev() {
+            match change {
+                Change::Edge(pos, before, _) => self.edges[pos] = before,
+                Change::Cell(pos, before, _) => self.cells[pos] = before,
+            }
+        }
+        self.redo_stack.push(step);
+        true
+    }
+
+    /// Reapplies the last undone change. Returns `false` if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let step = match self.redo_stack.pop() {
+            Some(step) => step,
+            None => return false,
+        };
+        for &change in &step {
+            match change {
+                Change::Edge(pos, _, after) => self.edges[pos] = after,
+                Change::Cell(pos, _, after) => self.cells[pos] = after,
+            }
+        }
+        self.undo_stack.push(step);
+        true
+    }
+
+    /// Decides the edges which are forced by the current marks, as a single undoable step.
+    /// For Numberlink, the edges shared by all the answers through the marked lines are decided,
+    /// provided that there are fewer than `NUMBERLINK_PROPAGATION_LIMIT` of them.
+    /// Returns `false` if the marks are inconsistent or nothing is decided.
+    pub fn propagate(&mut self) -> bool {
+        self.decide(0, NUMBERLINK_PROPAGATION_LIMIT)
+    }
+
+    /// Continues solving from the current marks and applies the result as a single undoable step.
+    /// `depth` is the depth of trial and error for Double Choco and ignored for the other genres.
+    /// For Numberlink, only lines are taken into account and the answer is applied only if it
+    /// is unique. Returns `false` if the marks are contradictory or nothing is decided.
+    pub fn solve(&mut self, depth: i32) -> bool {
+        self.decide(depth, 2)
+    }
+
+    /// Returns a minimal set of the marks which contradict together, as a flat list of
+    /// `y, x` pairs. The list is empty if no contradiction caused by the marks is found.
+    /// `depth` is the same as in `solve`.
+    pub fn conflicts(&self, depth: i32) -> Vec<i32> {
+        match self.solve_from_marks(depth, 2) {
+            Ok(_) => vec![],
+            Err(marks) => marks.iter().flat_map(|&LP(y, x)| vec![y, x]).collect(),
+        }
+    }
+
+    /// Checks the marks against the rules. Undecided edges are regarded as blanks
+    /// when judging whether the problem is solved.
+    pub fn check(&self) -> Status {
+        match self.problem {
+            Problem::DoubleChoco(..) => {
+                let mut field = self.doublechoco_field();
+                if field.inconsistent() {
+                    return Status::Inconsistent;
+                }
+                let mut completed = field.clone();
+                for pos in self.edge_positions() {
+                    let LP(y, x) = pos;
+                    if self.edges[pos] == Edge::Undecided {
+                        completed.decide_border(LP(y - 1, x - 1), doublechoco::Border::Blank);
+                    }
+                }
+                if completed.trial_and_error(0) {
+                    return Status::Solved;
+                }
+                field.solve();
+                if field.inconsistent() {
+                    Status::Inconsistent
+                } else {
+                    Status::Incomplete
+                }
+            }
+            Problem::Slitherlink(..) => {
+                let mut completed = self.slitherlink_field(true);
+                completed.check_all_cell();
+                if !completed.inconsistent() && completed.fully_solved() {
+                    return Status::Solved;
+                }
+                let mut field = self.slitherlink_field(false);
+                field.check_all_cell();
+                if field.inconsistent() {
+                    Status::Inconsistent
+                } else {
+                    Status::Incomplete
+                }
+            }
+            Problem::Numberlink(ref clue) => check_numberlink(clue, &self.line_placement()),
+        }
+    }
+
+    /// Draws the problem and the marked lines on the canvas whose id is `canvas_id`.
+    /// Returns `false` if the canvas is not found.
+    pub fn draw(&self, canvas_id: &str) -> bool {
+        crate::draw_on_canvas(&self.picture(), canvas_id)
+    }
+}
+
+impl Session {
+    /// Draws the problem together with the current marks.
+    pub fn picture(&self) -> render::Picture {
+        self.picture_with(false)
+    }
+    /// Draws the problem together with the current marks, which are a possibly partial answer
+    /// of a solver. Unlike `picture`, the undecided borders of Double Choco are highlighted.
+    pub fn answer_picture(&self) -> render::Picture {
+        self.picture_with(true)
+    }
+    fn picture_with(&self, highlight_undecided: bool) -> render::Picture {
+        match self.problem {
+            Problem::DoubleChoco(ref color, ref clue) => render::doublechoco(
+                color,
+                clue,
+                Some(&self.doublechoco_field()),
+                highlight_undecided,
+            ),
+            Problem::Slitherlink(ref clue, _) => {
+                render::slitherlink(clue, Some(&self.slitherlink_field(false)))
+            }
+            Problem::Numberlink(ref clue) => render::numberlink(clue, Some(&self.line_placement())),
+        }
+    }
+
+    /// Applies the edges decided by `solve_from_marks` as a single undoable step.
+    fn decide(&mut self, depth: i32, numberlink_limit: usize) -> bool {
+        let decided = match self.solve_from_marks(depth, numberlink_limit) {
+            Ok(decided) => decided,
+            Err(_) => return false,
+        };
+        let changes = self
+            .edge_positions()
+            .into_iter()
+            .filter(|&pos| self.edges[pos] != decided[pos])
+            .map(|pos| Change::Edge(pos, self.edges[pos], decided[pos]))
+            .collect::<Vec<_>>();
+        if changes.is_empty() {
+            return false;
+        }
+        self.apply(changes);
+        true
+    }
+
+    /// Solves the problem from the current marks. Returns the state of each edge after solving,
+    /// or the marks which contradict together.
+    /// For Numberlink, up to `numberlink_limit` answers are enumerated, and the edges are decided
+    /// only if all of them are found and agree on the edge.
+    fn solve_from_marks(&self, depth: i32, numberlink_limit: usize) -> Result<Grid<Edge>, Vec<LP>> {
+        let to_lattice = |marks: Vec<LP>| {
+            marks
+                .into_iter()
+                .map(|LP(y, x)| LP(y + 1, x + 1))
+                .collect::<Vec<_>>()
+        };
+        let mut ret = self.edges.clone();
+        match self.problem {
+            Problem::DoubleChoco(ref color, ref clue) => {
+                let mut marks = Grid::new(
+                    self.edges.height() - 2,
+                    self.edges.width() - 2,
+                    doublechoco::Border::Undecided,
+                );
+                for pos in self.edge_positions() {
+                    let LP(y, x) = pos;
+                    marks[LP(y - 1, x - 1)] = edge_to_border(self.edges[pos]);
+                }
+                let field =
+                    doublechoco::solve_from(color, clue, &marks, depth).map_err(to_lattice)?;
+                for pos in self.edge_positions() {
+                    let LP(y, x) = pos;
+                    ret[pos] = border_to_edge(field.border(LP(y - 1, x - 1)));
+                }
+            }
+            Problem::Slitherlink(ref clue, ref givens) => {
+                let mut marks = givens.clone();
+                for pos in self.edge_positions() {
+                    marks[pos] = self.edges[pos];
+                }
+                let field =
+                    slitherlink::solve_from(clue, &marks, slitherlink::Dictionary::shared())
+                        .map_err(|marks| {
+                            marks
+                                .into_iter()
+                                .filter(|&pos| self.is_editable_edge(pos))
+                                .collect::<Vec<_>>()
+                        })?;
+                for pos in self.edge_positions() {
+                    ret[pos] = field.get_edge(pos);
+                }
+            }
+            Problem::Numberlink(ref clue) => {
+                let opt = numberlink::SolverOption {
+                    limit: Some(numberlink_limit),
+                    ..Default::default()
+                };
+                let ans = numberlink::solve2_from(clue, &self.line_placement(), &opt)
+                    .map_err(to_lattice)?;
+                if !ans.answers.is_empty() && ans.len() < numberlink_limit {
+                    for pos in self.edge_positions() {
+                        let LP(y, x) = pos;
+                        let n_lines = ans
+                            .answers
+                            .iter()
+                            .filter(|lines| lines.get(LP(y - 1, x - 1)))
+                            .count();
+                        if n_lines == ans.len() {
+                            ret[pos] = Edge::Line;
+                        } else if n_lines == 0 {
+                            ret[pos] = Edge::Blank;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    fn apply(&mut self, step: Vec<Change>) {
+        for &change in &step {
+            match change {
+                Change::Edge(pos, _, after) => self.edges[pos] = after,
+                Change::Cell(pos, _, after) => self.cells[pos] = after,
+            }
+        }
+        self.undo_stack.push(step);
+        self.redo_stack.clear();
+    }
+
+    fn is_edge(&self, pos: LP) -> bool {
+        let LP(y, x) = pos;
+        self.edges.is_valid_lp(pos) && y % 2 != x % 2
+    }
+
+    /// Whether the edge at `pos` can be marked by the player.
+    /// Outer edges exist only in Slitherlink, and given edges cannot be changed.
+    fn is_editable_edge(&self, pos: LP) -> bool {
+        if !self.is_edge(pos) {
+            return false;
+        }
+        let LP(y, x) = pos;
+        match self.problem {
+            Problem::Slitherlink(_, ref givens) => givens[pos] == Edge::Undecided,
+            _ => 0 < y && y < self.edges.height() - 1 && 0 < x && x < self.edges.width() - 1,
+        }
+    }
+
+    fn edge_positions(&self) -> Vec<LP> {
+        let mut ret = vec![];
+        for y in 0..self.edges.height() {
+            for x in 0..self.edges.width() {
+                if self.is_editable_edge(LP(y, x)) {
+                    ret.push(LP(y, x));
+                }
+            }
+        }
+        ret
+    }
+
+    fn doublechoco_field(&self) -> doublechoco::Field {
+        let (color, clue) = match self.problem {
+            Problem::DoubleChoco(ref color, ref clue) => (color, clue),
+            _ => unreachable!(),
+        };
+        let mut field = doublechoco::Field::new(color, clue);
+        for pos in self.edge_positions() {
+            if self.edges[pos] != Edge::Undecided {
+                let LP(y, x) = pos;
+                field.decide_border(LP(y - 1, x - 1), edge_to_border(self.edges[pos]));
+            }
+        }
+        field
+    }
+
+    /// Returns a field with the given edges and the marks.
+    /// If `completed` is true, undecided edges are regarded as blanks.
+    fn slitherlink_field(&self, completed: bool) -> slitherlink::Field<'static> {
+        let (clue, givens) = match self.problem {
+            Problem::Slitherlink(ref clue, ref givens) => (clue, givens),
+            _ => unreachable!(),
+        };
+        let mut marks = givens.clone();
+        for pos in self.edge_positions() {
+            marks[pos] = match self.edges[pos] {
+                Edge::Undecided if completed => Edge::Blank,
+                edge => edge,
+            };
+        }
+        slitherlink::Field::with_givens(clue, &marks, slitherlink::Dictionary::shared())
+    }
+
+    fn line_placement(&self) -> numberlink::LinePlacement {
+        let mut lines = numberlink::LinePlacement::new(self.height(), self.width());
+        for pos in self.edge_positions() {
+            if self.edges[pos] == Edge::Line {
+                let LP(y, x) = pos;
+                if y % 2 == 1 {
+                    lines.set_right(P(y / 2, x / 2 - 1), true);
+                } else {
+                    lines.set_down(P(y / 2 - 1, x / 2), true);
+                }
+            }
+        }
+        lines
+    }
+}
+
+fn check_numberlink(clue: &Grid<numberlink::Clue>, lines: &numberlink::LinePlacement) -> Status {
+    let height = clue.height();
+    let width = clue.width();
+    let has_line = |pos: P, d: D| match d {
+        D(0, 1) => lines.right(pos),
+        D(0, -1) => lines.right(pos + d),
+        D(1, 0) => lines.down(pos),
+        _ => lines.down(pos + d),
+    };
+    let degree = |pos: P| {
+        FOUR_NEIGHBOURS
+            .iter()
+            .filter(|&&d| has_line(pos, d))
+            .count()
+    };
+
+    let mut complete = true;
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            let deg = degree(pos);
+            let ok = match clue[pos] {
+                numberlink::UNUSED => deg == 0,
+                numberlink::BRIDGE => match deg {
+                    0 | 1 | 4 => true,
+                    2 => has_line(pos, D(0, 1)) == has_line(pos, D(0, -1)),
+                    _ => false,
+                },
+                numberlink::NO_CLUE => deg <= 2,
+                _ => deg <= 1,
+            };
+            if !ok {
+                return Status::Inconsistent;
+            }
+            complete &= match clue[pos] {
+                numberlink::UNUSED => true,
+                numberlink::BRIDGE | numberlink::NO_CLUE => deg != 1 && deg != 3,
+                _ => deg == 1,
+            };
+        }
+    }
+
+    // Follow each chain from its end, going straight through crossings.
+    // Every segment of lines must be visited from a clue unless the answer is incomplete.
+    let mut visited = Grid::new(2 * height + 1, 2 * width + 1, false);
+    let mut n_visited = 0;
+    for y in 0..height {
+        for x in 0..width {
+            let start = P(y, x);
+            if degree(start) != 1 {
+                continue;
+            }
+            let mut pos = start;
+            let mut dir = *FOUR_NEIGHBOURS
+                .iter()
+                .find(|&&d| has_line(start, d))
+                .unwrap();
+            loop {
+                let seg = LP::of_vertex(pos) + dir;
+                if !visited[seg] {
+                    visited[seg] = true;
+                    n_visited += 1;
+                }
+                pos = pos + dir;
+                if degree(pos) == 4 {
+                    continue;
+                }
+                match FOUR_NEIGHBOURS
+                    .iter()
+                    .find(|&&d| d != D(-dir.0, -dir.1) && has_line(pos, d))
+                {
+                    Some(&d) => dir = d,
+                    None => break,
+                }
+            }
+            let (c1, c2) = (clue[start], clue[pos]);
+            if c1.0 > 0 && c2.0 > 0 && c1 != c2 {
+                return Status::Inconsistent;
+            }
+            if c1.0 <= 0 || c2.0 <= 0 {
+                complete = false;
+            }
+        }
+    }
+
+    let mut n_segments = 0;
+    for y in 0..height {
+        for x in 0..width {
+            n_segments += lines.right(P(y, x)) as i32 + lines.down(P(y, x)) as i32;
+        }
+    }
+    // Remaining segments form closed loops
+    if n_visited != n_segments {
+        if complete {
+            return Status::Inconsistent;
+        }
+        complete = false;
+    }
+
+    if complete {
+        Status::Solved
+    } else {
+        Status::Incomplete
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut session = Session::open("numlin", "https://puzz.link/p?numlin/3/2/1g1h").unwrap();
+        assert_eq!((session.height(), session.width()), (2, 3));
+        assert!(!session.undo());
+
+        assert!(session.set_edge(1, 2, 1));
+        assert!(session.set_edge(1, 4, 1));
+        assert!(session.set_cell(1, 1, 3));
+        assert!(!session.set_edge(0, 1, 1));
+        assert!(!session.set_edge(1, 1, 1));
+        assert_eq!(session.check(), Status::Solved);
+
+        assert!(session.undo());
+        assert_eq!(session.cell(1, 1), 0);
+        assert!(session.undo());
+        assert_eq!(session.edge(1, 4), 0);
+        assert_eq!(session.check(), Status::Incomplete);
+        assert!(session.redo());
+        assert_eq!(session.edge(1, 4), 1);
+
+        assert!(session.set_edge(2, 1, 1));
+        assert!(!session.redo());
+        assert_eq!(session.check(), Status::Inconsistent);
+    }
+
+    #[test]
+    fn test_numberlink_check() {
+        // 1 . 2
+        // . . .
+        // 2 . 1
+        let mut session =
+            Session::open("numlin", "https://puzz.link/p?numlin/3/3/1g2i2g1").unwrap();
+        session.set_edge(1, 2, 1);
+        assert_eq!(session.check(), Status::Incomplete);
+        session.set_edge(1, 4, 1);
+        assert_eq!(session.check(), Status::Inconsistent);
+
+        // A closed loop in an otherwise empty board
+        let mut session = Session::open("numlin", "https://puzz.link/p?numlin/2/2/g").unwrap();
+        for &(y, x) in &[(1, 2), (3, 2), (2, 1), (2, 3)] {
+            session.set_edge(y, x, 1);
+        }
+        assert_eq!(session.check(), Status::Inconsistent);
+    }
+
+    #[test]
+    fn test_numberlink_propagate() {
+        // . . . . .
+        // 1 2 . 2 1
+        // . . . . .
+        let mut session =
+            Session::open("numlin", "https://puzz.link/p?numlin/5/3/k12g21k").unwrap();
+        assert!(session.propagate());
+        assert_eq!(session.edge(3, 4), 1);
+        assert_eq!(session.edge(3, 6), 1);
+        assert_eq!(session.edge(3, 2), 2);
+        // The 1s can be connected either above or below the 2s
+        assert_eq!(session.edge(2, 1), 0);
+        assert_eq!(session.edge(4, 1), 0);
+        assert_eq!(session.check(), Status::Incomplete);
+        assert!(!session.propagate());
+
+        assert!(session.set_edge(2, 1, 1));
+        assert!(session.propagate());
+        assert_eq!(session.edge(4, 1), 2);
+        assert_eq!(session.check(), Status::Solved);
+        assert!(session.undo());
+        assert_eq!(session.edge(4, 1), 0);
+    }
+
+    #[test]
+    fn test_slitherlink() {
+        let mut session = Session::open("slither", "https://puzz.link/p?slither/1/1/0").unwrap();
+        assert!(session.set_edge(0, 1, 1));
+        assert_eq!(session.check(), Status::Inconsistent);
+        assert!(!session.propagate());
+
+        // Two separate loops
+        let mut session = Session::open("slither", "https://puzz.link/p?slither/3/1/g").unwrap();
+        for &(y, x) in &[
+            (0, 1),
+            (2, 1),
+            (1, 0),
+            (1, 2),
+            (0, 5),
+            (2, 5),
+            (1, 4),
+            (1, 6),
+        ] {
+            assert!(session.set_edge(y, x, 1));
+        }
+        assert_ne!(session.check(), Status::Solved);
+
+        let mut session = Session::open("slither", "https://puzz.link/p?slither/3/1/3g3").unwrap();
+        assert!(session.propagate());
+        assert_eq!(session.check(), Status::Solved);
+        assert!(!session.propagate());
+        assert!(session.undo());
+        assert_eq!(session.check(), Status::Incomplete);
+    }
+
+    #[test]
+    fn test_doublechoco() {
+        let url = "https://puzz.link/p?dbchoco/6/6/poc4f1tgj6zl3h3g";
+        let mut session = Session::open("dblchoco", url).unwrap();
+        assert_eq!(session.check(), Status::Incomplete);
+
+        let mut field = doublechoco::Field::new(
+            &crate::parse_url_dblchoco_internal(url).unwrap().0,
+            &crate::parse_url_dblchoco_internal(url).unwrap().1,
+        );
+        assert!(field.trial_and_error(2));
+        for pos in session.edge_positions() {
+            let LP(y, x) = pos;
+            if field.border(LP(y - 1, x - 1)) == doublechoco::Border::Line {
+                session.set_edge(y, x, 1);
+            }
+        }
+        assert_eq!(session.check(), Status::Solved);
+
+        let pos = session.edge_positions()[0];
+        let flipped = if session.edge(pos.0, pos.1) == 1 {
+            2
+        } else {
+            1
+        };
+        session.set_edge(pos.0, pos.1, flipped);
+        assert_ne!(session.check(), Status::Solved);
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut session = Session::open("numlin", "https://puzz.link/p?numlin/3/2/1g21g2").unwrap();
+        assert!(session.conflicts(0).is_empty());
+        assert!(session.solve(0));
+        assert_eq!(session.check(), Status::Solved);
+        assert!(session.undo());
+
+        // Each of the lines alone is a part of a detour
+        assert!(session.set_edge(1, 2, 1));
+        assert!(session.conflicts(0).is_empty());
+        assert!(session.set_edge(1, 4, 1));
+        assert_eq!(session.conflicts(0), vec![1, 2, 1, 4]);
+        assert!(!session.solve(0));
+
+        let mut session = Session::open("slither", "https://puzz.link/p?slither/3/1/3g3").unwrap();
+        assert!(session.set_edge(0, 1, 1));
+        assert!(session.set_edge(0, 3, 2));
+        assert_eq!(session.conflicts(0), vec![0, 3]);
+        assert!(!session.propagate());
+        assert!(session.undo());
+        assert!(session.propagate());
+        assert_eq!(session.check(), Status::Solved);
+    }
+}
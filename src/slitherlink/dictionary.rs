@@ -1,6 +1,9 @@
 use super::super::D;
+use super::dictionary_table;
 use super::Clue;
 use crate::grid_loop::Edge;
+use std::borrow::Cow;
+use std::sync::OnceLock;
 
 pub const DICTIONARY_NEIGHBOR_SIZE: usize = 12;
 pub const DICTIONARY_EDGE_OFFSET: [D; DICTIONARY_NEIGHBOR_SIZE] = [
@@ -18,78 +21,50 @@ pub const DICTIONARY_EDGE_OFFSET: [D; DICTIONARY_NEIGHBOR_SIZE] = [
     D(2, 1),
 ];
 
-const DICTIONARY_NEIGHBOR_PATTERN_COUNT: usize = 531441; // 3^12
-const DICTIONARY_SIZE: usize = DICTIONARY_NEIGHBOR_PATTERN_COUNT * 4;
-pub const DICTIONARY_INCONSISTENT: u32 = 0xffffffff;
+const DICTIONARY_NEIGHBOR_PATTERN_COUNT: usize = dictionary_table::PATTERN_COUNT;
+const DICTIONARY_SIZE: usize = dictionary_table::TABLE_SIZE;
+pub const DICTIONARY_INCONSISTENT: u32 = dictionary_table::INCONSISTENT;
+
+#[repr(C, align(4))]
+struct Aligned<T: ?Sized>(T);
+
+/// The table precomputed by `build.rs` as little-endian `u32`s, aligned so that it can be
+/// used in place.
+static EMBEDDED_DICTIONARY: &Aligned<[u8]> = &Aligned(*include_bytes!(concat!(
+    env!("OUT_DIR"),
+    "/slitherlink_dictionary.bin"
+)));
 
 pub struct Dictionary {
-    dic: Vec<u32>,
+    dic: Cow<'static, [u32]>,
 }
 impl Dictionary {
     pub fn complete() -> Dictionary {
-        let mut dic = vec![0u32; DICTIONARY_SIZE];
-        for clue in 0..4 {
-            let ofs = clue * DICTIONARY_NEIGHBOR_PATTERN_COUNT;
-            for pat_id in 0..DICTIONARY_NEIGHBOR_PATTERN_COUNT {
-                let pat_id = DICTIONARY_NEIGHBOR_PATTERN_COUNT - 1 - pat_id;
-                let mut pat = Dictionary::id_to_pattern(pat_id);
-                let mut undecided_pos = None;
-                for i in 0..DICTIONARY_NEIGHBOR_SIZE {
-                    if pat[i] == Edge::Undecided {
-                        undecided_pos = Some(i);
-                        break;
-                    }
-                }
-                match undecided_pos {
-                    Some(p) => {
-                        pat[p] = Edge::Line;
-                        let base1 = Dictionary::pattern_to_id(pat);
-                        pat[p] = Edge::Blank;
-                        let base2 = Dictionary::pattern_to_id(pat);
-
-                        dic[ofs + pat_id] = dic[ofs + base1] & dic[ofs + base2];
-                    }
-                    None => {
-                        if Dictionary::is_valid_vertex(pat, 0, 2, 3, 5)
-                            && Dictionary::is_valid_vertex(pat, 1, 3, 4, 6)
-                            && Dictionary::is_valid_vertex(pat, 5, 7, 8, 10)
-                            && Dictionary::is_valid_vertex(pat, 6, 8, 9, 11)
-                            && Dictionary::count_lines(pat, 3, 5, 6, 8) == clue
-                        {
-                            let mut pat_id_bin = 0u32;
-                            for i in 0..DICTIONARY_NEIGHBOR_SIZE {
-                                pat_id_bin |= match pat[i] {
-                                    Edge::Line => 1,
-                                    Edge::Blank => 2,
-                                    Edge::Undecided => unreachable!(),
-                                } << (2 * i);
-                            }
-                            dic[ofs + pat_id] = pat_id_bin;
-                        } else {
-                            dic[ofs + pat_id] = DICTIONARY_INCONSISTENT;
-                        }
-                    }
-                }
-            }
-            for pat_id in 0..DICTIONARY_NEIGHBOR_PATTERN_COUNT {
-                let pat_id = DICTIONARY_NEIGHBOR_PATTERN_COUNT - 1 - pat_id;
-                let mut pat = Dictionary::id_to_pattern(pat_id);
-
-                let mut pat_id_bin = 0u32;
-                for i in 0..DICTIONARY_NEIGHBOR_SIZE {
-                    pat_id_bin |= match pat[i] {
-                        Edge::Undecided => 0,
-                        Edge::Line => 1,
-                        Edge::Blank => 2,
-                    } << (2 * i);
-                }
-
-                if dic[ofs + pat_id] != DICTIONARY_INCONSISTENT {
-                    dic[ofs + pat_id] &= !pat_id_bin;
-                }
-            }
+        Dictionary {
+            dic: Cow::Owned(dictionary_table::complete_table()),
         }
-        Dictionary { dic }
+    }
+    /// Returns the dictionary precomputed by `build.rs`, which is identical to `complete()`,
+    /// without copying it. Returns `None` if the embedded table has a wrong size or
+    /// the target is big-endian.
+    pub fn load() -> Option<Dictionary> {
+        let bytes = &EMBEDDED_DICTIONARY.0;
+        if cfg!(target_endian = "big") || bytes.len() != DICTIONARY_SIZE * 4 {
+            return None;
+        }
+        // SAFETY: `bytes` is aligned to 4 bytes by `Aligned` and has `DICTIONARY_SIZE * 4` bytes,
+        // and any bit pattern is a valid `u32`.
+        let dic =
+            unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const u32, DICTIONARY_SIZE) };
+        Some(Dictionary {
+            dic: Cow::Borrowed(dic),
+        })
+    }
+    /// Returns the complete dictionary.
+    /// It is built on the first call and shared by all subsequent callers.
+    pub fn shared() -> &'static Dictionary {
+        static DICTIONARY: OnceLock<Dictionary> = OnceLock::new();
+        DICTIONARY.get_or_init(|| Dictionary::load().unwrap_or_else(Dictionary::complete))
     }
     pub fn consult_raw(&self, Clue(c): Clue, neighbor_code: u32) -> u32 {
         self.dic[c as usize * DICTIONARY_NEIGHBOR_PATTERN_COUNT + neighbor_code as usize]
@@ -111,44 +86,6 @@ impl Dictionary {
             false
         }
     }
-    fn count_lines(
-        pat: [Edge; DICTIONARY_NEIGHBOR_SIZE],
-        p1: usize,
-        p2: usize,
-        p3: usize,
-        p4: usize,
-    ) -> usize {
-        (if pat[p1] == Edge::Line { 1 } else { 0 })
-            + (if pat[p2] == Edge::Line { 1 } else { 0 })
-            + (if pat[p3] == Edge::Line { 1 } else { 0 })
-            + (if pat[p4] == Edge::Line { 1 } else { 0 })
-    }
-    fn is_valid_vertex(
-        pat: [Edge; DICTIONARY_NEIGHBOR_SIZE],
-        p1: usize,
-        p2: usize,
-        p3: usize,
-        p4: usize,
-    ) -> bool {
-        let cnt = Dictionary::count_lines(pat, p1, p2, p3, p4);
-        cnt == 0 || cnt == 2
-    }
-    fn id_to_pattern(pat_id: usize) -> [Edge; DICTIONARY_NEIGHBOR_SIZE] {
-        let mut pat = [Edge::Undecided; DICTIONARY_NEIGHBOR_SIZE];
-        {
-            let mut tmp = pat_id;
-            for i in 0..DICTIONARY_NEIGHBOR_SIZE {
-                pat[i] = match tmp % 3 {
-                    0 => Edge::Undecided,
-                    1 => Edge::Line,
-                    2 => Edge::Blank,
-                    _ => unreachable!(),
-                };
-                tmp /= 3;
-            }
-        }
-        pat
-    }
     fn pattern_to_id(pat: [Edge; DICTIONARY_NEIGHBOR_SIZE]) -> usize {
         let mut ret = 0;
         let mut coef = 1;
@@ -163,4 +100,97 @@ impl Dictionary {
         }
         ret
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The enumeration which `Dictionary::complete` used before the table was moved into
+    /// `dictionary_table`, kept as a reference independent of it.
+    fn reference_table() -> Vec<u32> {
+        fn count_lines(pat: [Edge; DICTIONARY_NEIGHBOR_SIZE], ps: [usize; 4]) -> usize {
+            ps.iter().filter(|&&p| pat[p] == Edge::Line).count()
+        }
+        fn is_valid_vertex(pat: [Edge; DICTIONARY_NEIGHBOR_SIZE], ps: [usize; 4]) -> bool {
+            let cnt = count_lines(pat, ps);
+            cnt == 0 || cnt == 2
+        }
+        fn id_to_pattern(pat_id: usize) -> [Edge; DICTIONARY_NEIGHBOR_SIZE] {
+            let mut pat = [Edge::Undecided; DICTIONARY_NEIGHBOR_SIZE];
+            let mut tmp = pat_id;
+            for p in pat.iter_mut() {
+                *p = match tmp % 3 {
+                    0 => Edge::Undecided,
+                    1 => Edge::Line,
+                    _ => Edge::Blank,
+                };
+                tmp /= 3;
+            }
+            pat
+        }
+        fn code(pat: [Edge; DICTIONARY_NEIGHBOR_SIZE]) -> u32 {
+            let mut ret = 0u32;
+            for (i, &e) in pat.iter().enumerate() {
+                ret |= match e {
+                    Edge::Undecided => 0,
+                    Edge::Line => 1,
+                    Edge::Blank => 2,
+                } << (2 * i);
+            }
+            ret
+        }
+
+        let mut dic = vec![0u32; DICTIONARY_SIZE];
+        for clue in 0..4 {
+            let ofs = clue * DICTIONARY_NEIGHBOR_PATTERN_COUNT;
+            for pat_id in (0..DICTIONARY_NEIGHBOR_PATTERN_COUNT).rev() {
+                let mut pat = id_to_pattern(pat_id);
+                match pat.iter().position(|&e| e == Edge::Undecided) {
+                    Some(p) => {
+                        pat[p] = Edge::Line;
+                        let base1 = Dictionary::pattern_to_id(pat);
+                        pat[p] = Edge::Blank;
+                        let base2 = Dictionary::pattern_to_id(pat);
+
+                        dic[ofs + pat_id] = dic[ofs + base1] & dic[ofs + base2];
+                    }
+                    None => {
+                        dic[ofs + pat_id] = if is_valid_vertex(pat, [0, 2, 3, 5])
+                            && is_valid_vertex(pat, [1, 3, 4, 6])
+                            && is_valid_vertex(pat, [5, 7, 8, 10])
+                            && is_valid_vertex(pat, [6, 8, 9, 11])
+                            && count_lines(pat, [3, 5, 6, 8]) == clue
+                        {
+                            code(pat)
+                        } else {
+                            DICTIONARY_INCONSISTENT
+                        };
+                    }
+                }
+            }
+            for pat_id in 0..DICTIONARY_NEIGHBOR_PATTERN_COUNT {
+                if dic[ofs + pat_id] != DICTIONARY_INCONSISTENT {
+                    dic[ofs + pat_id] &= !code(id_to_pattern(pat_id));
+                }
+            }
+        }
+        dic
+    }
+
+    #[test]
+    fn test_load() {
+        let reference = reference_table();
+        // Exhaustive, so avoid `assert_eq!` which would print millions of entries on failure
+        let loaded = Dictionary::load().unwrap();
+        assert!(*loaded.dic == *reference);
+        let complete = Dictionary::complete();
+        assert!(*complete.dic == *reference);
+
+        // A 0 makes every edge around it blank
+        let mut neighbor = [Edge::Undecided; DICTIONARY_NEIGHBOR_SIZE];
+        assert!(!loaded.consult(Clue(0), &mut neighbor));
+        for &i in &[3, 5, 6, 8] {
+            assert_eq!(neighbor[i], Edge::Blank);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Construction of the raw Slitherlink dictionary table.
+//!
+//! This file does not depend on the rest of the crate because it is also compiled into
+//! `build.rs`, which embeds the table into the library as little-endian `u32`s.
+//!
+//! An entry of the table corresponds to a clue and a pattern of the 12 edges around the clue,
+//! each of which is undecided (0), a line (1) or blank (2).
+//! Patterns are numbered in base 3, edge `i` being the `i`-th least significant digit.
+//! The value of an entry is `INCONSISTENT`, or the edges which can be newly decided
+//! (2 bits per edge, edge `i` at bits `2i` and `2i+1`).
+
+pub const NEIGHBOR_SIZE: usize = 12;
+pub const PATTERN_COUNT: usize = 531441; // 3^12
+pub const TABLE_SIZE: usize = PATTERN_COUNT * 4;
+pub const INCONSISTENT: u32 = 0xffffffff;
+
+pub fn id_to_pattern(pat_id: usize) -> [u32; NEIGHBOR_SIZE] {
+    let mut pat = [0; NEIGHBOR_SIZE];
+    let mut tmp = pat_id;
+    for p in pat.iter_mut() {
+        *p = (tmp % 3) as u32;
+        tmp /= 3;
+    }
+    pat
+}
+
+pub fn pattern_to_id(pat: [u32; NEIGHBOR_SIZE]) -> usize {
+    let mut ret = 0;
+    for &p in pat.iter().rev() {
+        ret = ret * 3 + p as usize;
+    }
+    ret
+}
+
+fn count_lines(pat: [u32; NEIGHBOR_SIZE], p1: usize, p2: usize, p3: usize, p4: usize) -> usize {
+    [p1, p2, p3, p4].iter().filter(|&&p| pat[p] == 1).count()
+}
+
+fn is_valid_vertex(pat: [u32; NEIGHBOR_SIZE], p1: usize, p2: usize, p3: usize, p4: usize) -> bool {
+    let cnt = count_lines(pat, p1, p2, p3, p4);
+    cnt == 0 || cnt == 2
+}
+
+fn pattern_code(pat: [u32; NEIGHBOR_SIZE]) -> u32 {
+    let mut ret = 0;
+    for (i, &p) in pat.iter().enumerate() {
+        ret |= p << (2 * i);
+    }
+    ret
+}
+
+/// Enumerates all patterns and builds the table.
+pub fn complete_table() -> Vec<u32> {
+    let mut dic = vec![0u32; TABLE_SIZE];
+    for clue in 0..4 {
+        let ofs = clue * PATTERN_COUNT;
+        // Every pattern with an undecided edge refers to patterns with larger ids
+        for pat_id in (0..PATTERN_COUNT).rev() {
+            let mut pat = id_to_pattern(pat_id);
+            match pat.iter().position(|&p| p == 0) {
+                Some(p) => {
+                    pat[p] = 1;
+                    let base1 = pattern_to_id(pat);
+                    pat[p] = 2;
+                    let base2 = pattern_to_id(pat);
+
+                    dic[ofs + pat_id] = dic[ofs + base1] & dic[ofs + base2];
+                }
+                None => {
+                    dic[ofs + pat_id] = if is_valid_vertex(pat, 0, 2, 3, 5)
+                        && is_valid_vertex(pat, 1, 3, 4, 6)
+                        && is_valid_vertex(pat, 5, 7, 8, 10)
+                        && is_valid_vertex(pat, 6, 8, 9, 11)
+                        && count_lines(pat, 3, 5, 6, 8) == clue
+                    {
+                        pattern_code(pat)
+                    } else {
+                        INCONSISTENT
+                    };
+                }
+            }
+        }
+        for pat_id in 0..PATTERN_COUNT {
+            if dic[ofs + pat_id] != INCONSISTENT {
+                dic[ofs + pat_id] &= !pattern_code(id_to_pattern(pat_id));
+            }
+        }
+    }
+    dic
+}
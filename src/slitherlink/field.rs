@@ -1,4 +1,4 @@
-use super::super::{Grid, D, LP, P};
+use super::super::{minimize_conflict, Contradiction, Grid, D, LP, P};
 use super::*;
 use crate::grid_loop::{Edge, GridLoop, GridLoopField};
 use crate::common::FOUR_NEIGHBOURS;
@@ -13,10 +13,48 @@ impl<'a> Field<'a> {
     pub fn new(clue: &Grid<Clue>, dic: &'a Dictionary) -> Field<'a> {
         let grid_loop = GridLoop::new(clue.height(), clue.width());
 
-        Field {
+        let mut field = Field {
             grid_loop: grid_loop,
             clue: clue.clone(),
             dic: dic,
+        };
+        field.exclude_holes();
+        field
+    }
+    /// Creates a field with pre-decided edges.
+    /// `givens` is indexed by `LP`, and `Edge::Undecided` means that the edge is not given.
+    pub fn with_givens(clue: &Grid<Clue>, givens: &Grid<Edge>, dic: &'a Dictionary) -> Field<'a> {
+        let mut field = Field::new(clue, dic);
+        for y in 0..givens.height() {
+            for x in 0..givens.width() {
+                let pos = LP(y, x);
+                if givens[pos] != Edge::Undecided {
+                    GridLoop::decide_edge(&mut field, pos, givens[pos]);
+                }
+            }
+        }
+        field
+    }
+    fn exclude_holes(&mut self) {
+        let height = self.height();
+        let width = self.width();
+        let is_hole = |p: P| !self.clue.is_valid_p(p) || self.clue[p] == HOLE;
+
+        let mut excluded = vec![];
+        for y in 0..(2 * height + 1) {
+            for x in 0..(2 * width + 1) {
+                let (c1, c2) = match (y % 2, x % 2) {
+                    (0, 1) => (P(y / 2 - 1, x / 2), P(y / 2, x / 2)),
+                    (1, 0) => (P(y / 2, x / 2 - 1), P(y / 2, x / 2)),
+                    _ => continue,
+                };
+                if is_hole(c1) && is_hole(c2) {
+                    excluded.push(LP(y, x));
+                }
+            }
+        }
+        for pos in excluded {
+            GridLoop::decide_edge(self, pos, Edge::Blank);
         }
     }
     pub fn height(&self) -> i32 {
@@ -31,9 +69,49 @@ impl<'a> Field<'a> {
     pub fn set_inconsistent(&mut self) {
         self.grid_loop.set_inconsistent()
     }
+    /// Returns the first contradiction found, as recorded by `GridLoop::contradiction`.
+    pub fn contradiction(&self) -> Option<&Contradiction<(LP, Edge)>> {
+        self.grid_loop.contradiction()
+    }
+    /// Returns a minimal set of clues which are contradictory by themselves under `check_all_cell`,
+    /// or `None` if the clues do not contradict without the edges decided so far.
+    pub fn explain_inconsistency(&self) -> Option<Vec<P>> {
+        let mut clues = vec![];
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let c = self.clue[P(y, x)];
+                if c != NO_CLUE && c != HOLE {
+                    clues.push(P(y, x));
+                }
+            }
+        }
+        let is_conflicting = |clues: &[P]| {
+            let mut clue = Grid::new(self.height(), self.width(), NO_CLUE);
+            for y in 0..self.height() {
+                for x in 0..self.width() {
+                    if self.clue[P(y, x)] == HOLE {
+                        clue[P(y, x)] = HOLE;
+                    }
+                }
+            }
+            for &pos in clues {
+                clue[pos] = self.clue[pos];
+            }
+            let mut field = Field::new(&clue, self.dic);
+            field.check_all_cell();
+            field.inconsistent()
+        };
+        if !is_conflicting(&clues) {
+            return None;
+        }
+        Some(minimize_conflict(&clues, is_conflicting))
+    }
     pub fn fully_solved(&self) -> bool {
         self.grid_loop.fully_solved()
     }
+    pub fn num_decided_edges(&self) -> i32 {
+        self.grid_loop.num_decided_edges()
+    }
     pub fn check_all_cell(&mut self) {
         let height = self.height();
         let width = self.width();
@@ -42,7 +120,7 @@ impl<'a> Field<'a> {
             for x in 0..width {
                 let pos = P(y, x);
                 let clue = handle.get_clue(pos);
-                if clue != NO_CLUE {
+                if clue != NO_CLUE && clue != HOLE {
                     handle.inspect_technique(LP::of_cell(pos));
                     GridLoop::check(&mut *handle, LP::of_cell(pos));
                 }
@@ -55,7 +133,7 @@ impl<'a> Field<'a> {
     pub fn add_clue(&mut self, pos: P, clue: Clue) {
         if self.clue[pos] != NO_CLUE {
             if self.clue[pos] != clue {
-                self.grid_loop.set_inconsistent();
+                self.grid_loop.set_contradiction("different clues in a cell", LP::of_cell(pos));
             }
         } else {
             self.clue[pos] = clue;
@@ -150,7 +228,7 @@ impl<'a> GridLoopField for Field<'a> {
     fn inspect(&mut self, pos: LP) {
         if pos.is_cell() {
             let clue = self.clue[pos.as_cell()];
-            if clue == NO_CLUE || clue == Clue(0) {
+            if clue == NO_CLUE || clue == HOLE || clue == Clue(0) {
                 return;
             }
 
@@ -169,7 +247,7 @@ impl<'a> GridLoopField for Field<'a> {
 
             let res = self.dic.consult_raw(clue, neighbors_code);
             if res == DICTIONARY_INCONSISTENT {
-                self.grid_loop.set_inconsistent();
+                self.grid_loop.set_contradiction("wrong number of lines around a clue", pos);
                 return;
             }
             let mut res = res;
@@ -188,10 +266,46 @@ impl<'a> GridLoopField for Field<'a> {
     }
 }
 
+/// Runs `check_all_cell` starting from the lines and crosses of the player, which are given
+/// in `marks` in the same way as the givens of `Field::with_givens`.
+///
+/// If the marks are found to be contradictory, returns a minimal set of the marked edges which
+/// contradict together instead. The set is empty if the problem itself is contradictory.
+pub fn solve_from<'a>(
+    clue: &Grid<Clue>,
+    marks: &Grid<Edge>,
+    dic: &'a Dictionary,
+) -> Result<Field<'a>, Vec<LP>> {
+    let mut decided = vec![];
+    for y in 0..marks.height() {
+        for x in 0..marks.width() {
+            if marks[LP(y, x)] != Edge::Undecided {
+                decided.push(LP(y, x));
+            }
+        }
+    }
+    let solve = |decided: &[LP]| {
+        let mut givens = Grid::new(marks.height(), marks.width(), Edge::Undecided);
+        for &pos in decided {
+            givens[pos] = marks[pos];
+        }
+        let mut field = Field::with_givens(clue, &givens, dic);
+        field.check_all_cell();
+        field
+    };
+
+    let field = solve(&decided);
+    if !field.inconsistent() {
+        return Ok(field);
+    }
+    Err(minimize_conflict(&decided, |decided| {
+        solve(decided).inconsistent()
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use common;
 
     fn run_problem_test(dic: &Dictionary, input: &[&str], fully_solved: bool) {
         let height = (input.len() / 2) as i32;
@@ -327,4 +441,82 @@ mod tests {
             false,
         );
     }
+
+    #[test]
+    fn test_holes_and_givens() {
+        let dic = Dictionary::complete();
+
+        let mut src = "
+3 3
++ +-+ +
+ # . .
++ + + +
+ . 1 .
++ + + +
+ . . #
++ + + +
+"
+        .as_bytes();
+        let (clue, givens) = read_penciloid_problem_with_givens(&mut src).unwrap();
+        assert_eq!(clue[P(0, 0)], HOLE);
+        assert_eq!(givens[LP(0, 3)], Edge::Line);
+
+        let field = Field::new(&clue, &dic);
+        assert_eq!(field.get_edge(LP(0, 1)), Edge::Blank);
+        assert_eq!(field.get_edge(LP(1, 0)), Edge::Blank);
+        assert_eq!(field.get_edge(LP(6, 5)), Edge::Blank);
+        assert_eq!(field.get_edge(LP(5, 6)), Edge::Blank);
+        assert_eq!(field.get_edge(LP(1, 2)), Edge::Undecided);
+
+        let mut field = Field::with_givens(&clue, &givens, &dic);
+        field.check_all_cell();
+        assert!(!field.inconsistent());
+        assert_eq!(field.get_edge(LP(0, 3)), Edge::Line);
+        // The vertex at the top-left of (0, 1) has only one more undecided edge
+        assert_eq!(field.get_edge(LP(1, 2)), Edge::Line);
+
+        let mut givens = Grid::new(7, 7, Edge::Undecided);
+        givens[LP(0, 1)] = Edge::Line;
+        let field = Field::with_givens(&clue, &givens, &dic);
+        assert!(field.inconsistent());
+    }
+
+    #[test]
+    fn test_solve_from() {
+        let dic = Dictionary::complete();
+        let mut clue = Grid::new(1, 3, NO_CLUE);
+        clue[P(0, 0)] = Clue(3);
+        clue[P(0, 2)] = Clue(3);
+
+        let mut marks = Grid::new(3, 7, Edge::Undecided);
+        marks[LP(0, 3)] = Edge::Line;
+        let field = solve_from(&clue, &marks, &dic).unwrap();
+        assert!(field.fully_solved());
+
+        marks[LP(2, 3)] = Edge::Blank;
+        marks[LP(1, 6)] = Edge::Line;
+        assert_eq!(solve_from(&clue, &marks, &dic).err(), Some(vec![LP(2, 3)]));
+    }
+
+    #[test]
+    fn test_explain_inconsistency() {
+        let dic = Dictionary::complete();
+        let mut clue = Grid::new(2, 3, NO_CLUE);
+        clue[P(0, 0)] = Clue(0);
+        clue[P(0, 1)] = Clue(3);
+        clue[P(1, 2)] = Clue(2);
+
+        let mut field = Field::new(&clue, &dic);
+        field.check_all_cell();
+        assert!(field.inconsistent());
+        assert!(field.contradiction().is_some());
+        assert_eq!(field.explain_inconsistency(), Some(vec![P(0, 0), P(0, 1)]));
+
+        clue[P(0, 0)] = NO_CLUE;
+        let mut field = Field::new(&clue, &dic);
+        field.check_all_cell();
+        assert!(!field.inconsistent());
+        assert_eq!(field.contradiction(), None);
+        assert_eq!(field.explain_inconsistency(), None);
+    }
 }
\ No newline at end of file
@@ -1,7 +1,8 @@
-use super::super::{Grid, Symmetry, D, LP, P};
+use super::super::{minimize_conflict, Grid, Symmetry, D, LP, P};
 use super::*;
 use crate::grid_loop::{Edge, GridLoop, GridLoopField};
 
+use crate::StableRng;
 use rand::Rng;
 use crate::common::FOUR_NEIGHBOURS;
 
@@ -42,7 +43,7 @@ pub fn generate<R: Rng>(
             }
         }
 
-        rng.shuffle(&mut pos_cand);
+        rng.stable_shuffle(&mut pos_cand);
 
         let mut interpos_common = last_field.clone();
         let mut pos_with_clue = vec![];
@@ -67,7 +68,7 @@ pub fn generate<R: Rng>(
                 }
             }
 
-            rng.shuffle(&mut new_clue_cand);
+            rng.stable_shuffle(&mut new_clue_cand);
 
             let mut common;
             if prev_clue == NO_CLUE {
@@ -117,8 +118,7 @@ pub fn generate<R: Rng>(
                     - count_prohibited_patterns(has_clue, &field, &current_problem) * 10;
 
                 if prev_score >= current_score {
-                    if !(rng.gen::<f64>()
-                        < ((current_score - prev_score) as f64 / temperature).exp())
+                    if rng.stable_f64() >= ((current_score - prev_score) as f64 / temperature).exp()
                     {
                         continue;
                     }
@@ -253,6 +253,26 @@ fn count_prohibited_patterns(has_clue: &Grid<bool>, field: &Field, problem: &Gri
     ret
 }
 
+/// Generates a problem with a unique solution by repeatedly calling `generate_placement` and
+/// `generate`. Returns `None` if no problem is found within `max_trials` placements.
+pub fn generate_problem<R: Rng>(
+    height: i32,
+    width: i32,
+    num_clues: i32,
+    symmetry: Symmetry,
+    dic: &Dictionary,
+    max_trials: usize,
+    rng: &mut R,
+) -> Option<Grid<Clue>> {
+    for _ in 0..max_trials {
+        let placement = generate_placement(height, width, num_clues, symmetry, rng);
+        if let Some(problem) = generate(&placement, dic, rng) {
+            return Some(problem);
+        }
+    }
+    None
+}
+
 pub fn generate_placement<R: Rng>(
     height: i32,
     width: i32,
@@ -261,11 +281,6 @@ pub fn generate_placement<R: Rng>(
     rng: &mut R,
 ) -> Grid<bool> {
     let mut num_clues = num_clues;
-    let mut symmetry = symmetry;
-
-    symmetry.dyad |= symmetry.tetrad;
-    symmetry.tetrad &= height == width;
-
     let mut grp_ids = Grid::new(height, width, false);
 
     let mut clue_positions: Vec<Vec<P>> = vec![];
@@ -273,8 +288,10 @@ pub fn generate_placement<R: Rng>(
     for y in 0..height {
         for x in 0..width {
             if !grp_ids[P(y, x)] {
-                let mut sto = vec![];
-                update_grp(y, x, symmetry, &mut grp_ids, &mut sto);
+                let sto = symmetry.orbit(P(y, x), height, width);
+                for &p in &sto {
+                    grp_ids[p] = true;
+                }
                 clue_positions.push(sto);
             }
         }
@@ -307,7 +324,7 @@ pub fn generate_placement<R: Rng>(
             scores_total += pos_score;
         }
 
-        let mut thresh = rng.gen_range(0.0f64, scores_total);
+        let mut thresh = rng.stable_f64() * scores_total;
         for i in 0..clue_positions.len() {
             if thresh < scores[i] {
                 for &c in &(clue_positions[i]) {
@@ -325,30 +342,58 @@ pub fn generate_placement<R: Rng>(
     ret
 }
 
-fn update_grp(y: i32, x: i32, symmetry: Symmetry, grp_ids: &mut Grid<bool>, sto: &mut Vec<P>) {
-    if grp_ids[P(y, x)] {
-        return;
-    }
-    grp_ids[P(y, x)] = true;
-    sto.push(P(y, x));
-
-    if symmetry.tetrad {
-        update_grp(grp_ids.height() - 1 - x, y, symmetry, grp_ids, sto);
-    } else if symmetry.dyad {
-        update_grp(
-            grp_ids.height() - 1 - y,
-            grp_ids.width() - 1 - x,
-            symmetry,
-            grp_ids,
-            sto,
-        );
-    }
-    if symmetry.horizontal {
-        update_grp(grp_ids.height() - 1 - y, x, symmetry, grp_ids, sto);
+/// Removes clues of `problem` one after another, in a random order, as long as the problem
+/// has a unique answer. Clues which are mapped to each other by `symmetry` are removed together,
+/// so a symmetric placement of clues stays symmetric.
+/// Returns `None` if `problem` does not have a unique answer in the first place.
+///
+/// The uniqueness is checked by `solve_sat` unless `check_all_cell` alone solves the problem,
+/// so the result may need more than the logical techniques of `Field` to be solved.
+pub fn minimize<R: Rng>(
+    problem: &Grid<Clue>,
+    symmetry: Symmetry,
+    dic: &Dictionary,
+    rng: &mut R,
+) -> Option<Grid<Clue>> {
+    let height = problem.height();
+    let width = problem.width();
+
+    let mut clues = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            if problem[P(y, x)] != NO_CLUE && problem[P(y, x)] != HOLE {
+                clues.push(P(y, x));
+            }
+        }
     }
-    if symmetry.vertical {
-        update_grp(y, grp_ids.width() - 1 - x, symmetry, grp_ids, sto);
+    let mut groups = symmetry.group_cells(&clues, height, width);
+    rng.stable_shuffle(&mut groups);
+
+    let problem_of = |groups: &[Vec<P>]| {
+        let mut ret = problem.clone();
+        for &pos in &clues {
+            ret[pos] = NO_CLUE;
+        }
+        for group in groups {
+            for &pos in group {
+                ret[pos] = problem[pos];
+            }
+        }
+        ret
+    };
+    let is_unique = |groups: &[Vec<P>]| {
+        let problem = problem_of(groups);
+        let mut field = Field::new(&problem, dic);
+        field.check_all_cell();
+        if field.inconsistent() {
+            return false;
+        }
+        field.fully_solved() || solve_sat(&problem, 2).len() == 1
+    };
+    if !is_unique(&groups) {
+        return None;
     }
+    Some(problem_of(&minimize_conflict(&groups, is_unique)))
 }
 
 #[cfg(test)]
@@ -357,7 +402,7 @@ mod tests {
     use rand;
 
     fn run_placement_test<R: Rng>(placement: Vec<Vec<bool>>, dic: &Dictionary, rng: &mut R) {
-        let placement = ::common::vec_to_grid(&placement);
+        let placement = crate::common::vec_to_grid(&placement);
         let mut succeeded = false;
 
         for _ in 0..10 {
@@ -435,4 +480,54 @@ mod tests {
             &mut rng,
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_generate_problem() {
+        let mut rng = crate::SeedRng::new(42);
+        let dic = Dictionary::shared();
+        let symmetry = Symmetry::from_names("dyad").unwrap();
+
+        let problem = generate_problem(6, 6, 14, symmetry, dic, 100, &mut rng).unwrap();
+        for y in 0..6 {
+            for x in 0..6 {
+                assert_eq!(
+                    problem[P(y, x)] == NO_CLUE,
+                    problem[P(5 - y, 5 - x)] == NO_CLUE
+                );
+            }
+        }
+
+        let mut field = Field::new(&problem, dic);
+        field.check_all_cell();
+        assert!(!field.inconsistent());
+        assert!(field.fully_solved());
+    }
+    #[test]
+    fn test_minimize() {
+        let mut rng = crate::SeedRng::new(42);
+        let dic = Dictionary::shared();
+        let symmetry = Symmetry::from_names("dyad").unwrap();
+
+        let problem = generate_problem(6, 6, 24, symmetry, dic, 100, &mut rng).unwrap();
+        let minimized = minimize(&problem, symmetry, dic, &mut rng).unwrap();
+
+        let mut n_clues = 0;
+        for y in 0..6 {
+            for x in 0..6 {
+                let clue = minimized[P(y, x)];
+                if clue != NO_CLUE {
+                    n_clues += 1;
+                    assert_eq!(clue, problem[P(y, x)]);
+                }
+                assert_eq!(clue == NO_CLUE, minimized[P(5 - y, 5 - x)] == NO_CLUE);
+            }
+        }
+        assert!(n_clues < 24);
+        assert_eq!(solve_sat(&minimized, 2).len(), 1);
+        assert!(minimize(&minimized, symmetry, dic, &mut rng).is_some());
+
+        let mut unsolvable = Grid::new(6, 6, NO_CLUE);
+        unsolvable[P(0, 0)] = Clue(2);
+        assert!(minimize(&unsolvable, symmetry, dic, &mut rng).is_none());
+    }
+}
@@ -1,47 +1,398 @@
-use std::io::BufRead;
+use std::io::{self, BufRead, Write};
 
-use crate::common::{Grid, P};
-use crate::io::{next_valid_line, ReadError};
+use crate::common::{Grid, LP, P};
+use crate::grid_loop::Edge;
+use crate::io::{
+    next_valid_line, read_pzprv3_borders, read_pzprv3_cells, read_pzprv3_header,
+    write_pzprv3_borders, write_pzprv3_cells, write_pzprv3_header, Pzprv3Header, ReadError,
+};
+use crate::url;
 
 use super::*;
 
+fn read_header<T: BufRead>(reader: &mut T, buffer: &mut String) -> Result<(i32, i32), ReadError> {
+    next_valid_line(reader, buffer)?;
+    let mut header = buffer.split(' ');
+    let height = header
+        .next()
+        .ok_or(ReadError::InvalidFormat)?
+        .trim()
+        .parse::<i32>()
+        .map_err(|_| ReadError::InvalidValue)?;
+    let width = header
+        .next()
+        .ok_or(ReadError::InvalidFormat)?
+        .trim()
+        .parse::<i32>()
+        .map_err(|_| ReadError::InvalidValue)?;
+
+    if height <= 0 || width <= 0 {
+        return Err(ReadError::InvalidValue);
+    }
+    Ok((height, width))
+}
+
+fn char_to_clue(c: char) -> Clue {
+    match c {
+        '0' | '1' | '2' | '3' => Clue((c as u8 - b'0') as i32),
+        '#' => HOLE,
+        _ => NO_CLUE,
+    }
+}
+
+fn clue_to_char(clue: Clue) -> char {
+    match clue {
+        NO_CLUE => '.',
+        HOLE => '#',
+        Clue(n) => std::char::from_digit(n as u32, 10).unwrap(),
+    }
+}
+
+/// Reads a problem in the penciloid format.
+/// A cell is a clue (`0`-`3`), a hole (`#`) or empty (any other character).
 pub fn read_penciloid_problem<T: BufRead>(reader: &mut T) -> Result<Grid<Clue>, ReadError> {
     let mut buffer = String::new();
+    let (height, width) = read_header(reader, &mut buffer)?;
 
-    let height;
-    let width;
+    let mut cells = vec![];
 
-    {
+    for _ in 0..height {
         next_valid_line(reader, &mut buffer)?;
-        let mut header = buffer.split(' ');
-        height = header
-            .next()
-            .ok_or(ReadError::InvalidFormat)?
-            .trim()
-            .parse::<i32>()
-            .map_err(|_| ReadError::InvalidValue)?;
-        width = header
-            .next()
-            .ok_or(ReadError::InvalidFormat)?
-            .trim()
-            .parse::<i32>()
-            .map_err(|_| ReadError::InvalidValue)?;
+        let mut row_iter = buffer.chars();
+
+        for _ in 0..width {
+            let c = row_iter.next().ok_or(ReadError::InvalidFormat)?;
+            cells.push(char_to_clue(c));
+        }
     }
 
     let mut ret = Grid::new(height, width, NO_CLUE);
+    for (i, clue) in cells.into_iter().enumerate() {
+        ret[i] = clue;
+    }
+
+    Ok(ret)
+}
 
-    for y in 0..height {
+pub fn write_penciloid_problem<T: Write>(writer: &mut T, problem: &Grid<Clue>) -> io::Result<()> {
+    writeln!(writer, "{} {}", problem.height(), problem.width())?;
+
+    for y in 0..problem.height() {
+        let row = (0..problem.width())
+            .map(|x| clue_to_char(problem[P(y, x)]))
+            .collect::<String>();
+        writeln!(writer, "{}", row)?;
+    }
+
+    Ok(())
+}
+
+/// Reads a problem with pre-decided edges.
+///
+/// After the header, the problem is given as `2 * height + 1` rows of the lattice, each of which
+/// has `2 * width + 1` characters. Cells are written as in `read_penciloid_problem`,
+/// `|` and `-` are given lines and `x` is a given blank. Other characters are ignored.
+/// The returned givens are indexed by `LP`.
+pub fn read_penciloid_problem_with_givens<T: BufRead>(
+    reader: &mut T,
+) -> Result<(Grid<Clue>, Grid<Edge>), ReadError> {
+    let mut buffer = String::new();
+    let (height, width) = read_header(reader, &mut buffer)?;
+
+    let mut rows = vec![];
+    for _ in 0..(2 * height + 1) {
         next_valid_line(reader, &mut buffer)?;
-        let mut row_iter = buffer.chars();
+        let row = buffer
+            .trim_end_matches(&['\n', '\r'][..])
+            .chars()
+            .collect::<Vec<_>>();
+        if row.len() > (2 * width + 1) as usize {
+            return Err(ReadError::InvalidFormat);
+        }
+        rows.push(row);
+    }
 
-        for x in 0..width {
-            let c = row_iter.next().ok_or(ReadError::InvalidFormat)?;
-            match c {
-                '0' | '1' | '2' | '3' => ret[P(y, x)] = Clue((c as u8 - '0' as u8) as i32),
-                _ => (),
+    let mut clue = Grid::new(height, width, NO_CLUE);
+    let mut givens = Grid::new(2 * height + 1, 2 * width + 1, Edge::Undecided);
+    for y in 0..(2 * height + 1) {
+        for x in 0..(2 * width + 1) {
+            // Trailing spaces may be omitted
+            let c = rows[y as usize].get(x as usize).cloned().unwrap_or(' ');
+            let pos = LP(y, x);
+            if pos.is_cell() {
+                clue[pos.as_cell()] = char_to_clue(c);
+            } else if y % 2 != x % 2 {
+                givens[pos] = match c {
+                    '|' | '-' => Edge::Line,
+                    'x' => Edge::Blank,
+                    _ => Edge::Undecided,
+                };
             }
         }
     }
 
-    Ok(ret)
-}
\ No newline at end of file
+    Ok((clue, givens))
+}
+
+pub fn write_penciloid_problem_with_givens<T: Write>(
+    writer: &mut T,
+    problem: &Grid<Clue>,
+    givens: &Grid<Edge>,
+) -> io::Result<()> {
+    writeln!(writer, "{} {}", problem.height(), problem.width())?;
+
+    for y in 0..(2 * problem.height() + 1) {
+        let row = (0..(2 * problem.width() + 1))
+            .map(|x| {
+                let pos = LP(y, x);
+                if pos.is_cell() {
+                    clue_to_char(problem[pos.as_cell()])
+                } else if y % 2 == 0 && x % 2 == 0 {
+                    '+'
+                } else {
+                    match givens[pos] {
+                        Edge::Line if y % 2 == 0 => '-',
+                        Edge::Line => '|',
+                        Edge::Blank => 'x',
+                        Edge::Undecided => ' ',
+                    }
+                }
+            })
+            .collect::<String>();
+        writeln!(writer, "{}", row)?;
+    }
+
+    Ok(())
+}
+
+/// Decodes a puzz.link URL of the form `.../slither/<width>/<height>/<body>`.
+/// Question mark clues are not supported.
+/// Holes are decoded, while given edges are ignored (see `parse_url_with_givens`).
+pub fn parse_url(url: &str) -> Option<Grid<Clue>> {
+    parse_url_with_givens(url).map(|(clue, _)| clue)
+}
+
+/// Decodes a puzz.link URL together with the extensions written by `encode_url_with_givens`.
+/// The format is described in `url::Slitherlink`.
+pub fn parse_url_with_givens(url: &str) -> Option<(Grid<Clue>, Grid<Edge>)> {
+    url::decode::<url::Slitherlink>(url)
+}
+
+/// Encodes `problem` as a puzz.link URL.
+pub fn encode_url(problem: &Grid<Clue>) -> String {
+    let givens = Grid::new(
+        2 * problem.height() + 1,
+        2 * problem.width() + 1,
+        Edge::Undecided,
+    );
+    encode_url_with_givens(problem, &givens)
+}
+
+/// Encodes `problem` and the edges given in `givens` (indexed by `LP`) as a puzz.link URL.
+/// Sections which would be empty are omitted, so that the URL of an ordinary problem
+/// is understood by puzz.link.
+pub fn encode_url_with_givens(problem: &Grid<Clue>, givens: &Grid<Edge>) -> String {
+    url::encode::<url::Slitherlink>(&(problem.clone(), givens.clone()))
+}
+
+/// Reads a problem in the pzprv3 format of `slither`.
+///
+/// The file has a cell block of clues (`0`-`3` or `.`), a cell block of background colors only
+/// if the version is 1 or later, and a border block of edges including the outer ones, in which
+/// a positive number is a line and `-1` is a blank. The edges are returned as the givens.
+pub fn read_pzprv3_problem<T: BufRead>(
+    reader: &mut T,
+) -> Result<(Grid<Clue>, Grid<Edge>), ReadError> {
+    let header = read_pzprv3_header(reader, "slither")?;
+    let (height, width) = (header.height, header.width);
+
+    let clue = read_pzprv3_cells(
+        reader,
+        height,
+        width,
+        |token: &str| match token {
+            "." => Ok(NO_CLUE),
+            "0" | "1" | "2" | "3" => Ok(Clue(token.parse::<i32>().unwrap())),
+            _ => Err(ReadError::InvalidValue),
+        },
+        NO_CLUE,
+    )?;
+    if header.version >= 1 {
+        read_pzprv3_cells(reader, height, width, |_| Ok(()), ())?;
+    }
+    let givens = read_pzprv3_borders(
+        reader,
+        height,
+        width,
+        true,
+        |token: &str| match token.parse::<i32>() {
+            Ok(-1) => Ok(Edge::Blank),
+            Ok(n) if n > 0 => Ok(Edge::Line),
+            Ok(_) => Ok(Edge::Undecided),
+            Err(_) => Err(ReadError::InvalidValue),
+        },
+        Edge::Undecided,
+    )?;
+
+    Ok((clue, givens))
+}
+
+/// Writes `problem` and `edges` (indexed by `LP`) in the pzprv3 format of `slither`, version 0.
+/// Holes cannot be written.
+pub fn write_pzprv3<T: Write>(
+    writer: &mut T,
+    problem: &Grid<Clue>,
+    edges: &Grid<Edge>,
+) -> io::Result<()> {
+    let (height, width) = (problem.height(), problem.width());
+    if (0..(height * width) as usize).any(|i| problem[i] == HOLE) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "holes are not supported in pzprv3",
+        ));
+    }
+
+    let header = Pzprv3Header {
+        version: 0,
+        height,
+        width,
+    };
+    write_pzprv3_header(writer, "slither", &header)?;
+    write_pzprv3_cells(writer, problem, |&c| match c {
+        Clue(n) if n >= 0 => n.to_string(),
+        _ => String::from("."),
+    })?;
+    write_pzprv3_borders(writer, edges, true, |&e| {
+        String::from(match e {
+            Edge::Line => "1",
+            Edge::Blank => "-1",
+            Edge::Undecided => "0",
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url() {
+        let mut src = "
+6 6
+.20..1
+.2...1
+3..3..
+..0..2
+3...3.
+3..32.
+"
+        .as_bytes();
+        let problem = read_penciloid_problem(&mut src).unwrap();
+        let url = encode_url(&problem);
+        assert_eq!(url, "https://puzz.link/p?slither/6/6/g2a6cg1ddha2dg8d37");
+        assert_eq!(parse_url(&url).unwrap(), problem);
+
+        let problem = parse_url("https://puzz.link/p?slither/3/3/dgcg0").unwrap();
+        assert_eq!(problem[P(0, 0)], Clue(3));
+        assert_eq!(problem[P(1, 1)], Clue(2));
+        assert_eq!(problem[P(2, 2)], Clue(0));
+        assert_eq!(problem[P(0, 1)], NO_CLUE);
+
+        let empty = Grid::new(5, 5, NO_CLUE);
+        assert_eq!(encode_url(&empty), "https://puzz.link/p?slither/5/5/zk");
+        assert_eq!(
+            parse_url("https://puzz.link/p?slither/5/5/zk").unwrap(),
+            empty
+        );
+    }
+
+    #[test]
+    fn test_holes_and_givens() {
+        let mut src = "
+2 3
++-+-+ +
+|# 3 .x
++ +x+-+
+ . 1 .|
++-+ +-+
+"
+        .as_bytes();
+        let (problem, givens) = read_penciloid_problem_with_givens(&mut src).unwrap();
+        assert_eq!(problem[P(0, 0)], HOLE);
+        assert_eq!(problem[P(0, 1)], Clue(3));
+        assert_eq!(problem[P(1, 1)], Clue(1));
+        assert_eq!(givens[LP(0, 1)], Edge::Line);
+        assert_eq!(givens[LP(1, 0)], Edge::Line);
+        assert_eq!(givens[LP(1, 6)], Edge::Blank);
+        assert_eq!(givens[LP(2, 3)], Edge::Blank);
+        assert_eq!(givens[LP(4, 3)], Edge::Undecided);
+
+        let mut dst = vec![];
+        write_penciloid_problem_with_givens(&mut dst, &problem, &givens).unwrap();
+        assert_eq!(
+            String::from_utf8(dst).unwrap(),
+            "2 3\n+-+-+ +\n|# 3 .x\n+ +x+-+\n . 1 .|\n+-+ +-+\n"
+        );
+
+        let url = encode_url_with_givens(&problem, &givens);
+        assert!(url.starts_with("https://puzz.link/p?slither/3/2/gd6/mg0/"));
+        assert_eq!(
+            parse_url_with_givens(&url).unwrap(),
+            (problem.clone(), givens)
+        );
+        assert_eq!(parse_url(&url).unwrap(), problem);
+
+        let mut src = "
+1 3
+#.2
+"
+        .as_bytes();
+        let problem = read_penciloid_problem(&mut src).unwrap();
+        let mut dst = vec![];
+        write_penciloid_problem(&mut dst, &problem).unwrap();
+        assert_eq!(String::from_utf8(dst).unwrap(), "1 3\n#.2\n");
+        let url = encode_url(&problem);
+        assert_eq!(url, "https://puzz.link/p?slither/3/1/h2/mg");
+        assert_eq!(parse_url(&url).unwrap(), problem);
+    }
+
+    #[test]
+    fn test_url_invalid() {
+        assert!(parse_url("https://puzz.link/p?slither/3/3/zz0").is_none());
+        assert!(parse_url("https://puzz.link/p?slither/3/3/f").is_none());
+        assert!(parse_url("https://puzz.link/p?slither/0/3/").is_none());
+        assert!(parse_url("slither/3").is_none());
+        assert!(parse_url("https://puzz.link/p?slither/3/1/h2/m").is_none());
+        assert!(parse_url("https://puzz.link/p?slither/3/1/h2/m1").is_none());
+        assert!(parse_url("https://puzz.link/p?slither/3/1/h2/m4").is_none());
+        assert!(parse_url("https://puzz.link/p?slither/3/1/h2/q00").is_none());
+        assert!(parse_url("https://puzz.link/p?slither/3/1/h2/l10/x10").is_none());
+    }
+
+    #[test]
+    fn test_pzprv3() {
+        let src = "pzprv3.1\nslither\n1\n2\n. 2 \n0 1 \n-1 1 0 \n1 -1 \n1 1 \nhistory:{}\n";
+        let (problem, givens) = read_pzprv3_problem(&mut src.as_bytes()).unwrap();
+        assert_eq!(problem[P(0, 0)], NO_CLUE);
+        assert_eq!(problem[P(0, 1)], Clue(2));
+        assert_eq!(givens[LP(1, 0)], Edge::Blank);
+        assert_eq!(givens[LP(1, 2)], Edge::Line);
+        assert_eq!(givens[LP(1, 4)], Edge::Undecided);
+        assert_eq!(givens[LP(0, 1)], Edge::Line);
+        assert_eq!(givens[LP(0, 3)], Edge::Blank);
+        assert_eq!(givens[LP(2, 3)], Edge::Line);
+
+        let mut dst = vec![];
+        write_pzprv3(&mut dst, &problem, &givens).unwrap();
+        assert_eq!(
+            String::from_utf8(dst).unwrap(),
+            "pzprv3\nslither\n1\n2\n. 2 \n-1 1 0 \n1 -1 \n1 1 \n"
+        );
+
+        let mut holes = problem.clone();
+        holes[P(0, 0)] = HOLE;
+        assert!(write_pzprv3(&mut vec![], &holes, &givens).is_err());
+        assert!(read_pzprv3_problem(&mut "pzprv3\nslither\n1\n1\n4 \n".as_bytes()).is_err());
+        assert!(read_pzprv3_problem(&mut "pzprv3.x\nslither\n1\n1\n".as_bytes()).is_err());
+    }
+}
@@ -1,13 +1,19 @@
 mod dictionary;
+mod dictionary_table;
 mod field;
 mod generator;
 mod io;
+mod sat;
 
 pub use self::dictionary::*;
 pub use self::field::*;
 pub use self::generator::*;
 pub use self::io::*;
+pub use self::sat::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Clue(pub i32);
-const NO_CLUE: Clue = Clue(-1);
\ No newline at end of file
+pub(crate) const NO_CLUE: Clue = Clue(-1);
+/// A masked cell, which is outside the playable area.
+/// Edges which do not touch any playable cell are always blank.
+pub const HOLE: Clue = Clue(-2);
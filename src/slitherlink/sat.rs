@@ -0,0 +1,116 @@
+use super::super::{Grid, LP, P};
+use super::*;
+use crate::common::FOUR_NEIGHBOURS;
+use crate::grid_loop::Edge;
+use crate::sat::Sat;
+
+/// Enumerates up to `limit` answers of `problem` with the SAT solver.
+///
+/// Unlike `Field`, which only applies logical techniques, this is a complete solver, so it can
+/// be used to check the uniqueness of an answer. Each answer is indexed in the same way as
+/// `Field::get_edge`, and every edge in it is decided.
+pub fn solve_sat(problem: &Grid<Clue>, limit: usize) -> Vec<Grid<Edge>> {
+    let height = problem.height();
+    let width = problem.width();
+    let is_hole = |p: P| !problem.is_valid_p(p) || problem[p] == HOLE;
+    let vertex_id = |y: i32, x: i32| ((y / 2) * (width + 1) + x / 2) as usize;
+
+    let mut sat = Sat::new();
+    let mut lit = Grid::new(height * 2 + 1, width * 2 + 1, None);
+    let mut edges = vec![];
+    let mut keys = vec![];
+    for y in 0..(height * 2 + 1) {
+        for x in 0..(width * 2 + 1) {
+            let (c1, c2, u, v) = match (y % 2, x % 2) {
+                (0, 1) => (
+                    P(y / 2 - 1, x / 2),
+                    P(y / 2, x / 2),
+                    vertex_id(y, x - 1),
+                    vertex_id(y, x + 1),
+                ),
+                (1, 0) => (
+                    P(y / 2, x / 2 - 1),
+                    P(y / 2, x / 2),
+                    vertex_id(y - 1, x),
+                    vertex_id(y + 1, x),
+                ),
+                _ => continue,
+            };
+            let l = sat.new_lit();
+            if is_hole(c1) && is_hole(c2) {
+                sat.add_clause(&[!l]);
+            }
+            lit[LP(y, x)] = Some(l);
+            edges.push((u, v, l));
+            keys.push(l);
+        }
+    }
+    for y in 0..height {
+        for x in 0..width {
+            let Clue(c) = problem[P(y, x)];
+            if c >= 0 {
+                let around = FOUR_NEIGHBOURS
+                    .iter()
+                    .map(|&d| lit[LP::of_cell(P(y, x)) + d].unwrap())
+                    .collect::<Vec<_>>();
+                sat.exactly_k(&around, c as usize);
+            }
+        }
+    }
+    sat.single_loop(((height + 1) * (width + 1)) as usize, &edges);
+
+    let mut ret = vec![];
+    while ret.len() < limit {
+        let model = match sat.solve() {
+            Some(model) => model,
+            None => break,
+        };
+        let mut answer = Grid::new(height * 2 + 1, width * 2 + 1, Edge::Undecided);
+        for y in 0..(height * 2 + 1) {
+            for x in 0..(width * 2 + 1) {
+                if let Some(l) = lit[LP(y, x)] {
+                    answer[LP(y, x)] = if model.value(l) {
+                        Edge::Line
+                    } else {
+                        Edge::Blank
+                    };
+                }
+            }
+        }
+        ret.push(answer);
+        sat.block(&model, &keys);
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_sat() {
+        let dic = Dictionary::shared();
+        let problem = parse_url("https://puzz.link/p?slither/5/5/06312dbbc130611").unwrap();
+        let answers = solve_sat(&problem, 2);
+        assert_eq!(answers.len(), 1);
+
+        let mut field = Field::new(&problem, dic);
+        field.check_all_cell();
+        assert!(field.fully_solved());
+        for y in 0..11 {
+            for x in 0..11 {
+                if y % 2 != x % 2 {
+                    assert_eq!(answers[0][LP(y, x)], field.get_edge(LP(y, x)));
+                }
+            }
+        }
+
+        // A loop in a 1x2 grid surrounds either cell or both of them
+        let mut problem = Grid::new(1, 2, NO_CLUE);
+        assert_eq!(solve_sat(&problem, 10).len(), 3);
+        problem[P(0, 1)] = HOLE;
+        assert_eq!(solve_sat(&problem, 10).len(), 1);
+        problem[P(0, 0)] = Clue(3);
+        assert_eq!(solve_sat(&problem, 10).len(), 0);
+    }
+}
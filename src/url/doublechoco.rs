@@ -0,0 +1,46 @@
+use super::*;
+use crate::doublechoco::{Clue, Color, NO_CLUE};
+
+/// Double Choco (`dbchoco`): a cell bitfield of black cells followed by clues in number16.
+pub struct DoubleChoco;
+
+impl Codec for DoubleChoco {
+    type Problem = (Grid<Color>, Grid<Clue>);
+
+    const GENRE: &'static str = "dbchoco";
+
+    fn size(problem: &Self::Problem) -> (i32, i32) {
+        (problem.0.height(), problem.0.width())
+    }
+    fn decode(height: i32, width: i32, body: &str, sections: &[&str]) -> Option<Self::Problem> {
+        if sections.iter().any(|s| !s.is_empty()) {
+            return None;
+        }
+        let (black, consumed) = decode_cell_bitfield(height, width, body)?;
+        let clues = decode_number16(&body[consumed..], (height * width) as usize)?;
+
+        let mut color = Grid::new(height, width, Color::White);
+        let mut clue = Grid::new(height, width, NO_CLUE);
+        for (i, c) in clues.into_iter().enumerate() {
+            if black[i] {
+                color[i] = Color::Black;
+            }
+            clue[i] = c.unwrap_or(NO_CLUE);
+        }
+        Some((color, clue))
+    }
+    fn encode(problem: &Self::Problem) -> (String, Vec<String>) {
+        let (color, clue) = problem;
+        let n_cells = (color.height() * color.width()) as usize;
+
+        let mut black = Grid::new(color.height(), color.width(), false);
+        for i in 0..n_cells {
+            black[i] = color[i] == Color::Black;
+        }
+        let clues = (0..n_cells).map(|i| clue[i]).collect::<Vec<_>>();
+        (
+            encode_cell_bitfield(&black) + &encode_number16(&clues),
+            vec![],
+        )
+    }
+}
@@ -0,0 +1,371 @@
+//! Encoding and decoding of puzz.link URLs.
+//!
+//! A URL has the form `https://puzz.link/p?<genre>/<width>/<height>/<body>`, optionally
+//! followed by `/`-separated sections. The header and the sections are handled by `decode`
+//! and `encode`, while each genre implements `Codec` for its body, mostly by combining the
+//! primitives in this module:
+//! - bitfields, which pack 5 bits into a base-32 digit with the first bit as the MSB;
+//! - number16, in which a clue is `0`-`f` (0-15), `-xx` (16-255) or `+xxx` (256-4095);
+//! - run-length skips `g`-`z`, which stand for 1-20 cells without a clue;
+//! - border bitfields and cell bitfields of a grid.
+//!
+//! Sections are our extension to puzz.link. Each of them is a one-character prefix followed
+//! by its content, and sections which would be empty are omitted so that the URL of an ordinary
+//! problem is understood by puzz.link.
+
+use crate::common::{Grid, LP};
+
+mod doublechoco;
+mod numberlink;
+mod slitherlink;
+
+pub use self::doublechoco::DoubleChoco;
+pub use self::numberlink::Numberlink;
+pub use self::slitherlink::Slitherlink;
+
+/// Grids larger than this in either direction are rejected by the decoders.
+pub const MAX_GRID_SIDE: i32 = 1000;
+
+/// The URL codec of a genre.
+pub trait Codec {
+    type Problem;
+
+    /// The genre name in URLs, such as `numlin`.
+    const GENRE: &'static str;
+
+    /// Returns the height and the width of `problem`.
+    fn size(problem: &Self::Problem) -> (i32, i32);
+    /// Decodes the body and the sections following it.
+    fn decode(height: i32, width: i32, body: &str, sections: &[&str]) -> Option<Self::Problem>;
+    /// Encodes `problem` into the body and the sections following it.
+    fn encode(problem: &Self::Problem) -> (String, Vec<String>);
+}
+
+/// Decodes a URL of the genre `C`.
+///
+/// If the genre name is not found in `url`, the last three `/`-separated tokens are taken as
+/// the width, the height and the body.
+pub fn decode<C: Codec>(url: &str) -> Option<C::Problem> {
+    let tokens = url.split('/').collect::<Vec<_>>();
+    let base = match tokens.iter().position(|t| t.ends_with(C::GENRE)) {
+        Some(i) => i + 1,
+        None if tokens.len() >= 3 => tokens.len() - 3,
+        None => return None,
+    };
+    if tokens.len() < base + 3 {
+        return None;
+    }
+    let width = tokens[base].parse::<i32>().ok()?;
+    let height = tokens[base + 1].parse::<i32>().ok()?;
+    if !(0 < width && width <= MAX_GRID_SIDE && 0 < height && height <= MAX_GRID_SIDE) {
+        return None;
+    }
+    C::decode(height, width, tokens[base + 2], &tokens[(base + 3)..])
+}
+
+/// Encodes `problem` as a URL of the genre `C`.
+pub fn encode<C: Codec>(problem: &C::Problem) -> String {
+    let (height, width) = C::size(problem);
+    let (body, sections) = C::encode(problem);
+    let mut ret = format!(
+        "https://puzz.link/p?{}/{}/{}/{}",
+        C::GENRE,
+        width,
+        height,
+        body
+    );
+    for section in &sections {
+        ret.push('/');
+        ret.push_str(section);
+    }
+    ret
+}
+
+/// Splits a section into its prefix and content. Returns `None` for an empty section.
+pub fn split_section(section: &str) -> Option<(char, &str)> {
+    let mut chars = section.chars();
+    let prefix = chars.next()?;
+    Some((prefix, chars.as_str()))
+}
+
+/// Returns the section consisting of `prefix` and the bitfield of `bits`,
+/// or `None` if no bit is set.
+pub fn bitfield_section(prefix: char, bits: &[bool]) -> Option<String> {
+    if bits.iter().any(|&b| b) {
+        Some(format!("{}{}", prefix, encode_bitfield(bits)))
+    } else {
+        None
+    }
+}
+
+/// Packs `bits` into base-32 digits, 5 bits per digit with the first bit as the MSB.
+pub fn encode_bitfield(bits: &[bool]) -> String {
+    bits.chunks(5)
+        .map(|chunk| {
+            let mut v = 0;
+            for i in 0..5 {
+                v = v * 2 + if chunk.get(i) == Some(&true) { 1 } else { 0 };
+            }
+            std::char::from_digit(v, 32).unwrap()
+        })
+        .collect()
+}
+
+/// Decodes a bitfield of `len` bits at the beginning of `s`.
+/// Returns the bits and the number of consumed characters. The padding bits are ignored.
+pub fn decode_bitfield_prefix(s: &str, len: usize) -> Option<(Vec<bool>, usize)> {
+    let n_digits = len.div_ceil(5);
+    let mut ret = Vec::with_capacity(n_digits * 5);
+    let mut chars = s.chars();
+    for _ in 0..n_digits {
+        let v = match chars.next()? {
+            c @ ('0'..='9' | 'a'..='v') => c.to_digit(32).unwrap(),
+            _ => return None,
+        };
+        for i in (0..5).rev() {
+            ret.push((v >> i) & 1 == 1);
+        }
+    }
+    ret.truncate(len);
+    Some((ret, n_digits))
+}
+
+/// Inverse of `encode_bitfield`.
+/// Returns `None` unless `s` consists of exactly enough digits and the padding bits are unset.
+pub fn decode_bitfield(s: &str, len: usize) -> Option<Vec<bool>> {
+    let (bits, consumed) = decode_bitfield_prefix(s, len)?;
+    if consumed != s.len() || encode_bitfield(&bits) != s {
+        return None;
+    }
+    Some(bits)
+}
+
+/// Returns the number of cells skipped by `c` (`g`-`z` for 1-20), if `c` is a skip.
+pub fn decode_skip(c: char) -> Option<usize> {
+    match c {
+        'g'..='z' => Some(c as usize - 'f' as usize),
+        _ => None,
+    }
+}
+
+/// Appends skips of `n` cells in total to `ret`.
+pub fn encode_skip(ret: &mut String, n: usize) {
+    for _ in 0..(n / 20) {
+        ret.push('z');
+    }
+    let rest = (n % 20) as u8;
+    if rest > 0 {
+        ret.push((b'f' + rest) as char);
+    }
+}
+
+/// Encodes clues in the number16 format. Non-positive values are cells without a clue.
+///
+/// Panics if a clue is 4096 or larger, which pzpr cannot represent either.
+pub fn encode_number16(clues: &[i32]) -> String {
+    let mut ret = String::new();
+    let mut skip = 0;
+    for &c in clues {
+        if c <= 0 {
+            skip += 1;
+            continue;
+        }
+        encode_skip(&mut ret, skip);
+        skip = 0;
+        assert!(c < 4096, "clue {} cannot be encoded", c);
+        if c < 16 {
+            ret.push(std::char::from_digit(c as u32, 16).unwrap());
+        } else if c < 256 {
+            ret.push_str(&format!("-{:02x}", c));
+        } else {
+            ret.push_str(&format!("+{:03x}", c));
+        }
+    }
+    encode_skip(&mut ret, skip);
+    ret
+}
+
+/// Decodes the whole of `s` in the number16 format into the clues of `n_cells` cells.
+/// Returns `None` if `s` is malformed or places a clue beyond the last cell.
+pub fn decode_number16(s: &str, n_cells: usize) -> Option<Vec<Option<i32>>> {
+    fn hex(c: Option<&char>) -> Option<i32> {
+        match c {
+            Some(&c @ ('0'..='9' | 'a'..='f')) => Some(c.to_digit(16).unwrap() as i32),
+            _ => None,
+        }
+    }
+    let s = s.chars().collect::<Vec<_>>();
+    let mut ret = vec![None; n_cells];
+    let mut pos = 0;
+    let mut idx = 0;
+    while idx < s.len() {
+        if let Some(n) = decode_skip(s[idx]) {
+            pos += n;
+            idx += 1;
+            continue;
+        }
+        let (start, n_digits) = match s[idx] {
+            '-' => (idx + 1, 2),
+            '+' => (idx + 1, 3),
+            _ => (idx, 1),
+        };
+        let mut val = 0;
+        for i in start..(start + n_digits) {
+            val = val * 16 + hex(s.get(i))?;
+        }
+        idx = start + n_digits;
+        if pos >= n_cells {
+            return None;
+        }
+        ret[pos] = Some(val);
+        pos += 1;
+    }
+    Some(ret)
+}
+
+/// Encodes the cells of `cells` in row-major order as a bitfield.
+pub fn encode_cell_bitfield(cells: &Grid<bool>) -> String {
+    let n_cells = (cells.height() * cells.width()) as usize;
+    let bits = (0..n_cells).map(|i| cells[i]).collect::<Vec<_>>();
+    encode_bitfield(&bits)
+}
+
+/// Decodes the bitfield of `encode_cell_bitfield` at the beginning of `s`.
+/// Returns the cells and the number of consumed characters.
+pub fn decode_cell_bitfield(height: i32, width: i32, s: &str) -> Option<(Grid<bool>, usize)> {
+    let (bits, consumed) = decode_bitfield_prefix(s, (height * width) as usize)?;
+    let mut ret = Grid::new(height, width, false);
+    for (i, &b) in bits.iter().enumerate() {
+        ret[i] = b;
+    }
+    Some((ret, consumed))
+}
+
+/// Encodes the borders between cells as a bitfield of the borders between horizontally
+/// adjacent cells followed by that of the borders between vertically adjacent cells.
+///
+/// `border` is of size `(2 * height - 1) * (2 * width - 1)`, in which `LP(2y, 2x)` is the cell
+/// `P(y, x)` (see `Regions`).
+pub fn encode_border_bitfield(border: &Grid<bool>) -> String {
+    let height = (border.height() + 1) / 2;
+    let width = (border.width() + 1) / 2;
+
+    let mut vertical = vec![];
+    for y in 0..height {
+        for x in 0..(width - 1) {
+            vertical.push(border[LP(y * 2, x * 2 + 1)]);
+        }
+    }
+    let mut horizontal = vec![];
+    for y in 0..(height - 1) {
+        for x in 0..width {
+            horizontal.push(border[LP(y * 2 + 1, x * 2)]);
+        }
+    }
+    encode_bitfield(&vertical) + &encode_bitfield(&horizontal)
+}
+
+/// Decodes the bitfield of `encode_border_bitfield` at the beginning of `s`.
+/// Returns the borders and the number of consumed characters.
+pub fn decode_border_bitfield(height: i32, width: i32, s: &str) -> Option<(Grid<bool>, usize)> {
+    if height <= 0 || width <= 0 {
+        return None;
+    }
+    let (vertical, len_vertical) = decode_bitfield_prefix(s, ((width - 1) * height) as usize)?;
+    let (horizontal, len_horizontal) =
+        decode_bitfield_prefix(s.get(len_vertical..)?, (width * (height - 1)) as usize)?;
+
+    let mut border = Grid::new(height * 2 - 1, width * 2 - 1, false);
+    for y in 0..height {
+        for x in 0..(width - 1) {
+            border[LP(y * 2, x * 2 + 1)] = vertical[(y * (width - 1) + x) as usize];
+        }
+    }
+    for y in 0..(height - 1) {
+        for x in 0..width {
+            border[LP(y * 2 + 1, x * 2)] = horizontal[(y * width + x) as usize];
+        }
+    }
+    Some((border, len_vertical + len_horizontal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitfield() {
+        let bits = [true, false, true, true, false, false, true];
+        assert_eq!(encode_bitfield(&bits), "m8");
+        assert_eq!(decode_bitfield("m8", 7), Some(bits.to_vec()));
+        assert_eq!(decode_bitfield_prefix("m8xyz", 7), Some((bits.to_vec(), 2)));
+        assert_eq!(decode_bitfield_prefix("m9", 7), Some((bits.to_vec(), 2)));
+
+        assert!(decode_bitfield("m9", 7).is_none());
+        assert!(decode_bitfield("m8", 5).is_none());
+        assert!(decode_bitfield("m", 7).is_none());
+        assert!(decode_bitfield("mw", 7).is_none());
+        assert_eq!(decode_bitfield("", 0), Some(vec![]));
+    }
+
+    #[test]
+    fn test_number16() {
+        let clues = [1, 0, 16, 2, -1, 0, 255, 256, 4095];
+        let encoded = encode_number16(&clues);
+        assert_eq!(encoded, "1g-102h-ff+100+fff");
+        assert_eq!(
+            decode_number16(&encoded, 9),
+            Some(vec![
+                Some(1),
+                None,
+                Some(16),
+                Some(2),
+                None,
+                None,
+                Some(255),
+                Some(256),
+                Some(4095)
+            ])
+        );
+
+        let mut empty = String::new();
+        encode_skip(&mut empty, 45);
+        assert_eq!(empty, "zzk");
+        assert_eq!(encode_number16(&[0; 45]), "zzk");
+        assert_eq!(decode_number16("zzk", 45), Some(vec![None; 45]));
+        assert_eq!(decode_skip('g'), Some(1));
+        assert_eq!(decode_skip('f'), None);
+
+        for s in &[
+            "11",
+            "G",
+            "-f",
+            "-fffffffff",
+            "z1",
+            "-g0",
+            "+ff",
+            "+fg0",
+            "+",
+        ] {
+            assert!(decode_number16(s, 1).is_none(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_grid_bitfields() {
+        let mut cells = Grid::new(2, 3, false);
+        cells[1] = true;
+        cells[5] = true;
+        assert_eq!(encode_cell_bitfield(&cells), "8g");
+        assert_eq!(decode_cell_bitfield(2, 3, "8gg"), Some((cells, 2)));
+
+        // vertical borders: 1001(0), horizontal borders: 010(00)
+        let mut border = Grid::new(3, 5, false);
+        border[LP(0, 1)] = true;
+        border[LP(2, 3)] = true;
+        border[LP(1, 2)] = true;
+        assert_eq!(encode_border_bitfield(&border), "i8");
+        assert_eq!(decode_border_bitfield(2, 3, "i8g"), Some((border, 2)));
+        assert!(decode_border_bitfield(2, 3, "i").is_none());
+    }
+}
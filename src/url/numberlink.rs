@@ -0,0 +1,59 @@
+use super::*;
+use crate::numberlink::{Clue, BRIDGE, NO_CLUE, UNUSED};
+
+/// Numberlink (`numlin`): clues in number16.
+///
+/// Walls and bridges are written in the sections `m` and `b` respectively,
+/// each of which is a bitfield over cells in row-major order.
+pub struct Numberlink;
+
+const SECTIONS: [(char, Clue); 2] = [('m', UNUSED), ('b', BRIDGE)];
+
+impl Codec for Numberlink {
+    type Problem = Grid<Clue>;
+
+    const GENRE: &'static str = "numlin";
+
+    fn size(problem: &Self::Problem) -> (i32, i32) {
+        (problem.height(), problem.width())
+    }
+    fn decode(height: i32, width: i32, body: &str, sections: &[&str]) -> Option<Self::Problem> {
+        let n_cells = (height * width) as usize;
+        let mut ret = Grid::new(height, width, NO_CLUE);
+        for (i, c) in decode_number16(body, n_cells)?.into_iter().enumerate() {
+            ret[i] = Clue(c.unwrap_or(0));
+        }
+
+        for section in sections {
+            let (prefix, content) = match split_section(section) {
+                Some(s) => s,
+                None => continue,
+            };
+            let &(_, value) = SECTIONS.iter().find(|&&(p, _)| p == prefix)?;
+            let bits = decode_bitfield(content, n_cells)?;
+            for (i, &b) in bits.iter().enumerate() {
+                if b {
+                    if ret[i] != NO_CLUE {
+                        return None;
+                    }
+                    ret[i] = value;
+                }
+            }
+        }
+        Some(ret)
+    }
+    fn encode(problem: &Self::Problem) -> (String, Vec<String>) {
+        let n_cells = (problem.height() * problem.width()) as usize;
+        let clues = (0..n_cells).map(|i| problem[i].0).collect::<Vec<_>>();
+        let sections = SECTIONS
+            .iter()
+            .filter_map(|&(prefix, value)| {
+                let bits = (0..n_cells)
+                    .map(|i| problem[i] == value)
+                    .collect::<Vec<_>>();
+                bitfield_section(prefix, &bits)
+            })
+            .collect();
+        (encode_number16(&clues), sections)
+    }
+}
@@ -0,0 +1,141 @@
+use super::*;
+use crate::grid_loop::Edge;
+use crate::slitherlink::{Clue, HOLE, NO_CLUE};
+
+/// Slitherlink (`slither`) with holes and given edges.
+///
+/// A clue is packed with up to two following empty cells (`0`-`4`, `5`-`9`, `a`-`e`) and runs
+/// of empty cells are skips. Question mark clues are not supported.
+///
+/// The sections are bitfields:
+/// - `m`: holes, over cells in row-major order;
+/// - `l`: given lines, over edges in row-major order of the lattice;
+/// - `x`: given blanks, in the same order as `l`.
+///
+/// Holes are written as empty cells in the body. Given edges are indexed by `LP`.
+pub struct Slitherlink;
+
+/// Edges of the lattice in the order used by the sections.
+fn edge_positions(height: i32, width: i32) -> Vec<LP> {
+    let mut ret = vec![];
+    for y in 0..(2 * height + 1) {
+        for x in 0..(2 * width + 1) {
+            if y % 2 != x % 2 {
+                ret.push(LP(y, x));
+            }
+        }
+    }
+    ret
+}
+
+impl Codec for Slitherlink {
+    type Problem = (Grid<Clue>, Grid<Edge>);
+
+    const GENRE: &'static str = "slither";
+
+    fn size(problem: &Self::Problem) -> (i32, i32) {
+        (problem.0.height(), problem.0.width())
+    }
+    fn decode(height: i32, width: i32, body: &str, sections: &[&str]) -> Option<Self::Problem> {
+        let mut ret = Grid::new(height, width, NO_CLUE);
+        let mut pos = 0;
+        for c in body.chars() {
+            let (clue, skip) = match c {
+                '0'..='4' => (Some(c as i32 - '0' as i32), 1),
+                '5'..='9' => (Some(c as i32 - '5' as i32), 2),
+                'a'..='e' => (Some(c as i32 - 'a' as i32), 3),
+                _ => (None, decode_skip(c)? as i32),
+            };
+            if let Some(n) = clue {
+                if pos >= height * width {
+                    return None;
+                }
+                ret[pos as usize] = Clue(n);
+            }
+            pos += skip;
+        }
+
+        let edges = edge_positions(height, width);
+        let mut givens = Grid::new(2 * height + 1, 2 * width + 1, Edge::Undecided);
+        for section in sections {
+            let (prefix, content) = match split_section(section) {
+                Some(s) => s,
+                None => continue,
+            };
+            match prefix {
+                'm' => {
+                    let bits = decode_bitfield(content, (height * width) as usize)?;
+                    for (i, &b) in bits.iter().enumerate() {
+                        if b {
+                            if ret[i] != NO_CLUE {
+                                return None;
+                            }
+                            ret[i] = HOLE;
+                        }
+                    }
+                }
+                'l' | 'x' => {
+                    let status = if prefix == 'l' {
+                        Edge::Line
+                    } else {
+                        Edge::Blank
+                    };
+                    let bits = decode_bitfield(content, edges.len())?;
+                    for (&pos, &b) in edges.iter().zip(bits.iter()) {
+                        if b {
+                            if givens[pos] != Edge::Undecided {
+                                return None;
+                            }
+                            givens[pos] = status;
+                        }
+                    }
+                }
+                _ => return None,
+            }
+        }
+
+        Some((ret, givens))
+    }
+    fn encode(problem: &Self::Problem) -> (String, Vec<String>) {
+        let (clue, givens) = problem;
+        let n_cells = (clue.height() * clue.width()) as usize;
+        let is_empty = |i: usize| i < n_cells && (clue[i] == NO_CLUE || clue[i] == HOLE);
+
+        let mut body = String::new();
+        let mut n_empty = 0;
+        let mut i = 0;
+        while i < n_cells {
+            if is_empty(i) {
+                n_empty += 1;
+                i += 1;
+                continue;
+            }
+            encode_skip(&mut body, n_empty);
+            n_empty = 0;
+            let (offset, skip) = if is_empty(i + 1) && is_empty(i + 2) {
+                (10, 2)
+            } else if is_empty(i + 1) {
+                (5, 1)
+            } else {
+                (0, 0)
+            };
+            body.push(std::char::from_digit((clue[i].0 + offset) as u32, 16).unwrap());
+            i += skip + 1;
+        }
+        encode_skip(&mut body, n_empty);
+
+        let holes = (0..n_cells).map(|i| clue[i] == HOLE).collect::<Vec<_>>();
+        let edges = edge_positions(clue.height(), clue.width());
+        let mut sections = vec![];
+        sections.extend(bitfield_section('m', &holes));
+        for &(prefix, status) in &[('l', Edge::Line), ('x', Edge::Blank)] {
+            let bits = edges
+                .iter()
+                .map(|&pos| givens[pos] == status)
+                .collect::<Vec<_>>();
+            sections.extend(bitfield_section(prefix, &bits));
+        }
+
+        (body, sections)
+    }
+}
@@ -0,0 +1,317 @@
+//! Cross-validation of the rule-based solvers against the SAT backends.
+//!
+//! Random problems are made with the generators (and by breaking the generated problems),
+//! then solved by both kinds of solvers. The answers must agree, and everything a rule-based
+//! solver decides by propagation alone must hold in every answer found by the SAT solver.
+//! A failure points to a bug in hand-written propagation such as `prune_cut` in Numberlink
+//! or the rules on connected components in Double Choco.
+
+use rand::Rng;
+
+use puzz_sqr::common::{Grid, Symmetry, LP, P};
+use puzz_sqr::doublechoco::{self, Border, Color};
+use puzz_sqr::grid_loop::Edge;
+use puzz_sqr::{logic, numberlink, slitherlink, SeedRng};
+
+/// The number of answers enumerated by each solver.
+const LIMIT: usize = 3;
+
+/// Asserts that two solvers found the same answers. If `LIMIT` answers are found,
+/// only the numbers are compared since the solvers may stop at different ones.
+fn assert_same_answers<T: PartialEq + std::fmt::Debug>(
+    context: &str,
+    expected: &[T],
+    actual: &[T],
+) {
+    assert_eq!(
+        expected.len(),
+        actual.len(),
+        "{}: numbers of answers differ",
+        context
+    );
+    if actual.len() < LIMIT {
+        for answer in actual {
+            assert!(
+                expected.contains(answer),
+                "{}: unexpected answer {:?}",
+                context,
+                answer
+            );
+        }
+    }
+}
+
+fn numberlink_lines(answer: &numberlink::LinePlacement) -> Vec<LP> {
+    let mut ret = vec![];
+    for y in 0..(answer.height() * 2 - 1) {
+        for x in 0..(answer.width() * 2 - 1) {
+            if y % 2 != x % 2 && answer.get(LP(y, x)) {
+                ret.push(LP(y, x));
+            }
+        }
+    }
+    ret
+}
+
+fn check_numberlink(context: &str, problem: &Grid<numberlink::Clue>) {
+    let sat = numberlink::solve_sat(problem, true, LIMIT)
+        .iter()
+        .map(numberlink_lines)
+        .collect::<Vec<_>>();
+    let opt = numberlink::SolverOption {
+        limit: Some(LIMIT),
+        every_cell_used: true,
+        terminate_on_not_fully_filled: false,
+    };
+    let rule = numberlink::solve2(problem, &opt)
+        .answers
+        .iter()
+        .map(numberlink_lines)
+        .collect::<Vec<_>>();
+    assert_same_answers(context, &sat, &rule);
+
+    // `solve2` enumerates only the canonical answers if unused cells are allowed
+    let sat_any = numberlink::solve_sat(problem, false, LIMIT)
+        .iter()
+        .map(numberlink_lines)
+        .collect::<Vec<_>>();
+    let opt = numberlink::SolverOption {
+        every_cell_used: false,
+        ..opt
+    };
+    let rule_any = numberlink::solve2(problem, &opt);
+    assert_eq!(sat_any.is_empty(), rule_any.len() == 0, "{}", context);
+    if sat_any.len() < LIMIT {
+        for answer in &rule_any.answers {
+            assert!(sat_any.contains(&numberlink_lines(answer)), "{}", context);
+        }
+    }
+
+    // The encoding in `logic` finds an answer using every cell
+    let mut field = vec![vec![0; problem.width() as usize]; problem.height() as usize];
+    for y in 0..problem.height() {
+        for x in 0..problem.width() {
+            field[y as usize][x as usize] = problem[P(y, x)].0.max(0) as usize;
+        }
+    }
+    let logic_answer = logic::solve_numberlink(&field);
+    assert_eq!(logic_answer.is_some(), !sat.is_empty(), "{}", context);
+    if let Some(arcs) = logic_answer {
+        let mut lines = vec![];
+        for &((y1, x1), (y2, x2)) in &arcs {
+            let lp = LP((y1 + y2) as i32, (x1 + x2) as i32);
+            if !lines.contains(&lp) {
+                lines.push(lp);
+            }
+        }
+        lines.sort_by_key(|&LP(y, x)| (y, x));
+        if sat.len() < LIMIT {
+            assert!(
+                sat.contains(&lines),
+                "{}: unexpected answer {:?}",
+                context,
+                lines
+            );
+        }
+    }
+}
+
+#[test]
+fn cross_check_numberlink() {
+    let (height, width) = (6, 6);
+    let mut generator = numberlink::PlacementGenerator::new(height, width);
+    let opt = numberlink::GeneratorOption {
+        chain_threshold: 3,
+        endpoint_constraint: None,
+        forbid_adjacent_clue: false,
+        symmetry: Symmetry::none(),
+        clue_limit: None,
+        prioritized_extension: false,
+    };
+    for seed in 0..10 {
+        let mut rng = SeedRng::new(seed);
+        let problem = match (0..100)
+            .filter_map(|_| generator.generate_and_test(&opt, &mut rng))
+            .next()
+        {
+            Some(problem) => problem,
+            None => continue,
+        };
+        check_numberlink(&format!("numberlink seed {}", seed), &problem);
+
+        // Removing a pair of numbers usually makes the answer ambiguous
+        let max_clue = (0..height)
+            .flat_map(|y| (0..width).map(move |x| P(y, x)))
+            .map(|p| problem[p].0)
+            .max()
+            .unwrap();
+        let removed = rng.gen_range(1, max_clue + 1);
+        let mut broken = problem.clone();
+        for y in 0..height {
+            for x in 0..width {
+                if broken[P(y, x)].0 == removed {
+                    broken[P(y, x)] = numberlink::NO_CLUE;
+                }
+            }
+        }
+        check_numberlink(
+            &format!("numberlink seed {} without {}", seed, removed),
+            &broken,
+        );
+    }
+}
+
+fn check_slitherlink(
+    context: &str,
+    problem: &Grid<slitherlink::Clue>,
+    dic: &slitherlink::Dictionary,
+) {
+    let answers = slitherlink::solve_sat(problem, LIMIT);
+    let mut field = slitherlink::Field::new(problem, dic);
+    field.check_all_cell();
+    if field.inconsistent() {
+        assert!(
+            answers.is_empty(),
+            "{}: answer to an inconsistent problem",
+            context
+        );
+        return;
+    }
+    if field.fully_solved() {
+        assert_eq!(
+            answers.len(),
+            1,
+            "{}: solved problem with more answers",
+            context
+        );
+    }
+    for answer in &answers {
+        for y in 0..(problem.height() * 2 + 1) {
+            for x in 0..(problem.width() * 2 + 1) {
+                let pos = LP(y, x);
+                if y % 2 != x % 2 && field.get_edge(pos) != Edge::Undecided {
+                    assert_eq!(field.get_edge(pos), answer[pos], "{}: {:?}", context, pos);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn cross_check_slitherlink() {
+    let (height, width) = (5, 5);
+    let dic = slitherlink::Dictionary::shared();
+    for seed in 0..10 {
+        let mut rng = SeedRng::new(seed);
+        let problem = match slitherlink::generate_problem(
+            height,
+            width,
+            10,
+            Symmetry::none(),
+            dic,
+            10,
+            &mut rng,
+        ) {
+            Some(problem) => problem,
+            None => continue,
+        };
+        check_slitherlink(&format!("slitherlink seed {}", seed), &problem, dic);
+
+        // Replacing or removing (-1) a clue usually breaks the problem
+        let pos = P(rng.gen_range(0, height), rng.gen_range(0, width));
+        let mut broken = problem.clone();
+        broken[pos] = slitherlink::Clue(rng.gen_range(-1, 4));
+        check_slitherlink(
+            &format!("slitherlink seed {} at {:?}", seed, pos),
+            &broken,
+            dic,
+        );
+    }
+}
+
+/// Colors the grid along a random tiling by dominoes, so that the problem has an answer
+/// unless the clues rule it out.
+fn random_domino_coloring<R: Rng>(height: i32, width: i32, rng: &mut R) -> Grid<Color> {
+    'retry: loop {
+        let mut color: Grid<Option<Color>> = Grid::new(height, width, None);
+        for y in 0..height {
+            for x in 0..width {
+                if color[P(y, x)].is_some() {
+                    continue;
+                }
+                let mut cand = vec![];
+                if x + 1 < width && color[P(y, x + 1)].is_none() {
+                    cand.push(P(y, x + 1));
+                }
+                if y + 1 < height {
+                    cand.push(P(y + 1, x));
+                }
+                if cand.is_empty() {
+                    continue 'retry;
+                }
+                let other = cand[rng.gen_range(0, cand.len())];
+                let black_first = rng.gen::<bool>();
+                color[P(y, x)] = Some(if black_first {
+                    Color::Black
+                } else {
+                    Color::White
+                });
+                color[other] = Some(if black_first {
+                    Color::White
+                } else {
+                    Color::Black
+                });
+            }
+        }
+        let mut ret = Grid::new(height, width, Color::White);
+        for y in 0..height {
+            for x in 0..width {
+                ret[P(y, x)] = color[P(y, x)].unwrap();
+            }
+        }
+        return ret;
+    }
+}
+
+#[test]
+fn cross_check_doublechoco() {
+    let (height, width) = (4, 4);
+    for seed in 0..20 {
+        let context = format!("doublechoco seed {}", seed);
+        let mut rng = SeedRng::new(seed);
+        let color = random_domino_coloring(height, width, &mut rng);
+        let mut clue = Grid::new(height, width, doublechoco::NO_CLUE);
+        for _ in 0..rng.gen_range(0, 4) {
+            clue[P(rng.gen_range(0, height), rng.gen_range(0, width))] = rng.gen_range(1, 4);
+        }
+
+        let answers = doublechoco::solve_sat(&color, &clue, LIMIT);
+        assert_eq!(
+            doublechoco::count_solutions(&color, &clue, LIMIT),
+            answers.len(),
+            "{}: numbers of answers differ",
+            context
+        );
+
+        let mut field = doublechoco::Field::new(&color, &clue);
+        field.solve();
+        if field.inconsistent() {
+            assert!(
+                answers.is_empty(),
+                "{}: answer to an inconsistent problem",
+                context
+            );
+            continue;
+        }
+        for answer in &answers {
+            for y in 0..(height * 2 - 1) {
+                for x in 0..(width * 2 - 1) {
+                    let pos = LP(y, x);
+                    if y % 2 != x % 2 && field.border(pos) != Border::Undecided {
+                        assert_eq!(field.border(pos), answer[pos], "{}: {:?}", context, pos);
+                    }
+                }
+            }
+        }
+    }
+}